@@ -0,0 +1,193 @@
+use crate::solution::Answer;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Name of the answers file, kept next to the inputs it has the answers for.
+///
+pub const ANSWERS_FILE: &str = "answers.txt";
+
+/// The puzzle an answer belongs to: the day, the part and the input variant (`None` for the puzzle input itself).
+///
+pub type Key = (u32, u32, Option<String>);
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct AnswersError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "answers line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AnswersError {}
+
+/// The known answers, one per line as `<day> <part> <variant> <answer>`, with `-` as the variant of the puzzle input
+/// itself. Line breaks in an answer are written as `\n`. Blank lines and lines starting with `#` are ignored.
+///
+/// ```text
+/// 1 1 - 72017
+/// 10 1 example1 13140
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct Answers {
+    answers: BTreeMap<Key, String>,
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Answers, AnswersError> {
+        let mut answers = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| AnswersError {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let mut fields = line.splitn(4, ' ');
+            let day = match fields.next().map(str::parse) {
+                Some(Ok(day @ 1..=25)) => day,
+                _ => return Err(error("expected a day between 1 and 25")),
+            };
+            let part = match fields.next().map(str::parse) {
+                Some(Ok(part @ 1..=2)) => part,
+                _ => return Err(error("expected part 1 or 2")),
+            };
+            let variant = match fields.next() {
+                Some("-") => None,
+                Some(variant) if !variant.is_empty() => Some(variant.to_string()),
+                _ => return Err(error("expected a variant, or `-` for the puzzle input")),
+            };
+            let answer = match fields.next() {
+                Some(answer) if !answer.is_empty() => answer.replace("\\n", "\n"),
+                _ => return Err(error("expected an answer")),
+            };
+
+            if answers.insert((day, part, variant), answer).is_some() {
+                return Err(error("duplicate answer"));
+            }
+        }
+        Ok(Answers { answers })
+    }
+
+    pub fn get(&self, day: u32, part: u32, variant: Option<&str>) -> Option<&str> {
+        self.answers
+            .get(&(day, part, variant.map(str::to_string)))
+            .map(|s| s.as_str())
+    }
+
+    /// All the recorded answers in day, part, variant order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &str)> {
+        self.answers
+            .iter()
+            .map(|(key, answer)| (key, answer.as_str()))
+    }
+
+    /// The input variants that have answers for a day.
+    ///
+    pub fn variants(&self, day: u32) -> Vec<&str> {
+        let mut variants = self
+            .answers
+            .keys()
+            .filter(|(d, _, _)| *d == day)
+            .filter_map(|(_, _, variant)| variant.as_deref())
+            .collect::<Vec<_>>();
+        variants.sort();
+        variants.dedup();
+        variants
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String },
+    Missing,
+}
+
+/// Compares an answer with the recorded one. Text answers are compared without trailing whitespace so that the CRT
+/// drawings can be recorded without their final line break.
+///
+pub fn check(expected: Option<&str>, actual: &Answer) -> Verdict {
+    match expected {
+        None => Verdict::Missing,
+        Some(expected) if expected.trim_end() == actual.to_string().trim_end() => Verdict::Pass,
+        Some(expected) => Verdict::Fail {
+            expected: expected.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Answers, AnswersError, Verdict, ANSWERS_FILE};
+    use crate::input::{read_path, InputDir};
+    use crate::solution::Answer;
+
+    const ANSWERS: &str = "# day part variant answer
+1 1 - 72017
+10 1 example1 13140
+10 2 - ##..\\n#..#
+
+5 1 - MQTPGLLDN
+";
+
+    #[test]
+    fn test_parse() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(answers.get(1, 1, None), Some("72017"));
+        assert_eq!(answers.get(1, 2, None), None);
+        assert_eq!(answers.get(10, 1, Some("example1")), Some("13140"));
+        assert_eq!(answers.get(10, 2, None), Some("##..\n#..#"));
+        assert_eq!(answers.variants(10), vec!["example1"]);
+        assert_eq!(answers.iter().count(), 4);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Answers::parse("1 1 - 72017\n1 3 - 5\n").unwrap_err(),
+            AnswersError {
+                line: 2,
+                message: "expected part 1 or 2".to_string()
+            }
+        );
+        assert_eq!(
+            Answers::parse("1 1 - 72017\n1 1 - 72017\n")
+                .unwrap_err()
+                .message,
+            "duplicate answer"
+        );
+        assert_eq!(Answers::parse("26 1 - 5").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(Some("24000"), &Answer::Number(24000)), Verdict::Pass);
+        assert_eq!(
+            check(Some("24000"), &Answer::Number(45000)),
+            Verdict::Fail {
+                expected: "24000".to_string()
+            }
+        );
+        assert_eq!(
+            check(Some("##\n.#"), &Answer::from("##\n.#\n")),
+            Verdict::Pass
+        );
+        assert_eq!(check(None, &Answer::Number(1)), Verdict::Missing);
+    }
+
+    #[test]
+    fn test_answers_file() {
+        let path = InputDir::from_env().root().join(ANSWERS_FILE);
+        let answers = Answers::parse(&read_path(&path).unwrap()).unwrap();
+        assert_eq!(answers.get(1, 1, None), Some("72017"));
+        assert_eq!(answers.variants(10), vec!["example1"]);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::parse::ParseError;
+use crate::solution::{Answer, DynSolution};
+use std::fmt;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The stages of solving a day that are timed separately.
+///
+pub const STAGES: [&str; 3] = ["parse", "part1", "part2"];
+
+/// The fastest, median and slowest of a number of runs.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    /// Panics if there are no samples.
+    ///
+    pub fn from_samples(samples: &mut [Duration]) -> Stats {
+        samples.sort();
+        Stats {
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// How long one stage of a day took over a number of runs.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Timing {
+    pub day: u32,
+    pub stage: String,
+    pub runs: usize,
+    pub stats: Stats,
+}
+
+/// Times parsing the input and solving each part `runs` times, which must be at least once. Part two is left out
+/// for days that don't have one.
+///
+pub fn measure(
+    day: u32,
+    solution: &dyn DynSolution,
+    input: &str,
+    runs: usize,
+) -> Result<Vec<Timing>, ParseError> {
+    let timing = |stage: &str, mut samples: Vec<Duration>| Timing {
+        day,
+        stage: stage.to_string(),
+        runs,
+        stats: Stats::from_samples(&mut samples),
+    };
+
+    let mut samples = Vec::new();
+    let mut parsed = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        parsed = Some(black_box(solution.parse(black_box(input))?));
+        samples.push(start.elapsed());
+    }
+    let parsed = parsed.unwrap();
+    let mut timings = vec![timing(STAGES[0], samples)];
+
+    for (part, stage) in [(1, STAGES[1]), (2, STAGES[2])] {
+        let mut samples = Vec::new();
+        let mut answer = Answer::None;
+        for _ in 0..runs {
+            let start = Instant::now();
+            answer = black_box(match part {
+                1 => solution.part1(black_box(parsed.as_ref())),
+                _ => solution.part2(black_box(parsed.as_ref())),
+            });
+            samples.push(start.elapsed());
+        }
+        if answer != Answer::None {
+            timings.push(timing(stage, samples));
+        }
+    }
+    Ok(timings)
+}
+
+/// The file formats timings can be saved in.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    /// JSON for `.json` files, otherwise CSV.
+    ///
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Format::Json,
+            _ => Format::Csv,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "day,stage,runs,min_ns,median_ns,max_ns";
+
+/// The fields of a timing, in the order the CSV columns are in.
+///
+const FIELDS: [&str; 6] = ["day", "stage", "runs", "min_ns", "median_ns", "max_ns"];
+
+/// The timings as CSV or JSON, with the times in nanoseconds. JSON has one object per line.
+///
+pub fn write(timings: &[Timing], format: Format) -> String {
+    let mut buf = String::new();
+    if format == Format::Csv {
+        buf.push_str(CSV_HEADER);
+        buf.push('\n');
+    } else {
+        buf.push_str("[\n");
+    }
+    for (i, t) in timings.iter().enumerate() {
+        let (min, median, max) = (
+            t.stats.min.as_nanos(),
+            t.stats.median.as_nanos(),
+            t.stats.max.as_nanos(),
+        );
+        buf.push_str(&match format {
+            Format::Csv => format!("{},{},{},{},{},{}\n", t.day, t.stage, t.runs, min, median, max),
+            Format::Json => format!(
+                "  {{\"day\": {}, \"stage\": \"{}\", \"runs\": {}, \"min_ns\": {}, \"median_ns\": {}, \"max_ns\": {}}}{}\n",
+                t.day,
+                t.stage,
+                t.runs,
+                min,
+                median,
+                max,
+                if i + 1 < timings.len() { "," } else { "" }
+            ),
+        });
+    }
+    if format == Format::Json {
+        buf.push_str("]\n");
+    }
+    buf
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct BenchError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timings line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for BenchError {}
+
+/// Reads timings saved by `write`, in either format. JSON is only read the way `write` lays it out, with each object
+/// on a line of its own, but its fields are found by name and can be in any order.
+///
+pub fn read(text: &str) -> Result<Vec<Timing>, BenchError> {
+    let json = text.trim_start().starts_with('[');
+    let mut timings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line == CSV_HEADER || line == "[" || line == "]" {
+            continue;
+        }
+
+        let error = |message: &str| BenchError {
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let values = if json {
+            let fields = line
+                .trim_end_matches(',')
+                .strip_prefix('{')
+                .and_then(|line| line.strip_suffix('}'))
+                .ok_or_else(|| error("expected an object on one line"))?;
+            let mut values = [None; FIELDS.len()];
+            for field in fields.split(", ") {
+                let (name, value) = field
+                    .split_once(": ")
+                    .ok_or_else(|| error("expected `\"name\": value`"))?;
+                let name = name.trim_matches('"');
+                let i = FIELDS
+                    .iter()
+                    .position(|&field| field == name)
+                    .ok_or_else(|| error(&format!("unknown field `{}`", name)))?;
+                values[i] = Some(value.trim_matches('"'));
+            }
+            values
+                .into_iter()
+                .zip(FIELDS)
+                .map(|(value, name)| {
+                    value.ok_or_else(|| error(&format!("missing field `{}`", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            line.split(',').collect()
+        };
+
+        let [day, stage, runs, min, median, max] = values[..] else {
+            return Err(error(
+                "expected day, stage, runs, min_ns, median_ns and max_ns",
+            ));
+        };
+        let number = |s: &str| s.parse::<u64>().map_err(|_| error("expected a number"));
+        let nanos = |s: &str| number(s).map(Duration::from_nanos);
+        timings.push(Timing {
+            day: number(day)? as u32,
+            stage: stage.to_string(),
+            runs: number(runs)? as usize,
+            stats: Stats {
+                min: nanos(min)?,
+                median: nanos(median)?,
+                max: nanos(max)?,
+            },
+        });
+    }
+    Ok(timings)
+}
+
+/// A stage whose median time is slower than in the baseline by more than the threshold.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub struct Regression {
+    pub day: u32,
+    pub stage: String,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Regression {
+    /// How much slower it got, as a percentage of the baseline.
+    ///
+    pub fn percent(&self) -> f64 {
+        (self.current.as_secs_f64() / self.baseline.as_secs_f64() - 1.0) * 100.0
+    }
+}
+
+/// The stages that are more than `threshold` percent slower than in the baseline. Stages missing from the baseline
+/// aren't compared.
+///
+pub fn regressions(current: &[Timing], baseline: &[Timing], threshold: f64) -> Vec<Regression> {
+    current
+        .iter()
+        .filter_map(|timing| {
+            let base = baseline
+                .iter()
+                .find(|b| b.day == timing.day && b.stage == timing.stage)?;
+            let limit = base.stats.median.as_secs_f64() * (1.0 + threshold / 100.0);
+            (timing.stats.median.as_secs_f64() > limit).then(|| Regression {
+                day: timing.day,
+                stage: timing.stage.clone(),
+                baseline: base.stats.median,
+                current: timing.stats.median,
+            })
+        })
+        .collect()
+}
+
+/// A duration in the largest unit it has at least one of, to three decimal places; whole nanoseconds have none.
+///
+pub fn format_duration(d: Duration) -> String {
+    if d.as_nanos() < 1000 {
+        return format!("{}ns", d.as_nanos());
+    }
+    let nanos = d.as_nanos() as f64;
+    match nanos {
+        n if n < 1e6 => format!("{:.3}µs", n / 1e3),
+        n if n < 1e9 => format!("{:.3}ms", n / 1e6),
+        n => format!("{:.3}s", n / 1e9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_duration, measure, read, regressions, write, BenchError, Format, Stats, Timing,
+        STAGES,
+    };
+    use crate::solution;
+    use std::time::Duration;
+
+    fn timing(day: u32, stage: &str, median: u64) -> Timing {
+        Timing {
+            day,
+            stage: stage.to_string(),
+            runs: 3,
+            stats: Stats {
+                min: Duration::from_nanos(median / 2),
+                median: Duration::from_nanos(median),
+                max: Duration::from_nanos(median * 2),
+            },
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut samples = [5, 1, 3, 2, 4].map(Duration::from_millis);
+        let stats = Stats::from_samples(&mut samples);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.max, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_measure() {
+        let timings = measure(1, solution::get(1).unwrap(), "1000\n2000\n\n3000\n", 3).unwrap();
+        assert_eq!(
+            timings.iter().map(|t| t.stage.as_str()).collect::<Vec<_>>(),
+            STAGES
+        );
+        assert!(timings
+            .iter()
+            .all(|t| t.runs == 3 && t.stats.min <= t.stats.max));
+
+        // Day 25 has no part two to time.
+        let timings = measure(25, solution::get(25).unwrap(), "1=\n", 2).unwrap();
+        assert_eq!(timings.len(), 2);
+
+        assert!(measure(1, solution::get(1).unwrap(), "x\n", 1).is_err());
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let timings = vec![timing(1, "parse", 1500), timing(1, "part1", 2_000_000)];
+        for format in [Format::Csv, Format::Json] {
+            assert_eq!(read(&write(&timings, format)).unwrap(), timings);
+        }
+        assert_eq!(
+            write(&timings[..1], Format::Csv),
+            "day,stage,runs,min_ns,median_ns,max_ns\n1,parse,3,750,1500,3000\n"
+        );
+        assert_eq!(
+            write(&timings[..1], Format::Json),
+            "[\n  {\"day\": 1, \"stage\": \"parse\", \"runs\": 3, \"min_ns\": 750, \"median_ns\": 1500, \"max_ns\": 3000}\n]\n"
+        );
+        assert_eq!(
+            read("day,stage,runs,min_ns,median_ns,max_ns\n1,parse,3,x,1,1\n")
+                .unwrap_err()
+                .line,
+            2
+        );
+
+        let reordered = "[\n  {\"stage\": \"parse\", \"max_ns\": 3000, \"day\": 1, \"median_ns\": 1500, \"runs\": 3, \"min_ns\": 750}\n]\n";
+        assert_eq!(read(reordered).unwrap(), timings[..1]);
+        assert_eq!(
+            read(&reordered.replace("\"runs\"", "\"count\"")).unwrap_err(),
+            BenchError {
+                line: 2,
+                message: "unknown field `count`".to_string()
+            }
+        );
+        assert_eq!(
+            read(&reordered.replace(", \"runs\": 3", "")).unwrap_err(),
+            BenchError {
+                line: 2,
+                message: "missing field `runs`".to_string()
+            }
+        );
+        // Pretty printed across lines isn't read.
+        let pretty = "[\n  {\n    \"day\": 1,\n    \"stage\": \"parse\"\n  }\n]\n";
+        assert_eq!(read(pretty).unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_regressions() {
+        let baseline = vec![timing(1, "parse", 1000), timing(1, "part1", 1000)];
+        let current = vec![
+            timing(1, "parse", 1050),
+            timing(1, "part1", 1200),
+            timing(1, "part2", 5000),
+        ];
+        let res = regressions(&current, &baseline, 10.0);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].stage, "part1");
+        assert!((res[0].percent() - 20.0).abs() < 1e-9);
+        assert!(regressions(&current, &baseline, 25.0).is_empty());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0ns");
+        assert_eq!(format_duration(Duration::from_nanos(999)), "999ns");
+        assert_eq!(format_duration(Duration::from_nanos(1000)), "1.000µs");
+        assert_eq!(format_duration(Duration::from_nanos(12_345)), "12.345µs");
+        assert_eq!(format_duration(Duration::from_nanos(999_999)), "999.999µs");
+        assert_eq!(format_duration(Duration::from_micros(2_500)), "2.500ms");
+        assert_eq!(
+            format_duration(Duration::from_nanos(123_456_789)),
+            "123.457ms"
+        );
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.500s");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90.000s");
+    }
+}
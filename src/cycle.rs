@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A simulation whose state came back to one it was in before: it was first in it after `start` steps and is back
+/// in it every `length` steps from then on. `totals` has what the steps added up to after each number of steps, up
+/// to the first repeat.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+    totals: Vec<i64>,
+}
+
+impl Cycle {
+    /// What `n` steps add up to, going round the cycle as many times as it takes.
+    ///
+    pub fn total_after(&self, n: u64) -> i64 {
+        let start = self.start as u64;
+        if n <= start {
+            return self.totals[n as usize];
+        }
+        let gain = self.totals[self.start + self.length] - self.totals[self.start];
+        let (cycles, rest) = (
+            (n - start) / self.length as u64,
+            (n - start) % self.length as u64,
+        );
+        self.totals[self.start + rest as usize] + cycles as i64 * gain
+    }
+}
+
+/// Runs up to `limit` steps, stopping at the first state that repeats. `key` picks out what decides the rest of the
+/// simulation, which is what is compared, and `step` advances the state and returns what the step adds to the total.
+///
+fn run<S, K: Hash + Eq>(
+    mut state: S,
+    limit: u64,
+    mut key: impl FnMut(&S) -> K,
+    mut step: impl FnMut(&mut S) -> i64,
+) -> (Vec<i64>, Option<(usize, usize)>) {
+    let mut seen = HashMap::new();
+    let mut totals = vec![0];
+    for i in 0..limit as usize {
+        if let Some(start) = seen.insert(key(&state), i) {
+            return (totals, Some((start, i - start)));
+        }
+        totals.push(totals[i] + step(&mut state));
+    }
+    (totals, None)
+}
+
+/// The first cycle in a simulation, if a state repeats within `limit` steps. See `extrapolate` for `key` and `step`.
+///
+pub fn find<S, K: Hash + Eq>(
+    state: S,
+    limit: u64,
+    key: impl FnMut(&S) -> K,
+    step: impl FnMut(&mut S) -> i64,
+) -> Option<Cycle> {
+    let (totals, cycle) = run(state, limit, key, step);
+    cycle.map(|(start, length)| Cycle {
+        start,
+        length,
+        totals,
+    })
+}
+
+/// What `n` steps of a simulation add up to, skipping ahead once its state repeats, so `n` can be as large as
+/// `1_000_000_000_000`. `key` picks out what decides the rest of the simulation, such as the top of a tower rather
+/// than all of it, and `step` advances the state and returns what the step adds to the total.
+///
+pub fn extrapolate<S, K: Hash + Eq>(
+    state: S,
+    n: u64,
+    key: impl FnMut(&S) -> K,
+    step: impl FnMut(&mut S) -> i64,
+) -> i64 {
+    match run(state, n, key, step) {
+        (totals, Some((start, length))) => Cycle {
+            start,
+            length,
+            totals,
+        }
+        .total_after(n),
+        (totals, None) => totals[n as usize],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extrapolate, find};
+
+    /// Squaring modulo 100 from 3 goes 3, 9, 81, 61, 21, 41, 81, ...
+    ///
+    fn square(x: &mut u64) -> i64 {
+        *x = *x * *x % 100;
+        *x as i64
+    }
+
+    #[test]
+    fn test_find() {
+        let cycle = find(3, 100, |&x| x, square).unwrap();
+        assert_eq!((cycle.start, cycle.length), (2, 4));
+        assert_eq!(cycle.total_after(0), 0);
+        assert_eq!(cycle.total_after(3), 9 + 81 + 61);
+        assert_eq!(cycle.total_after(10), 9 + 81 + 2 * (61 + 21 + 41 + 81));
+        assert_eq!(find(3, 5, |&x| x, square), None);
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let brute_force = |n| {
+            let mut x = 3;
+            (0..n).map(|_| square(&mut x)).sum::<i64>()
+        };
+        for n in [0, 1, 2, 5, 6, 7, 100, 1001] {
+            assert_eq!(extrapolate(3, n, |&x| x, square), brute_force(n));
+        }
+        assert_eq!(
+            extrapolate(3, 1_000_000_000_000, |&x| x, square),
+            9 + 81 + 250_000_000_000 * 204 - 61 - 21 - 41 - 81 + (61 + 21)
+        );
+    }
+}
@@ -1,56 +1,354 @@
-use itertools::Itertools;
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ParseError};
+use crate::solution::{Answer, Solution};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::iter;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day1.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-fn elf_calories(input: impl Iterator<Item = String>) -> impl Iterator<Item = u64> {
-    input.chain(iter::once("".to_string())).batching(|it| {
-        let mut sum = 0;
-        loop {
-            match it.next() {
-                None => return None,
-                Some(str) if str.is_empty() => return Some(sum),
-                Some(calories) => sum += u64::from_str(calories.as_str()).unwrap(),
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// An elf and the calories of each item it carries. Elves are numbered from 1 in the order they appear in the input.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Elf {
+    pub index: usize,
+    pub items: Vec<u64>,
+    pub total: u64,
+}
+
+impl Elf {
+    fn new(index: usize, items: Vec<u64>) -> Elf {
+        let total = items.iter().sum();
+        Elf {
+            index,
+            items,
+            total,
+        }
+    }
+}
+
+/// The elves and their items. Elves are separated by blank lines.
+///
+pub fn parse_elves(input: &str) -> Result<Vec<Elf>, ParseError> {
+    let mut res = Vec::new();
+    let mut items = Vec::new();
+    for mut cursor in cursors(1, input) {
+        if cursor.is_empty() {
+            if !items.is_empty() {
+                res.push(Elf::new(res.len() + 1, std::mem::take(&mut items)));
             }
+        } else {
+            items.push(cursor.number()?);
+            cursor.end()?;
         }
-    })
+    }
+    if !items.is_empty() {
+        res.push(Elf::new(res.len() + 1, items));
+    }
+    Ok(res)
+}
+
+/// The total calories carried by each elf.
+///
+pub fn parse_input(input: &str) -> Result<Vec<u64>, ParseError> {
+    Ok(parse_elves(input)?.iter().map(|elf| elf.total).collect())
 }
 
-fn max_elf_calories(input: impl Iterator<Item = String>) -> u64 {
-    elf_calories(input).max().unwrap()
+pub fn max_elf_calories(elf_calories: &[u64]) -> u64 {
+    elf_calories.iter().copied().max().unwrap_or(0)
 }
 
 /// Rather than sorting the elf calories, uses a min-heap of 3 elements for an O(nlog3) operation (rather than O(nlogn))
 ///
-fn top_n_elf_calories(input: impl Iterator<Item = String>, n: usize) -> u64 {
-    let mut min_heap = BinaryHeap::with_capacity(n);
-    for (i, calories) in elf_calories(input).enumerate() {
-        match min_heap.peek() {
-            Some(Reverse((smallest_max, _i))) => {
-                if calories > *smallest_max {
-                    if min_heap.len() == n {
-                        min_heap.pop();
-                    }
-                    min_heap.push(Reverse((calories, i)));
-                }
+pub fn top_n_elf_calories(elf_calories: &[u64], n: usize) -> u64 {
+    let mut top = TopN::new(n);
+    for &calories in elf_calories {
+        top.push(calories);
+    }
+    top.sum()
+}
+
+/// The `n` largest totals seen so far, kept in a min-heap of at most `n` so that the smallest of them is the one to
+/// drop. Elves are numbered from 1 in the order they are pushed. An elf only displaces another carrying less, and
+/// of the elves carrying the smallest total the latest goes first.
+///
+#[derive(Clone, Debug)]
+pub struct TopN {
+    n: usize,
+    min_heap: BinaryHeap<Reverse<(u64, Reverse<usize>)>>,
+    elves: usize,
+}
+
+impl TopN {
+    pub fn new(n: usize) -> TopN {
+        TopN {
+            n,
+            min_heap: BinaryHeap::with_capacity(n + 1),
+            elves: 0,
+        }
+    }
+
+    pub fn push(&mut self, calories: u64) {
+        self.elves += 1;
+        if self.min_heap.len() < self.n {
+            self.min_heap.push(Reverse((calories, Reverse(self.elves))));
+        } else if let Some(Reverse((smallest_max, _i))) = self.min_heap.peek() {
+            if calories > *smallest_max {
+                self.min_heap.pop();
+                self.min_heap.push(Reverse((calories, Reverse(self.elves))));
             }
-            _ => min_heap.push(Reverse((calories, i))),
         }
     }
-    min_heap.iter().map(|&Reverse((v, _i))| v).sum()
+
+    /// How many elves have been pushed.
+    ///
+    pub fn elves(&self) -> usize {
+        self.elves
+    }
+
+    /// The elves kept as `(index, total)`, most first.
+    ///
+    pub fn top(&self) -> Vec<(usize, u64)> {
+        let mut top = self
+            .min_heap
+            .iter()
+            .map(|&Reverse((v, Reverse(i)))| (i, v))
+            .collect::<Vec<_>>();
+        top.sort_by_key(|&(i, v)| (Reverse(v), i));
+        top
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.min_heap.iter().map(|&Reverse((v, _i))| v).sum()
+    }
+}
+
+/// Why the elves couldn't be read from a stream.
+///
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "could not read the input: {}", error),
+            StreamError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Io(error) => Some(error),
+            StreamError::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// The `n` elves carrying the most in the input read from `reader`, a line at a time, so it takes the same memory
+/// however big the input is. `on_elf` is called with the top `n` so far as each elf's items end. Lines may end in
+/// `\r\n`, and any number of blank lines separate the elves or end the input.
+///
+pub fn stream_top_n(
+    reader: impl Read,
+    n: usize,
+    mut on_elf: impl FnMut(&TopN),
+) -> Result<TopN, StreamError> {
+    let mut reader = BufReader::new(reader);
+    let mut top = TopN::new(n);
+    let mut line = String::new();
+    let mut sum = None;
+    for number in 1.. {
+        line.clear();
+        let eof = reader.read_line(&mut line).map_err(StreamError::Io)? == 0;
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let mut cursor = Cursor::new(1, number, text.strip_suffix('\r').unwrap_or(text));
+        if eof || cursor.is_empty() {
+            if let Some(calories) = sum.take() {
+                top.push(calories);
+                on_elf(&top);
+            }
+            if eof {
+                break;
+            }
+        } else {
+            let calories: u64 = cursor.number().map_err(StreamError::Parse)?;
+            cursor.end().map_err(StreamError::Parse)?;
+            sum = Some(sum.unwrap_or(0) + calories);
+        }
+    }
+    Ok(top)
+}
+
+/// The elves ranked by the calories they carry, most first. Elves carrying the same share a rank, and among them the
+/// one earlier in the input comes first.
+///
+#[derive(Clone, Debug)]
+pub struct Leaderboard<'a> {
+    ranked: Vec<&'a Elf>,
+    /// The rank of each elf in `ranked`.
+    ranks: Vec<usize>,
+}
+
+impl<'a> Leaderboard<'a> {
+    pub fn new(elves: &'a [Elf]) -> Leaderboard<'a> {
+        let mut ranked = elves.iter().collect::<Vec<_>>();
+        ranked.sort_by_key(|elf| (Reverse(elf.total), elf.index));
+        let mut ranks = Vec::with_capacity(ranked.len());
+        for (i, elf) in ranked.iter().enumerate() {
+            match ranks.last() {
+                Some(&rank) if ranked[i - 1].total == elf.total => ranks.push(rank),
+                _ => ranks.push(i + 1),
+            }
+        }
+        Leaderboard { ranked, ranks }
+    }
+
+    pub fn ranked(&self) -> &[&'a Elf] {
+        &self.ranked
+    }
+
+    /// The rank of the elf with `index`: one more than the number of elves carrying more than it.
+    ///
+    pub fn rank(&self, index: usize) -> Option<usize> {
+        let i = self.ranked.iter().position(|elf| elf.index == index)?;
+        Some(self.ranks[i])
+    }
+
+    /// The `n` elves carrying the most, and any others carrying as much as the last of them.
+    ///
+    pub fn top(&self, n: usize) -> &[&'a Elf] {
+        if n == 0 || self.ranked.is_empty() {
+            return &[];
+        }
+        let last = self.ranked[n.min(self.ranked.len()) - 1];
+        let end = self.ranked.partition_point(|elf| elf.total >= last.total);
+        &self.ranked[..end]
+    }
+
+    /// The `n` elves carrying the least, and any others carrying as little as the first of them, still most first.
+    ///
+    pub fn bottom(&self, n: usize) -> &[&'a Elf] {
+        if n == 0 || self.ranked.is_empty() {
+            return &[];
+        }
+        let first = self.ranked[self.ranked.len().saturating_sub(n)];
+        let start = self.ranked.partition_point(|elf| elf.total > first.total);
+        &self.ranked[start..]
+    }
+
+    /// The groups of elves carrying the same calories as each other, most first.
+    ///
+    pub fn ties(&self) -> Vec<&[&'a Elf]> {
+        self.ranked
+            .chunk_by(|a, b| a.total == b.total)
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    /// The middle total, or the mean of the two middle totals for an even number of elves.
+    ///
+    pub fn median(&self) -> Option<f64> {
+        let n = self.ranked.len();
+        match n {
+            0 => None,
+            n if n % 2 == 1 => Some(self.ranked[n / 2].total as f64),
+            n => Some((self.ranked[n / 2 - 1].total + self.ranked[n / 2].total) as f64 / 2.0),
+        }
+    }
+
+    /// The smallest total that at least `p` percent of the elves carry no more than (the nearest-rank percentile).
+    /// `None` if there are no elves or `p` isn't between 0 and 100.
+    ///
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        if !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let n = self.ranked.len();
+        let k = ((p / 100.0 * n as f64).ceil() as usize).max(1);
+        Some(self.ranked.get(n.checked_sub(k)?)?.total)
+    }
+
+    /// A table of the top and bottom `n` elves, with their ranks, items and totals, and a line on the spread of the
+    /// totals below it.
+    ///
+    pub fn table(&self, n: usize) -> String {
+        let row = |i: usize| {
+            let elf = self.ranked[i];
+            format!(
+                "{:>4}  {:>4}  {:>5}  {:>8}\n",
+                self.ranks[i],
+                elf.index,
+                elf.items.len(),
+                elf.total
+            )
+        };
+        let n_elves = self.ranked.len();
+        let (top, bottom) = (self.top(n).len(), self.bottom(n).len());
+        let mut res = "rank   elf  items  calories\n".to_string();
+        if top + bottom >= n_elves {
+            res.extend((0..n_elves).map(row));
+        } else {
+            res.extend((0..top).map(row));
+            res.push_str("   ...\n");
+            res.extend((n_elves - bottom..n_elves).map(row));
+        }
+        if let (Some(median), Some(p90)) = (self.median(), self.percentile(90.0)) {
+            res.push_str(&format!(
+                "{} elves, median {}, 90th percentile {}\n",
+                self.ranked.len(),
+                median,
+                p90
+            ));
+        }
+        res
+    }
+}
+
+/// `size` elves (at least one) with 1 to 10 snacks of up to 60000 calories each.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            (0..rng.range(1, 10))
+                .map(|_| format!("{}\n", rng.range(1000, 60000)))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<u64>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        max_elf_calories(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        top_n_elf_calories(input, 3).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day1::{max_elf_calories, read_file, top_n_elf_calories};
+    use crate::day1::{
+        max_elf_calories, parse_elves, parse_input, stream_top_n, top_n_elf_calories, Elf,
+        Leaderboard, StreamError, TopN,
+    };
+    use crate::generate::{generate, Rng};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use std::io::{self, Read};
 
     const EXAMPLE: &str = "1000
 2000
@@ -70,29 +368,172 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        let input = EXAMPLE.lines().map(|s| s.to_string());
-        assert_eq!(max_elf_calories(input), 24000);
+        let input = parse_input(EXAMPLE).unwrap();
+        assert_eq!(max_elf_calories(&input), 24000);
     }
 
     #[test]
     fn part1() {
-        let input = read_file();
-        let res = max_elf_calories(input);
+        let input = parse_input(&read(1).unwrap()).unwrap();
+        let res = max_elf_calories(&input);
         println!("{}", res);
         assert_eq!(res, 72017);
     }
 
     #[test]
     fn part2_example() {
-        let input = EXAMPLE.lines().map(|s| s.to_string());
-        assert_eq!(top_n_elf_calories(input, 3), 45000);
+        let input = parse_input(EXAMPLE).unwrap();
+        assert_eq!(top_n_elf_calories(&input, 3), 45000);
     }
 
     #[test]
     fn part2() {
-        let input = read_file();
-        let res = top_n_elf_calories(input, 3);
+        let input = parse_input(&read(1).unwrap()).unwrap();
+        let res = top_n_elf_calories(&input, 3);
         println!("{}", res);
         assert_eq!(res, 212520);
     }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse_input("1000\n2000 \n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+
+        let err = parse_input("1000\n\nabc\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.kind, ErrorKind::Expected("a number".to_string()));
+    }
+
+    #[test]
+    fn elves() {
+        let elves = parse_elves(EXAMPLE).unwrap();
+        assert_eq!(elves.len(), 5);
+        assert_eq!(elves[3].index, 4);
+        assert_eq!(elves[3].items, vec![7000, 8000, 9000]);
+        assert_eq!(elves[3].total, 24000);
+        assert!(parse_elves("\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn leaderboard() {
+        let elves = parse_elves("10\n\n30\n\n20\n\n5\n25\n\n40\n\n10\n").unwrap();
+        let board = Leaderboard::new(&elves);
+        let indices = |elves: &[&Elf]| elves.iter().map(|elf| elf.index).collect::<Vec<_>>();
+        assert_eq!(indices(board.ranked()), vec![5, 2, 4, 3, 1, 6]);
+        assert_eq!(
+            (1..=7).map(|i| board.rank(i)).collect::<Vec<_>>(),
+            vec![Some(5), Some(2), Some(4), Some(2), Some(1), Some(5), None]
+        );
+        assert_eq!(indices(board.top(2)), vec![5, 2, 4]);
+        assert_eq!(indices(board.top(0)), Vec::<usize>::new());
+        assert_eq!(indices(board.top(10)), vec![5, 2, 4, 3, 1, 6]);
+        assert_eq!(indices(board.bottom(1)), vec![1, 6]);
+        assert_eq!(indices(board.bottom(3)), vec![3, 1, 6]);
+        assert_eq!(
+            board.ties().into_iter().map(indices).collect::<Vec<_>>(),
+            vec![vec![2, 4], vec![1, 6]]
+        );
+        assert_eq!(board.median(), Some(25.0));
+        assert_eq!(board.percentile(0.0), Some(10));
+        assert_eq!(board.percentile(50.0), Some(20));
+        assert_eq!(board.percentile(90.0), Some(40));
+        assert_eq!(board.percentile(100.0), Some(40));
+        assert_eq!(board.percentile(-1.0), None);
+        assert_eq!(board.percentile(100.5), None);
+        assert_eq!(board.percentile(f64::NAN), None);
+
+        assert_eq!(
+            board.table(1),
+            "rank   elf  items  calories
+   1     5      1        40
+   ...
+   5     1      1        10
+   5     6      1        10
+6 elves, median 25, 90th percentile 40
+"
+        );
+
+        let board = Leaderboard::new(&[]);
+        assert_eq!((board.median(), board.percentile(50.0)), (None, None));
+        assert_eq!(board.table(3), "rank   elf  items  calories\n");
+    }
+
+    #[test]
+    fn top_n() {
+        let mut top = TopN::new(2);
+        for calories in [10, 30, 20, 30, 40] {
+            top.push(calories);
+        }
+        assert_eq!(top.elves(), 5);
+        assert_eq!(top.top(), vec![(5, 40), (2, 30)]);
+        assert_eq!(top.sum(), 70);
+
+        let mut none = TopN::new(0);
+        none.push(10);
+        assert_eq!((none.top(), none.sum()), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn stream() {
+        let mut running = Vec::new();
+        let top = stream_top_n(EXAMPLE.as_bytes(), 3, |top| running.push(top.sum())).unwrap();
+        assert_eq!(running, vec![6000, 10000, 21000, 41000, 45000]);
+        assert_eq!(top.top(), vec![(4, 24000), (3, 11000), (5, 10000)]);
+
+        let windows = "\r\n1000\r\n2000\r\n\r\n\r\n4000\r\n\r\n\r\n";
+        let top = stream_top_n(windows.as_bytes(), 1, |_| {}).unwrap();
+        assert_eq!((top.elves(), top.top()), (2, vec![(2, 4000)]));
+        let top = stream_top_n("1000\n2000".as_bytes(), 1, |_| {}).unwrap();
+        assert_eq!(top.top(), vec![(1, 3000)]);
+
+        let err = stream_top_n("1000\r\n\r\n2000 \r\n".as_bytes(), 1, |_| {}).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 1 line 3 column 5: unexpected text after the end of the line"
+        );
+        let err = stream_top_n(&[0xff, b'\n'][..], 1, |_| {}).unwrap_err();
+        assert!(matches!(err, StreamError::Io(_)));
+    }
+
+    /// A generated input streamed through a reader that makes it up as it goes, so it is never all in memory.
+    ///
+    #[test]
+    fn stream_generated() {
+        struct Elves {
+            rng: Rng,
+            left: usize,
+            chunk: Vec<u8>,
+        }
+
+        impl Read for Elves {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.chunk.is_empty() && self.left > 0 {
+                    self.left -= 1;
+                    self.chunk = format!("\n{}", generate(1, 1, self.rng.next_u64()).unwrap())
+                        .replace('\n', "\r\n")
+                        .into_bytes();
+                }
+                let n = buf.len().min(self.chunk.len());
+                buf[..n].copy_from_slice(&self.chunk[..n]);
+                self.chunk.drain(..n);
+                Ok(n)
+            }
+        }
+
+        let elves = || Elves {
+            rng: Rng::new(1),
+            left: 20_000,
+            chunk: Vec::new(),
+        };
+        let mut text = String::new();
+        elves().read_to_string(&mut text).unwrap();
+        let totals = parse_input(&text).unwrap();
+        assert_eq!(totals.len(), 20_000);
+
+        let top = stream_top_n(elves(), 3, |_| {}).unwrap();
+        assert_eq!(top.elves(), 20_000);
+        assert_eq!(top.sum(), top_n_elf_calories(&totals, 3));
+        assert_eq!(top.top()[0].1, max_elf_calories(&totals));
+    }
 }
@@ -1,92 +1,322 @@
-use itertools::Itertools;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
+use crate::generate::Rng;
+use crate::ocr::{self, OcrError};
+use crate::parse::{parse_lines, ParseError};
+use crate::solution::{Answer, Solution};
+use std::fmt;
 
-fn read_file() -> impl Iterator<Item = String> {
-    read_named_file("day10.txt")
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    Noop,
+    Addx(i32),
 }
 
-fn read_named_file(name: &str) -> impl Iterator<Item = String> {
-    let file = File::open(format!("input/{}", name)).unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+impl Instruction {
+    /// How many cycles the instruction takes to complete.
+    ///
+    pub fn cycles(self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Addx(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Addx(v) => write!(f, "addx {}", v),
+        }
+    }
+}
+
+/// One clock cycle: its number, counting from 1, the value of X during it and the instruction being executed, with
+/// `step` counting that instruction's cycles from 1.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub cycle: usize,
+    pub x: i32,
+    pub instruction: Instruction,
+    pub step: usize,
+}
+
+impl Cycle {
+    pub fn signal_strength(&self) -> i32 {
+        self.cycle as i32 * self.x
+    }
+}
+
+/// Runs a program cycle by cycle, as an iterator over the cycles. An instruction's effect on X shows from the cycle
+/// after its last one.
+///
+pub struct Cpu<'a> {
+    program: std::slice::Iter<'a, Instruction>,
+    current: Option<(Instruction, usize)>,
+    x: i32,
+    cycle: usize,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Instruction]) -> Cpu<'a> {
+        Cpu {
+            program: program.iter(),
+            current: None,
+            x: 1,
+            cycle: 0,
+        }
+    }
+
+    /// The value of X after the cycles run so far.
+    ///
+    pub fn x(&self) -> i32 {
+        self.x
+    }
 }
 
-fn part1(input: impl Iterator<Item = String>) -> i32 {
-    let mut cycle = 1;
-    let mut x = 1;
-    let mut score = 0;
+impl Iterator for Cpu<'_> {
+    type Item = Cycle;
 
-    for line in input {
-        if (cycle - 20) % 40 == 0 {
-            score += cycle * x;
+    fn next(&mut self) -> Option<Cycle> {
+        let (instruction, step) = match self.current.take() {
+            Some((instruction, step)) => (instruction, step + 1),
+            None => (*self.program.next()?, 1),
+        };
+        self.cycle += 1;
+        let cycle = Cycle {
+            cycle: self.cycle,
+            x: self.x,
+            instruction,
+            step,
+        };
+        if step < instruction.cycles() {
+            self.current = Some((instruction, step));
+        } else if let Instruction::Addx(v) = instruction {
+            self.x += v;
         }
-        cycle += 1;
-
-        let words = line.split_ascii_whitespace().collect_vec();
-        match words.as_slice() {
-            ["noop"] => {}
-            ["addx", val_str] => {
-                if (cycle - 20) % 40 == 0 {
-                    score += cycle * x;
-                }
-                cycle += 1;
-                x += i32::from_str(val_str).unwrap();
+        Some(cycle)
+    }
+}
+
+/// The signal strength during each of `cycles`. Cycles after the end of the program see the final value of X.
+///
+pub fn signal_strengths(program: &[Instruction], cycles: &[usize]) -> Vec<i32> {
+    let mut cpu = Cpu::new(program);
+    let mut xs = vec![1];
+    xs.extend(cpu.by_ref().map(|cycle| cycle.x));
+    cycles
+        .iter()
+        .map(|&cycle| cycle as i32 * xs.get(cycle).copied().unwrap_or(cpu.x()))
+        .collect()
+}
+
+/// A CRT whose beam draws one pixel per cycle, left to right and top to bottom, lighting it if the 3 pixel wide
+/// sprite centred on X covers it.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Screen {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Screen {
+    /// The screen of the puzzle.
+    ///
+    pub const CRT: Screen = Screen {
+        width: 40,
+        height: 6,
+    };
+
+    /// The picture the program draws, `#` for lit pixels and `.` for dark ones, a line per row. Cycles past the
+    /// last pixel draw nothing.
+    ///
+    pub fn draw(&self, program: &[Instruction]) -> String {
+        let mut pixels = vec!['.'; self.width * self.height];
+        for cycle in Cpu::new(program).take(pixels.len()) {
+            let pixel = cycle.cycle - 1;
+            if ((pixel % self.width) as i32 - cycle.x).abs() <= 1 {
+                pixels[pixel] = '#';
             }
-            _ => {}
         }
+        pixels
+            .chunks(self.width)
+            .map(|row| format!("{}\n", row.iter().collect::<String>()))
+            .collect()
     }
-    score
 }
 
-fn part2(input: impl Iterator<Item = String>) -> String {
-    let mut pos = 0;
-    let mut x: i32 = 1;
-    let mut display = ['.'; 240];
-    for line in input {
-        let hor_pos = (pos % 40) as i32;
-        if hor_pos >= x - 1 && hor_pos <= x + 1 {
-            display[pos] = '#';
+/// A line per instruction with the cycles it takes and what it does to X, for debugging programs.
+///
+pub fn trace(program: &[Instruction]) -> String {
+    let mut buf = String::new();
+    let mut cpu = Cpu::new(program);
+    while let Some(cycle) = cpu.next() {
+        let n = cycle.instruction.cycles();
+        if cycle.step < n {
+            continue;
         }
-        pos += 1;
-
-        let words = line.split_ascii_whitespace().collect_vec();
-        match words.as_slice() {
-            ["noop"] => {}
-            ["addx", val_str] => {
-                let hor_pos = (pos % 40) as i32;
-                if hor_pos >= x - 1 && hor_pos <= x + 1 {
-                    display[pos] = '#';
-                }
-                pos += 1;
-                x += i32::from_str(val_str).unwrap();
-            }
-            _ => {}
+        let cycles = format!("{}-{}", cycle.cycle + 1 - n, cycle.cycle);
+        let cycles = if n == 1 {
+            cycle.cycle.to_string()
+        } else {
+            cycles
+        };
+        buf.push_str(&format!(
+            "cycle {:<9} {:<10} x {} -> {}\n",
+            cycles,
+            cycle.instruction.to_string(),
+            cycle.x,
+            cpu.x()
+        ));
+    }
+    buf
+}
+
+/// The sum of the signal strengths during the 20th cycle and every 40th one after.
+///
+pub fn part1(program: &[Instruction]) -> i32 {
+    Cpu::new(program)
+        .filter(|cycle| cycle.cycle % 40 == 20)
+        .map(|cycle| cycle.signal_strength())
+        .sum()
+}
+
+pub fn part2(program: &[Instruction]) -> String {
+    Screen::CRT.draw(program)
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    parse_lines(10, input, |cursor| {
+        if cursor.rest().starts_with("addx") {
+            cursor.literal("addx")?;
+            cursor.literal(" ")?;
+            Ok(Instruction::Addx(cursor.number()?))
+        } else {
+            cursor.literal("noop")?;
+            Ok(Instruction::Noop)
         }
+    })
+}
+
+/// A program of `size` cycles, and at least the 240 the screen takes, that keeps X on the screen.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut program = Vec::new();
+    let (mut x, mut cycles) = (1, 0);
+    while cycles < size.max(240) {
+        let to = rng.range((x - 10).max(0), (x + 10).min(39));
+        let instruction = if to == x || rng.chance(0.3) {
+            Instruction::Noop
+        } else {
+            Instruction::Addx((to - x) as i32)
+        };
+        if let Instruction::Addx(v) = instruction {
+            x += v as i64;
+        }
+        cycles += instruction.cycles();
+        program.push(format!("{}\n", instruction));
+    }
+    program.concat()
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Vec<Instruction>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
     }
 
-    let mut res = String::new();
-    for i in 0..6 {
-        res.push_str(format!("{}\n", &display[(i * 40)..((i + 1) * 40)].iter().join("")).as_str())
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        read_screen(part2(input))
+    }
+}
+
+/// The letters on the screen. Screens that don't show letters, like the example's, are given as they are, but a letter
+/// missing from the font leaves the puzzle unsolvable, with the error above the screen.
+///
+fn read_screen(screen: String) -> Answer {
+    match ocr::read(&screen) {
+        Ok(letters) => letters.into(),
+        Err(OcrError::Shape(_)) => screen.into(),
+        Err(e) => Answer::Unsolvable(format!("{}\n{}", e, screen)),
     }
-    res
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
-    use crate::day10::read_named_file;
+    use super::{
+        parse_input, part1, part2, read_screen, signal_strengths, trace, Cpu, Day10, Screen,
+    };
+    use crate::input::{read, read_variant};
+    use crate::ocr;
+    use crate::parse::ErrorKind;
+    use crate::solution::{Answer, Solution};
+
+    const SMALL: &str = "noop\naddx 3\naddx -5\n";
+
+    #[test]
+    fn test_cpu() {
+        let program = parse_input(SMALL).unwrap();
+        let mut cpu = Cpu::new(&program);
+        let xs = cpu.by_ref().map(|cycle| cycle.x).collect::<Vec<_>>();
+        assert_eq!(xs, vec![1, 1, 1, 4, 4]);
+        assert_eq!(cpu.x(), -1);
+
+        assert_eq!(
+            trace(&program),
+            "cycle 1         noop       x 1 -> 1\n\
+             cycle 2-3       addx 3     x 1 -> 4\n\
+             cycle 4-5       addx -5    x 4 -> -1\n"
+        );
+    }
+
+    #[test]
+    fn test_signal_strengths() {
+        let program = parse_input(&read_variant(10, "example1").unwrap()).unwrap();
+        assert_eq!(
+            signal_strengths(&program, &[20, 60, 100, 140, 180, 220]),
+            vec![420, 1140, 1800, 2940, 2880, 3960]
+        );
+        let program = parse_input(SMALL).unwrap();
+        assert_eq!(
+            signal_strengths(&program, &[1, 4, 6, 10]),
+            vec![1, 16, -6, -10]
+        );
+    }
+
+    #[test]
+    fn test_screen() {
+        let program = parse_input(&read_variant(10, "example1").unwrap()).unwrap();
+        // X follows the beam of the 40 pixel wide CRT, so it is off a narrower screen by the second row.
+        let screen = Screen {
+            width: 20,
+            height: 2,
+        };
+        assert_eq!(
+            screen.draw(&program),
+            "##..##..##..##..##..\n....................\n"
+        );
+    }
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(read_named_file("day10_example1.txt")), 13140);
+        assert_eq!(
+            part1(&parse_input(&read_variant(10, "example1").unwrap()).unwrap()),
+            13140
+        );
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(10).unwrap()).unwrap());
         println!("{}", res);
-        // assert_eq!(res, 0);
+        assert_eq!(res, 14360);
     }
 
     #[test]
@@ -99,14 +329,14 @@ mod tests {
 #######.......#######.......#######.....
 ";
 
-        let actual = part2(read_named_file("day10_example1.txt"));
+        let actual = part2(&parse_input(&read_variant(10, "example1").unwrap()).unwrap());
         println!("{}", actual);
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(10).unwrap()).unwrap());
         println!("{}", res);
 
         let answer = "###...##..#..#..##..####.###..####.####.
@@ -117,5 +347,38 @@ mod tests {
 ###...###.#..#.#..#.####.#..#.####.####.
 ";
         assert_eq!(res, answer);
+        assert_eq!(ocr::read(&res), Ok("BGKAEREZ".to_string()));
+    }
+
+    #[test]
+    fn test_part2_answer() {
+        let input = Day10::parse(&read(10).unwrap()).unwrap();
+        assert_eq!(Day10::part2(&input), Answer::from("BGKAEREZ"));
+
+        // The example's screen doesn't show letters.
+        let input = Day10::parse(&read_variant(10, "example1").unwrap()).unwrap();
+        assert!(Day10::part2(&input).to_string().starts_with("##..##.."));
+
+        let screen = "####.#..#\n####.#..#\n####.####\n####.#..#\n####.#..#\n####.#..#\n";
+        assert_eq!(
+            read_screen(screen.to_string()),
+            Answer::Unsolvable(format!(
+                "letter 1 is not in the font: ####/####/####/####/####/####\n{}",
+                screen
+            ))
+        );
+        let screen = "#..#\n";
+        assert_eq!(read_screen(screen.to_string()), Answer::from(screen));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("noop\naddx 3\naddx\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 5));
+        assert_eq!(err.kind, ErrorKind::EndOfLine("` `".to_string()));
+
+        let err = parse_input("noop\nadd 3\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.kind, ErrorKind::Expected("`noop`".to_string()));
     }
 }
@@ -1,24 +1,19 @@
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
 use std::collections::VecDeque;
-use std::fmt::Debug;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::iter;
-use std::str::FromStr;
 
 use itertools::Itertools;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day11.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
+#[derive(Clone, Copy, Debug)]
 enum Operation {
     Times(u64),
     Plus(u64),
     Square(),
 }
 
-struct Monkey<ITEM> {
+#[derive(Clone, Debug)]
+pub struct Monkey<ITEM> {
     items: VecDeque<ITEM>,
     op: Operation,
     test_denom: u64,
@@ -26,77 +21,139 @@ struct Monkey<ITEM> {
     false_monkey: usize,
 }
 
-fn parse_input<F, ITEM>(lines: impl Iterator<Item = String>, mut init_item: F) -> Vec<Monkey<ITEM>>
-where
-    F: FnMut(u64) -> ITEM,
-{
-    lines
-        .batching(|iter| {
-            let mut iter = iter.peekable();
-            if let None = iter.next() {
-                return None;
-            }
-            let items = iter
-                .next()
-                .unwrap()
-                .split_whitespace()
-                .skip(2)
-                .map(|v| {
-                    let level = u64::from_str(v.trim_end_matches(',')).unwrap();
-                    init_item(level)
-                })
-                .collect();
-
-            let op = match iter
-                .next()
-                .unwrap()
-                .split_whitespace()
-                .skip(4)
-                .collect_tuple()
-                .unwrap()
-            {
-                ("*", "old") => Operation::Square(),
-                ("*", n) => Operation::Times(u64::from_str(n).unwrap()),
-                ("+", n) => Operation::Plus(u64::from_str(n).unwrap()),
-                s => panic!("Unexpected operation: {:?}", s),
+impl<ITEM> Monkey<ITEM> {
+    fn map_items<F, T>(&self, f: F) -> Monkey<T>
+    where
+        F: FnMut(&ITEM) -> T,
+    {
+        Monkey {
+            items: self.items.iter().map(f).collect(),
+            op: self.op,
+            test_denom: self.test_denom,
+            true_monkey: self.true_monkey,
+            false_monkey: self.false_monkey,
+        }
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<Monkey<u64>>, ParseError> {
+    let mut monkeys = Vec::new();
+    // The lines of the throw targets, to check them once all the monkeys are known.
+    let mut targets = Vec::new();
+    let mut cursors = cursors(11, input);
+
+    while let Some(mut cursor) = cursors.next() {
+        if !monkeys.is_empty() {
+            cursor.end()?;
+            cursor = match cursors.next() {
+                Some(cursor) => cursor,
+                None => break,
             };
+        }
+        cursor.literal("Monkey ")?;
+        let column = cursor.column();
+        if cursor.number::<usize>()? != monkeys.len() {
+            return Err(cursor.error_at(
+                column,
+                ErrorKind::Expected(format!("monkey {}", monkeys.len())),
+            ));
+        }
+        cursor.literal(":")?;
+        cursor.end()?;
 
-            let test_denom = parse_last_num(&mut iter);
-            let true_monkey = parse_last_num(&mut iter);
-            let false_monkey = parse_last_num(&mut iter);
+        let mut cursor = next_line(input, &mut cursors, "the starting items")?;
+        cursor.literal("  Starting items: ")?;
+        let mut items = VecDeque::new();
+        loop {
+            items.push_back(cursor.number()?);
+            if cursor.is_empty() {
+                break;
+            }
+            cursor.literal(", ")?;
+        }
 
-            if let Some(s) = iter.peek() {
-                if s.is_empty() {
-                    iter.next().unwrap();
-                }
+        let mut cursor = next_line(input, &mut cursors, "the operation")?;
+        cursor.literal("  Operation: new = old ")?;
+        let op = match cursor.char("`*` or `+`", |c| c == '*' || c == '+')? {
+            '*' if cursor.rest() == " old" => {
+                cursor.literal(" old")?;
+                Operation::Square()
             }
+            '*' => {
+                cursor.literal(" ")?;
+                Operation::Times(cursor.number()?)
+            }
+            _ => {
+                cursor.literal(" ")?;
+                Operation::Plus(cursor.number()?)
+            }
+        };
+        cursor.end()?;
 
-            Some(Monkey {
-                items,
-                op,
-                test_denom,
-                true_monkey,
-                false_monkey,
-            })
-        })
-        .collect_vec()
+        let mut cursor = next_line(input, &mut cursors, "the test")?;
+        cursor.literal("  Test: divisible by ")?;
+        let column = cursor.column();
+        let test_denom = cursor.number()?;
+        if test_denom == 0 {
+            let expected = "a divisor above 0".to_string();
+            return Err(cursor.error_at(column, ErrorKind::Expected(expected)));
+        }
+        cursor.end()?;
+
+        let mut cursor = next_line(input, &mut cursors, "the target if true")?;
+        cursor.literal("    If true: throw to monkey ")?;
+        targets.push((cursor.line(), cursor.column()));
+        let true_monkey = cursor.number()?;
+        cursor.end()?;
+
+        let mut cursor = next_line(input, &mut cursors, "the target if false")?;
+        cursor.literal("    If false: throw to monkey ")?;
+        targets.push((cursor.line(), cursor.column()));
+        let false_monkey = cursor.number()?;
+        cursor.end()?;
+
+        monkeys.push(Monkey {
+            items,
+            op,
+            test_denom,
+            true_monkey,
+            false_monkey,
+        });
+    }
+
+    // Both parts multiply together the two busiest monkeys' inspections.
+    if monkeys.len() < 2 {
+        return Err(ParseError::end_of_input(11, input, "another monkey"));
+    }
+    let throws = monkeys
+        .iter()
+        .flat_map(|monkey| [monkey.true_monkey, monkey.false_monkey]);
+    for ((line, column), target) in targets.into_iter().zip(throws) {
+        if target >= monkeys.len() {
+            return Err(ParseError {
+                day: 11,
+                line,
+                column,
+                kind: ErrorKind::Expected(format!("a monkey below {}", monkeys.len())),
+            });
+        }
+    }
+    Ok(monkeys)
 }
 
-fn parse_last_num<T: FromStr>(iter: &mut impl Iterator<Item = String>) -> T
-where
-    <T as FromStr>::Err: Debug,
-{
-    iter.next()
-        .unwrap()
-        .split_whitespace()
-        .last()
-        .map(|v| T::from_str(v).unwrap())
-        .unwrap()
+fn next_line<'a>(
+    input: &str,
+    cursors: &mut impl Iterator<Item = Cursor<'a>>,
+    expected: &str,
+) -> Result<Cursor<'a>, ParseError> {
+    cursors
+        .next()
+        .ok_or_else(|| ParseError::end_of_input(11, input, expected))
 }
 
-fn part1(input: impl Iterator<Item = String>) -> u64 {
-    let mut monkeys = parse_input(input, |v| v);
-    let mut inspections = iter::repeat(0).take(monkeys.len()).collect_vec();
+pub fn part1(monkeys: &[Monkey<u64>]) -> u64 {
+    let mut monkeys = monkeys.to_vec();
+    let mut inspections = std::iter::repeat_n(0, monkeys.len()).collect_vec();
     for _round in 0..20 {
         for i in 0..monkeys.len() {
             while let Some(item) = monkeys[i].items.pop_front() {
@@ -122,39 +179,43 @@ fn part1(input: impl Iterator<Item = String>) -> u64 {
     inspections[inspections.len() - 2] * inspections[inspections.len() - 1]
 }
 
-const PRIMES: [u64; 9] = [2, 3, 5, 7, 11, 13, 17, 19, 23];
-
-fn part2(input: impl Iterator<Item = String>) -> u64 {
-    let mut monkeys = parse_input(input, |v| {
-        let mut item = [0; 9];
-        for (i, prime) in PRIMES.iter().enumerate() {
-            item[i] = v % prime;
-        }
-        item
-    });
-    let mut inspections = iter::repeat(0).take(monkeys.len()).collect_vec();
+/// Part two keeps each worry level as its remainders by each of the tests' divisors, which is all the tests look at
+/// and which stay small however many rounds are played.
+///
+pub fn part2(monkeys: &[Monkey<u64>]) -> u64 {
+    let denoms = monkeys
+        .iter()
+        .map(|monkey| monkey.test_denom)
+        .unique()
+        .collect_vec();
+    let mut monkeys = monkeys
+        .iter()
+        .map(|monkey| monkey.map_items(|v| denoms.iter().map(|d| v % d).collect_vec()))
+        .collect_vec();
+    let mut inspections = std::iter::repeat_n(0, monkeys.len()).collect_vec();
     for _round in 0..10000 {
         for i in 0..monkeys.len() {
             while let Some(mut item) = monkeys[i].items.pop_front() {
                 inspections[i] += 1;
 
-                for j in 0..PRIMES.len() {
-                    item[j] = match monkeys[i].op {
-                        Operation::Times(v) => item[j] * v,
-                        Operation::Plus(v) => item[j] + v,
-                        Operation::Square() => item[j] * item[j],
-                    } % PRIMES[j];
+                for (r, &d) in item.iter_mut().zip(denoms.iter()) {
+                    let (r128, d128) = (*r as u128, d as u128);
+                    *r = (match monkeys[i].op {
+                        Operation::Times(v) => r128 * v as u128,
+                        Operation::Plus(v) => r128 + v as u128,
+                        Operation::Square() => r128 * r128,
+                    } % d128) as u64;
                 }
 
-                let mut target = monkeys[i].false_monkey;
-                for j in 0..PRIMES.len() {
-                    if PRIMES[j] == monkeys[i].test_denom {
-                        if item[j] == 0 {
-                            target = monkeys[i].true_monkey;
-                        }
-                        break;
-                    }
-                }
+                let j = denoms
+                    .iter()
+                    .position(|&d| d == monkeys[i].test_denom)
+                    .unwrap();
+                let target = if item[j] == 0 {
+                    monkeys[i].true_monkey
+                } else {
+                    monkeys[i].false_monkey
+                };
                 monkeys[target].items.push_back(item);
             }
         }
@@ -164,9 +225,156 @@ fn part2(input: impl Iterator<Item = String>) -> u64 {
     inspections[inspections.len() - 2] * inspections[inspections.len() - 1]
 }
 
+/// `size` monkeys (at least two) with tests dividing by 2 to 40, primes or not and maybe the same for several monkeys,
+/// and one of them squaring like in the real inputs. Worry levels can grow too big for a `u64` in part one, so the
+/// monkeys are drawn again until they don't, with smaller multipliers and no squaring after a few tries. Multiplying by
+/// at most 3 can't outgrow the division by 3.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n_monkeys = size.max(2);
+    let mut attempt = 0;
+    let monkeys = loop {
+        let square = (attempt < 5).then(|| rng.below(n_monkeys));
+        let max_factor = (19 - attempt).max(3);
+        let monkeys = (0..n_monkeys)
+            .map(|i| {
+                let mut others = (0..n_monkeys).filter(|&j| j != i).collect::<Vec<_>>();
+                rng.shuffle(&mut others);
+                Monkey {
+                    items: (0..rng.range(1, 6))
+                        .map(|_| rng.range(50, 99) as u64)
+                        .collect(),
+                    op: match rng.below(3) {
+                        _ if square == Some(i) => Operation::Square(),
+                        0 => Operation::Plus(rng.range(1, 8) as u64),
+                        _ => Operation::Times(rng.range(2, max_factor) as u64),
+                    },
+                    test_denom: rng.range(2, 40) as u64,
+                    true_monkey: others[0],
+                    false_monkey: *others.get(1).unwrap_or(&others[0]),
+                }
+            })
+            .collect::<Vec<_>>();
+        if worry_fits(&monkeys) {
+            break monkeys;
+        }
+        attempt += 1;
+    };
+
+    monkeys
+        .iter()
+        .enumerate()
+        .map(|(i, monkey)| {
+            let op = match monkey.op {
+                Operation::Times(v) => format!("* {}", v),
+                Operation::Plus(v) => format!("+ {}", v),
+                Operation::Square() => "* old".to_string(),
+            };
+            format!(
+                "Monkey {}:\n  Starting items: {}\n  Operation: new = old {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+                i,
+                monkey.items.iter().join(", "),
+                op,
+                monkey.test_denom,
+                monkey.true_monkey,
+                monkey.false_monkey
+            )
+        })
+        .join("\n")
+}
+
+/// Whether the 20 rounds of part one can be played without the worry levels overflowing.
+///
+fn worry_fits(monkeys: &[Monkey<u64>]) -> bool {
+    let mut monkeys = monkeys.to_vec();
+    for _round in 0..20 {
+        for i in 0..monkeys.len() {
+            while let Some(item) = monkeys[i].items.pop_front() {
+                let worry = match monkeys[i].op {
+                    Operation::Times(v) => item.checked_mul(v),
+                    Operation::Plus(v) => item.checked_add(v),
+                    Operation::Square() => item.checked_mul(item),
+                };
+                let Some(worry) = worry else {
+                    return false;
+                };
+                let target = if (worry / 3) % monkeys[i].test_denom == 0 {
+                    monkeys[i].true_monkey
+                } else {
+                    monkeys[i].false_monkey
+                };
+                monkeys[target].items.push_back(worry / 3);
+            }
+        }
+    }
+    true
+}
+
+/// Part two keeping each worry level modulo the least common multiple of the tests' divisors, which doesn't change
+/// whether any test passes. Slower than `part2` but with no assumptions about the divisors, as a check on it. `None` if
+/// the least common multiple doesn't fit in a `u64`.
+///
+pub fn reference_part2(monkeys: &[Monkey<u64>]) -> Option<u64> {
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+    let modulus = monkeys.iter().try_fold(1_u64, |lcm, monkey| {
+        (lcm / gcd(lcm, monkey.test_denom)).checked_mul(monkey.test_denom)
+    })?;
+
+    let mut monkeys = monkeys.to_vec();
+    let mut inspections = vec![0; monkeys.len()];
+    for _round in 0..10000 {
+        for i in 0..monkeys.len() {
+            while let Some(item) = monkeys[i].items.pop_front() {
+                inspections[i] += 1;
+                // The worry level is below the modulus, but its square needn't fit in a `u64`.
+                let worry = (match monkeys[i].op {
+                    Operation::Times(v) => item as u128 * v as u128,
+                    Operation::Plus(v) => item as u128 + v as u128,
+                    Operation::Square() => item as u128 * item as u128,
+                } % modulus as u128) as u64;
+                let target = if worry.is_multiple_of(monkeys[i].test_denom) {
+                    monkeys[i].true_monkey
+                } else {
+                    monkeys[i].false_monkey
+                };
+                monkeys[target].items.push_back(worry);
+            }
+        }
+    }
+
+    inspections.sort();
+    Some(inspections[inspections.len() - 2] * inspections[inspections.len() - 1])
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = Vec<Monkey<u64>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2, reference_part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "Monkey 0:
   Starting items: 79, 98
@@ -199,25 +407,95 @@ Monkey 3:
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 10605);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 10605);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(11).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 118674);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 2713310158);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 2713310158);
+        assert_eq!(
+            reference_part2(&parse_input(EXAMPLE).unwrap()),
+            Some(2713310158)
+        );
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(11).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 32333418600);
     }
+
+    /// Divisors that aren't prime, or are too big for their squares to fit in a `u64`, work the same in both ways of
+    /// solving part two.
+    ///
+    #[test]
+    fn test_part2_divisors() {
+        for (from, to) in [("23", "29"), ("19", "25"), ("13", "4294967311")] {
+            let input = EXAMPLE.replace(
+                &format!("divisible by {}", from),
+                &format!("divisible by {}", to),
+            );
+            let monkeys = parse_input(&input).unwrap();
+            assert_eq!(Some(part2(&monkeys)), reference_part2(&monkeys), "{}", to);
+        }
+        // The divisors' least common multiple is too big for the reference, but not for `part2`.
+        let input = EXAMPLE
+            .replace("divisible by 23", "divisible by 4294967311")
+            .replace("divisible by 19", "divisible by 4294967357");
+        let monkeys = parse_input(&input).unwrap();
+        assert_eq!(reference_part2(&monkeys), None);
+        assert!(part2(&monkeys) > 0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = EXAMPLE.replace("old * 19", "old / 19");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 24));
+        assert_eq!(err.kind, ErrorKind::Expected("`*` or `+`".to_string()));
+
+        let input = EXAMPLE.replace(
+            "throw to monkey 3\n\nMonkey 1",
+            "throw to monkey 4\n\nMonkey 1",
+        );
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (6, 31));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a monkey below 4".to_string())
+        );
+
+        let input = EXAMPLE.replace("divisible by 19", "divisible by 0");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (11, 22));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a divisor above 0".to_string())
+        );
+
+        let input = EXAMPLE
+            .split("\n\n")
+            .next()
+            .unwrap()
+            .replace("monkey 2", "monkey 0");
+        let input = input.replace("monkey 3", "monkey 0");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (7, 1));
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfInput("another monkey".to_string())
+        );
+
+        let err = parse_input("Monkey 0:\n  Starting items: 79, 98\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.kind, ErrorKind::EndOfInput("the operation".to_string()));
+    }
 }
@@ -1,130 +1,138 @@
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day12.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+use crate::generate::Rng;
+use crate::grid::{Grid, Pos};
+use crate::parse::{ErrorKind, ParseError};
+use crate::search;
+use crate::solution::{Answer, Solution};
+
+pub type HeightMap = (Grid<i32>, Pos, Pos);
+
+fn parse_input(input: &str) -> Result<HeightMap, ParseError> {
+    let chars = Grid::parse(12, input, "a height `a`-`z`, `S` or `E`", |c| {
+        (c.is_ascii_lowercase() || c == 'S' || c == 'E').then_some(c)
+    })?;
+    let start_coord = find_single(input, &chars, 'S')?;
+    let end_coord = find_single(input, &chars, 'E')?;
+    let grid = chars.map(|&c| match c {
+        'S' => 0,
+        'E' => 25,
+        c => (u32::from(c) - u32::from('a')) as i32,
+    });
+    Ok((grid, start_coord, end_coord))
 }
 
-type Coord = (i32, i32);
-
-fn parse_input(iter: impl Iterator<Item = String>) -> (Vec<Vec<i32>>, Coord, Coord) {
-    let mut grid = Vec::new();
-    let mut start_coord = (0, 0);
-    let mut end_coord = (0, 0);
-    for (row, line) in iter.enumerate() {
-        let mut vec = Vec::new();
-        for (col, c) in line.chars().enumerate() {
-            let value = match c {
-                'S' => {
-                    start_coord = (row as i32, col as i32);
-                    0
-                }
-                'E' => {
-                    end_coord = (row as i32, col as i32);
-                    25
-                }
-                c => (u32::from(c) - u32::from('a')) as i32,
-            };
-            vec.push(value);
-        }
-        grid.push(vec);
+/// The position of the one `marker`, the start `S` or the end `E`.
+///
+fn find_single(input: &str, chars: &Grid<char>, marker: char) -> Result<Pos, ParseError> {
+    let mut found = chars
+        .iter()
+        .filter(|&(_, &c)| c == marker)
+        .map(|(pos, _)| pos);
+    let pos = found.next().ok_or_else(|| {
+        let expected = if marker == 'S' {
+            "the start `S`"
+        } else {
+            "the end `E`"
+        };
+        ParseError::end_of_input(12, input, expected)
+    })?;
+    match found.next() {
+        Some(other) => Err(ParseError {
+            day: 12,
+            line: other.row + 1,
+            column: other.col + 1,
+            kind: ErrorKind::Expected(format!("a single `{}`", marker)),
+        }),
+        None => Ok(pos),
     }
-
-    (grid, start_coord, end_coord)
 }
 
-#[derive(Clone, Eq, PartialEq)]
-struct State {
-    steps: i32,
-    coord: Coord,
+/// The fewest steps from any of the starts to the end, climbing at most one higher with each step.
+///
+fn shortest_path(grid: &Grid<i32>, start_coords: Vec<Pos>, end_coord: Pos) -> i32 {
+    let neighbours = |&coord: &Pos| {
+        grid.neighbours4(coord)
+            .filter(move |&new_coord| grid[new_coord] <= grid[coord] + 1)
+    };
+    search::bfs(start_coords, neighbours, |&coord| coord == end_coord)
+        .map_or(0, |path| path.cost as i32)
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (self.steps).cmp(&other.steps).reverse()
-    }
+pub fn part1((grid, start_coord, end_coord): &HeightMap) -> i32 {
+    shortest_path(grid, vec![*start_coord], *end_coord)
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+pub fn part2((grid, _, end_coord): &HeightMap) -> i32 {
+    let start_coords = grid
+        .iter()
+        .filter(|&(_, &height)| height == 0)
+        .map(|(pos, _)| pos)
+        .collect();
+    shortest_path(grid, start_coords, *end_coord)
 }
 
-fn shortest_path(grid: Vec<Vec<i32>>, start_coords: Vec<Coord>, end_coord: Coord) -> i32 {
-    let n_rows = grid.len() as i32;
-    let n_cols = grid[0].len() as i32;
-    let mut distances = HashMap::new();
-    let mut q = BinaryHeap::new();
-    for start_coord in start_coords.iter() {
-        let initial_state = State {
-            steps: 0,
-            coord: *start_coord,
-        };
-        q.push(initial_state);
-    }
-
-    while let Some(State {
-        steps,
-        coord: coord @ (row, col),
-    }) = q.pop()
-    {
-        if coord == end_coord {
-            return steps;
-        }
-        if let Some(&old_steps) = distances.get(&coord) {
-            if old_steps < steps {
-                distances.insert(coord, steps);
-            } else {
-                continue;
+/// A heightmap `size` rows high (at least 2) and twice as wide (at least 26) with a way from `S` to `E`: a path that
+/// wanders right across the map, climbing one at a time. The rest of the map is random.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let (n_rows, n_cols) = (size.max(2), (2 * size).max(26));
+    let cells = (0..n_rows * n_cols)
+        .map(|_| (b'a' + rng.below(26) as u8) as char)
+        .collect();
+    let mut map = Grid::from_vec(n_rows, n_cols, cells);
+
+    // Going up or down carries on the same way until the path next goes right, so it never crosses itself.
+    let mut path = vec![Pos::new(rng.below(n_rows), 0)];
+    let mut vertical = None;
+    while path.last().unwrap().col < n_cols - 1 {
+        let pos = *path.last().unwrap();
+        let dir = vertical.unwrap_or(if rng.chance(0.5) { 1 } else { -1 });
+        match map.pos(pos.row as isize + dir, pos.col as isize) {
+            Some(next) if rng.chance(0.4) => {
+                vertical = Some(dir);
+                path.push(next);
             }
-        } else {
-            distances.insert(coord, steps);
-        }
-
-        let c = grid[row as usize][col as usize];
-        for (dr, dc) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-            let new_row = row + dr;
-            let new_col = col + dc;
-            if new_row >= 0 && new_row < n_rows && new_col >= 0 && new_col < n_cols {
-                if grid[new_row as usize][new_col as usize] <= c + 1 {
-                    q.push(State {
-                        steps: steps + 1,
-                        coord: (new_row, new_col),
-                    });
-                }
+            _ => {
+                vertical = None;
+                path.push(Pos::new(pos.row, pos.col + 1));
             }
         }
     }
-    0
-}
 
-fn part1(input: impl Iterator<Item = String>) -> i32 {
-    let (grid, start_coord, end_coord) = parse_input(input);
-    shortest_path(grid, vec![start_coord], end_coord)
+    let mut climbs = (1..path.len()).collect::<Vec<_>>();
+    rng.shuffle(&mut climbs);
+    climbs.truncate(25);
+    for (i, &pos) in path.iter().enumerate() {
+        let height = climbs.iter().filter(|&&climb| climb <= i).count();
+        map[pos] = (b'a' + height as u8) as char;
+    }
+    map[path[0]] = 'S';
+    map[*path.last().unwrap()] = 'E';
+    map.render(|&c| c)
 }
 
-fn part2(input: impl Iterator<Item = String>) -> i32 {
-    let (grid, _, end_coord) = parse_input(input);
-    let mut start_coords = Vec::new();
-    for r in 0..grid.len() {
-        let row = &grid[r];
-        for c in 0..row.len() {
-            if row[c] == 0 {
-                start_coords.push((r as i32, c as i32));
-            }
-        }
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input = HeightMap;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
     }
 
-    shortest_path(grid, start_coords, end_coord)
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "Sabqponm
 abcryxxl
@@ -135,25 +143,39 @@ abdefghi
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 31);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 31);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(12).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 447);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 29);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 29);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(12).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 446);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("Sab\nac1\nxyE\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a height `a`-`z`, `S` or `E`".to_string())
+        );
+
+        let err = parse_input("Sab\nabc\nxyz\n").unwrap_err();
+        assert_eq!(err.line, 4);
+        assert_eq!(err.kind, ErrorKind::EndOfInput("the end `E`".to_string()));
+    }
 }
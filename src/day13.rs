@@ -1,16 +1,12 @@
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ParseError};
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
 use std::cmp::Ordering;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day13.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use std::fmt;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
-enum Packet {
+pub enum Packet {
     List(Vec<Packet>),
     Scalar(u32),
 }
@@ -41,62 +37,60 @@ impl Ord for Packet {
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
-impl From<String> for Packet {
-    fn from(str: String) -> Self {
-        let mut stack = VecDeque::new();
-        let mut iter = str.chars().peekable();
-        while let Some(char) = iter.next() {
-            match char {
-                '[' => stack.push_back(Packet::List(Vec::new())),
-                ']' => {
-                    if stack.len() > 1 {
-                        let packet = stack.pop_back().unwrap();
-                        if let Some(Packet::List(ls)) = stack.back_mut() {
-                            ls.push(packet)
-                        }
-                    }
-                }
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::List(ls) => write!(f, "[{}]", ls.iter().join(",")),
+            Packet::Scalar(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl Packet {
+    /// Reads a list like `[1,[2,3],[]]`.
+    ///
+    fn parse(cursor: &mut Cursor) -> Result<Packet, ParseError> {
+        cursor.literal("[")?;
+        let mut ls = Vec::new();
+        if cursor.peek() == Some(']') {
+            cursor.literal("]")?;
+            return Ok(Packet::List(ls));
+        }
+        loop {
+            if cursor.peek() == Some('[') {
+                ls.push(Packet::parse(cursor)?);
+            } else if cursor.peek().is_some_and(|c| c.is_ascii_digit()) {
+                ls.push(Packet::Scalar(cursor.number()?));
+            } else {
+                return Err(cursor.expected("a number or `[`"));
+            }
+
+            match cursor.char("`,` or `]`", |c| c == ',' || c == ']')? {
                 ',' => {}
-                v => {
-                    let mut d = v.to_digit(10).unwrap();
-                    while let Some(c) = iter.peek() {
-                        match c.to_digit(10) {
-                            Some(d2) => {
-                                d = d * 10 + d2;
-                                iter.next();
-                            }
-                            None => break,
-                        }
-                    }
-                    if let Some(Packet::List(ls)) = stack.back_mut() {
-                        ls.push(Packet::Scalar(d))
-                    }
-                }
+                _ => return Ok(Packet::List(ls)),
             }
         }
-        stack.pop_back().unwrap()
     }
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> Vec<Packet> {
-    input
-        .batching(|iter| loop {
-            let line = iter.next();
-            match line {
-                None => return None,
-                Some(s) if s.is_empty() => {}
-                Some(s) => return Some(Packet::from(s)),
-            }
-        })
-        .collect_vec()
+/// The packets in order, ignoring the blank lines between pairs.
+///
+fn parse_input(input: &str) -> Result<Vec<Packet>, ParseError> {
+    let mut packets = Vec::new();
+    for mut cursor in cursors(13, input) {
+        if !cursor.is_empty() {
+            packets.push(Packet::parse(&mut cursor)?);
+            cursor.end()?;
+        }
+    }
+    Ok(packets)
 }
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    let packets = parse_input(input);
+pub fn part1(packets: &[Packet]) -> usize {
     let mut res = 0;
     for (i, (p1, p2)) in packets.iter().tuples().enumerate() {
         if p1.cmp(p2) == Ordering::Less {
@@ -106,8 +100,8 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
     res
 }
 
-fn part2(input: impl Iterator<Item = String>) -> usize {
-    let mut packets = parse_input(input);
+pub fn part2(packets: &[Packet]) -> usize {
+    let mut packets = packets.to_vec();
     let d1 = Packet::List(vec![Packet::List(vec![Packet::Scalar(2)])]);
     let d2 = Packet::List(vec![Packet::List(vec![Packet::Scalar(6)])]);
     packets.push(d1.clone());
@@ -123,9 +117,73 @@ fn part2(input: impl Iterator<Item = String>) -> usize {
     res
 }
 
+/// `size` pairs of packets (at least one). The second packet of a pair is often the first with a change or two, so
+/// that comparing them has to look past the start.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    fn random(rng: &mut Rng, depth: usize) -> Packet {
+        Packet::List(
+            (0..rng.range(0, 4))
+                .map(|_| {
+                    if depth < 3 && rng.chance(0.3) {
+                        random(rng, depth + 1)
+                    } else {
+                        Packet::Scalar(rng.range(0, 10) as u32)
+                    }
+                })
+                .collect(),
+        )
+    }
+    fn change(rng: &mut Rng, packet: &Packet) -> Packet {
+        match packet {
+            Packet::List(ls) if !ls.is_empty() && rng.chance(0.7) => {
+                let mut ls = ls.clone();
+                let i = rng.below(ls.len());
+                ls[i] = change(rng, &ls[i]);
+                Packet::List(ls)
+            }
+            Packet::List(_) => random(rng, 2),
+            Packet::Scalar(v) if rng.chance(0.5) => Packet::List(vec![Packet::Scalar(*v)]),
+            Packet::Scalar(_) => Packet::Scalar(rng.range(0, 10) as u32),
+        }
+    }
+
+    (0..size.max(1))
+        .map(|_| {
+            let left = random(rng, 0);
+            let right = if rng.chance(0.7) {
+                change(rng, &left)
+            } else {
+                random(rng, 0)
+            };
+            format!("{}\n{}\n", left, right)
+        })
+        .join("\n")
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Vec<Packet>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
@@ -154,25 +212,39 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 13);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 13);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(13).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 5503);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 140);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 140);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(13).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 20952);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("[1,1,3]\n[[1],[2,3,4]\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 13));
+        assert_eq!(err.kind, ErrorKind::EndOfLine("`,` or `]`".to_string()));
+
+        let err = parse_input("[1,,3]\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 4));
+        assert_eq!(err.kind, ErrorKind::Expected("a number or `[`".to_string()));
+
+        let err = parse_input("[1,3] \n").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+    }
 }
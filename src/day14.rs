@@ -1,59 +1,85 @@
-use itertools::Itertools;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day14.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use crate::generate::Rng;
+use crate::grid::{Grid, Pos};
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::render::Frames;
+use crate::solution::{Answer, Solution};
 
 type Coord = (usize, usize);
 
-fn parse_input(input: impl Iterator<Item = String>) -> (HashSet<Coord>, usize) {
-    let mut grid = HashSet::new();
-    let mut max_y = 0;
-
-    for line in input {
-        for (coord1_str, coord2_str) in line.split(" -> ").tuple_windows() {
-            let (x1, y1) = parse_coord(coord1_str);
-            let (x2, y2) = parse_coord(coord2_str);
-            let start_x = x1.min(x2);
-            let end_x = x1.max(x2);
-            let start_y = y1.min(y2);
-            let end_y = y1.max(y2);
-
-            for x in start_x..(end_x + 1) {
-                for y in start_y..(end_y + 1) {
-                    grid.insert((x, y));
+/// The rock in the cave, indexed by `y` then `x`, and the lowest rock's `y`. The grid has room for the sand to pile
+/// up on the floor two below that.
+///
+pub type Cave = (Grid<bool>, usize);
+
+fn parse_input(input: &str) -> Result<Cave, ParseError> {
+    let mut rocks = Vec::new();
+
+    for mut cursor in cursors(14, input) {
+        let (mut x1, mut y1) = parse_coord(&mut cursor)?;
+        rocks.push((x1, y1));
+        while !cursor.is_empty() {
+            cursor.literal(" -> ")?;
+            let column = cursor.column();
+            let (x2, y2) = parse_coord(&mut cursor)?;
+            if x1 != x2 && y1 != y2 {
+                return Err(cursor.error_at(
+                    column,
+                    ErrorKind::Expected("a point in line with the previous one".to_string()),
+                ));
+            }
+
+            for x in x1.min(x2)..=x1.max(x2) {
+                for y in y1.min(y2)..=y1.max(y2) {
+                    rocks.push((x, y));
                 }
             }
-            max_y = max_y.max(y1).max(y2);
+            (x1, y1) = (x2, y2);
         }
     }
 
-    (grid, max_y)
+    let max_y = rocks.iter().map(|&(_x, y)| y).max().unwrap_or(0);
+    let max_x = rocks.iter().map(|&(x, _y)| x).max().unwrap_or(0);
+    let mut grid = Grid::new(max_y + 2, max_x.max(500 + max_y) + 3, false);
+    for (x, y) in rocks {
+        grid[Pos::new(y, x)] = true;
+    }
+    Ok((grid, max_y))
+}
+
+fn parse_coord(cursor: &mut Cursor) -> Result<Coord, ParseError> {
+    let x = cursor.number()?;
+    cursor.literal(",")?;
+    let y = cursor.number()?;
+    Ok((x, y))
 }
 
-fn parse_coord(coord_str: &str) -> Coord {
-    let (x_str, y_str) = coord_str.split(",").collect_tuple().unwrap();
-    let x = usize::from_str(x_str).unwrap();
-    let y = usize::from_str(y_str).unwrap();
-    (x, y)
+pub fn part1(cave: &Cave) -> u32 {
+    pour(cave, false, |_| {})
 }
 
-fn part1(input: impl Iterator<Item = String>) -> u32 {
-    let (mut grid, max_y) = parse_input(input);
+/// Drops sand until it starts falling into the abyss or, with the floor, until it blocks the source. Calls `rest`
+/// with where each unit comes to rest and returns how many did.
+///
+fn pour((grid, max_y): &Cave, floor: bool, mut rest: impl FnMut(Coord)) -> u32 {
+    let mut grid = grid.clone();
     let mut sand = 0;
-    while let Some(sand_coord) = drop_sand(&grid, max_y, usize::MAX) {
-        grid.insert(sand_coord);
+    let (max_iterations, max_y) = if floor {
+        (usize::MAX, *max_y + 1)
+    } else {
+        (*max_y, usize::MAX)
+    };
+    while let Some(sand_coord @ (x, y)) = drop_sand(&grid, max_iterations, max_y) {
+        grid[Pos::new(y, x)] = true;
         sand += 1;
+        rest(sand_coord);
+        if sand_coord == (500, 0) {
+            break;
+        }
     }
-    return sand;
+    sand
 }
 
-fn drop_sand(grid: &HashSet<Coord>, max_iterations: usize, max_y: usize) -> Option<Coord> {
+fn drop_sand(grid: &Grid<bool>, max_iterations: usize, max_y: usize) -> Option<Coord> {
     let mut x = 500;
     let mut y = 0;
     let mut iterations = 0;
@@ -61,12 +87,12 @@ fn drop_sand(grid: &HashSet<Coord>, max_iterations: usize, max_y: usize) -> Opti
         if y == max_y {
             return Some((x, y));
         } else {
-            if !grid.contains(&(x, y + 1)) {
+            if !grid[Pos::new(y + 1, x)] {
                 y += 1;
-            } else if !grid.contains(&(x - 1, y + 1)) {
+            } else if !grid[Pos::new(y + 1, x - 1)] {
                 y += 1;
                 x -= 1;
-            } else if !grid.contains(&(x + 1, y + 1)) {
+            } else if !grid[Pos::new(y + 1, x + 1)] {
                 y += 1;
                 x += 1;
             } else {
@@ -78,22 +104,90 @@ fn drop_sand(grid: &HashSet<Coord>, max_iterations: usize, max_y: usize) -> Opti
     None
 }
 
-fn part2(input: impl Iterator<Item = String>) -> u32 {
-    let (mut grid, max_y) = parse_input(input);
-    let mut sand = 0;
-    while let Some(sand_coord) = drop_sand(&grid, usize::MAX, max_y + 1) {
-        grid.insert(sand_coord);
-        sand += 1;
-        if sand_coord == (500, 0) {
-            break;
+pub fn part2(cave: &Cave) -> u32 {
+    pour(cave, true, |_| {})
+}
+
+/// The cave after each unit of sand comes to rest: rock `#`, sand `o` and air `.`, with the source `+`. Only the
+/// columns the sand can reach are drawn, and part two adds the floor.
+///
+pub fn draw(cave: &Cave, part: u32, frames: &mut Frames) {
+    let (grid, max_y) = cave;
+    let (min_x, max_x) = (500 - max_y - 2, 500 + max_y + 2);
+    let mut frame = Grid::new(max_y + 2 + part as usize - 1, max_x - min_x + 1, '.');
+    for pos in frame.positions().collect::<Vec<_>>() {
+        if pos.row == max_y + 2 || grid[Pos::new(pos.row, pos.col + min_x)] {
+            frame[pos] = '#';
         }
     }
-    return sand;
+    frame[Pos::new(0, 500 - min_x)] = '+';
+
+    pour(cave, part == 2, |(x, y)| {
+        frame[Pos::new(y, x - min_x)] = 'o';
+        frames.step(|| frame.clone());
+    });
+    frames.finish(|| frame.clone());
+}
+
+/// `size` rock paths (at least one) of 1 to 4 lines each, spread out under the source. The first starts with a line
+/// right under the source so that some sand comes to rest in part one.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let spread = 3 + size as i64;
+    (0..size.max(1))
+        .map(|i| {
+            let (mut x, mut y) = (
+                rng.range(500 - spread, 500 + spread),
+                rng.range(2, 2 * spread),
+            );
+            if i == 0 {
+                x = rng.range(495, 499);
+            }
+            let mut points = vec![format!("{},{}", x, y)];
+            let mut horizontal = i == 0 || rng.chance(0.5);
+            for j in 0..rng.range(1, 4) {
+                let length = if i == 0 && j == 0 {
+                    rng.range(501 - x, 505 - x)
+                } else {
+                    rng.range(1, 6) * if rng.chance(0.5) { 1 } else { -1 }
+                };
+                if horizontal {
+                    x += length;
+                } else {
+                    y = (y + length).max(1);
+                }
+                horizontal = !horizontal;
+                points.push(format!("{},{}", x, y));
+            }
+            format!("{}\n", points.join(" -> "))
+        })
+        .collect()
+}
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Cave;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{draw, parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
 
     const EXAMPLE: &str = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9
@@ -101,25 +195,66 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 24);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 24);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(14).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 644);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 93);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 93);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(14).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 27324);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("498,4 -> 498,6 -> 496,7\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 19));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a point in line with the previous one".to_string())
+        );
+
+        let err = parse_input("498,4 -> 498,6\n503,4 > 502,4\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 6));
+        assert_eq!(err.kind, ErrorKind::Expected("` -> `".to_string()));
+    }
+
+    #[test]
+    fn test_draw() {
+        let cave = parse_input(EXAMPLE).unwrap();
+        let mut frames = Vec::new();
+        let mut sink = |_: usize, frame: &Frame| frames.push(frame.render(|&c| c));
+        draw(&cave, 1, &mut Frames::new(1, &mut sink));
+        assert_eq!(frames.len(), 24);
+        assert_eq!(
+            frames[23]
+                .lines()
+                .skip(2)
+                .map(|line| &line[5..16])
+                .collect::<Vec<_>>(),
+            vec![
+                "......o....",
+                ".....ooo...",
+                "....#ooo##.",
+                "...o#ooo#..",
+                "..###ooo#..",
+                "....oooo#..",
+                ".o.ooooo#..",
+                "#########..",
+                "...........",
+            ]
+        );
+    }
 }
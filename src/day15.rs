@@ -1,12 +1,56 @@
+use crate::generate::Rng;
+use crate::parse::{parse_lines, Cursor, ParseError};
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
 
 type Coord = (i64, i64);
 
+/// The sensor and closest beacon positions, from lines like
+/// `Sensor at x=2, y=18: closest beacon is at x=-2, y=15`. Leading spaces are ignored. There must be at least one.
+///
+fn parse_input(input: &str) -> Result<Vec<(Coord, Coord)>, ParseError> {
+    let sensors = parse_lines(15, input, |cursor| {
+        cursor.take_while(|c| c == ' ');
+        cursor.literal("Sensor at ")?;
+        let sensor = parse_coord(cursor)?;
+        cursor.literal(": closest beacon is at ")?;
+        let beacon = parse_coord(cursor)?;
+        Ok((sensor, beacon))
+    })?;
+    if sensors.is_empty() {
+        return Err(ParseError::end_of_input(15, input, "a sensor"));
+    }
+    Ok(sensors)
+}
+
+fn parse_coord(cursor: &mut Cursor) -> Result<Coord, ParseError> {
+    cursor.literal("x=")?;
+    let x = cursor.number()?;
+    cursor.literal(", y=")?;
+    let y = cursor.number()?;
+    Ok((x, y))
+}
+
 fn distance((x1, y1): &Coord, (x2, y2): &Coord) -> i64 {
     (x1 - x2).abs() + (y1 - y2).abs()
 }
 
-fn part1(input: &[(Coord, Coord)], y: i64) -> u32 {
+/// The distress beacon is somewhere in `0..=bound` in both directions: 20 for the example and 4000000 for the real
+/// inputs. The example's is only taken when it has all the sensors in it.
+///
+pub fn search_bound(input: &[(Coord, Coord)]) -> i64 {
+    if input.iter().all(|((x, y), _bc)| *x.max(y) <= 20) {
+        20
+    } else {
+        4_000_000
+    }
+}
+
+/// The row to count in, `y`, can be given, otherwise it is the middle of the search area: 10 for the example and
+/// 2000000 for the real inputs.
+///
+pub fn part1(input: &[(Coord, Coord)], y: Option<i64>) -> u32 {
+    let y = y.unwrap_or_else(|| search_bound(input) / 2);
     let max_distance = input.iter().map(|(c1, c2)| distance(c1, c2)).max().unwrap();
     let &min_x = input
         .iter()
@@ -36,7 +80,10 @@ fn part1(input: &[(Coord, Coord)], y: i64) -> u32 {
     count
 }
 
-fn part2(input: &[(Coord, Coord)], max: i64) -> i64 {
+/// The search bound can be given, otherwise see `search_bound`.
+///
+pub fn part2(input: &[(Coord, Coord)], max: Option<i64>) -> i64 {
+    let max = max.unwrap_or_else(|| search_bound(input));
     let distances = input
         .iter()
         .map(|(sc, bc)| (*sc, *bc, distance(sc, bc)))
@@ -61,96 +108,188 @@ fn part2(input: &[(Coord, Coord)], max: i64) -> i64 {
                     && y <= max
                     && distances
                         .iter()
-                        .map(|(sc, _bc, dist)| distance(sc, &c) > *dist)
-                        .all(|v| v)
+                        .all(|(sc, _bc, dist)| distance(sc, &c) > *dist)
                 {
                     return x * 4_000_000 + y;
                 }
             }
         }
     }
-    return 0;
+    0
+}
+
+/// Part two by trying every point in the search area in turn, which only works for an area as small as the example's.
+/// A check on the border scan in `part2`.
+///
+pub fn reference_part2(input: &[(Coord, Coord)], max: Option<i64>) -> i64 {
+    let max = max.unwrap_or_else(|| search_bound(input));
+    for x in 0..=max {
+        for y in 0..=max {
+            if input
+                .iter()
+                .all(|(sc, bc)| distance(sc, &(x, y)) > distance(sc, bc))
+            {
+                return x * 4_000_000 + y;
+            }
+        }
+    }
+    0
+}
+
+/// `size` sensors (at least four) with the distress beacon at a random point inside the search area, which is the
+/// example's when there are up to 12 sensors and the real one otherwise. A sensor at each corner of the area reaches
+/// to just short of the distress beacon, which covers everything else in the area, and the other sensors are anywhere
+/// in it with beacons closer than the distress beacon.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let bound = if size <= 12 { 20 } else { 4_000_000 };
+    let hidden = (rng.range(1, bound - 1), rng.range(1, bound - 1));
+    let mut sensors = vec![(0, 0), (bound, 0), (0, bound), (bound, bound)];
+    while sensors.len() < size.max(4) {
+        let sensor = (rng.range(0, bound), rng.range(0, bound));
+        if sensor != hidden {
+            sensors.push(sensor);
+        }
+    }
+    rng.shuffle(&mut sensors);
+
+    sensors
+        .into_iter()
+        .map(|sensor @ (x, y)| {
+            let reach = distance(&sensor, &hidden) - 1;
+            let d = if x % bound == 0 && y % bound == 0 {
+                reach
+            } else {
+                rng.range(0, reach)
+            };
+            let dx = rng.range(-d, d);
+            let dy = (d - dx.abs()) * if rng.chance(0.5) { 1 } else { -1 };
+            format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+                x,
+                y,
+                x + dx,
+                y + dy
+            )
+        })
+        .collect()
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = Vec<(Coord, Coord)>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input, None).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input, None).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
-    use crate::day15::Coord;
-
-    const EXAMPLE: [(Coord, Coord); 14] = [
-        ((2, 18), (-2, 15)),
-        ((9, 16), (10, 16)),
-        ((13, 2), (15, 3)),
-        ((12, 14), (10, 16)),
-        ((10, 20), (10, 16)),
-        ((14, 17), (10, 16)),
-        ((8, 7), (2, 10)),
-        ((2, 0), (2, 10)),
-        ((0, 11), (2, 10)),
-        ((20, 14), (25, 17)),
-        ((17, 20), (21, 22)),
-        ((16, 7), (15, 3)),
-        ((14, 3), (15, 3)),
-        ((20, 1), (15, 3)),
-    ];
+    use super::{distance, generate, parse_input, part1, part2, reference_part2, search_bound};
+    use crate::generate::Rng;
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+
+    const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3
+";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(&EXAMPLE, 10), 26);
+        let input = parse_input(EXAMPLE).unwrap();
+        assert_eq!(input[0], ((2, 18), (-2, 15)));
+        assert_eq!(search_bound(&input), 20);
+        assert_eq!(part1(&input, None), 26);
+        assert_eq!(part1(&input, Some(10)), 26);
     }
 
-    const INPUT: [(Coord, Coord); 33] = [
-        ((2302110, 2237242), (2348729, 1239977)),
-        ((47903, 2473047), (-432198, 2000000)),
-        ((2363579, 1547888), (2348729, 1239977)),
-        ((3619841, 520506), (2348729, 1239977)),
-        ((3941908, 3526118), (3772294, 3485243)),
-        ((3206, 1564595), (-432198, 2000000)),
-        ((3123411, 3392077), (2977835, 3592946)),
-        ((3279053, 3984688), (2977835, 3592946)),
-        ((2968162, 3938490), (2977835, 3592946)),
-        ((1772120, 2862246), (2017966, 3158243)),
-        ((3283241, 2619168), (3172577, 2521434)),
-        ((2471642, 3890150), (2977835, 3592946)),
-        ((3163348, 3743489), (2977835, 3592946)),
-        ((2933313, 2919047), (3172577, 2521434)),
-        ((2780640, 3629927), (2977835, 3592946)),
-        ((3986978, 2079918), (3998497, 2812428)),
-        ((315464, 370694), (-550536, 260566)),
-        ((3957316, 3968366), (3772294, 3485243)),
-        ((2118533, 1074658), (2348729, 1239977)),
-        ((3494855, 3378533), (3772294, 3485243)),
-        ((2575727, 210553), (2348729, 1239977)),
-        ((3999990, 2813525), (3998497, 2812428)),
-        ((3658837, 3026912), (3998497, 2812428)),
-        ((1551619, 1701155), (2348729, 1239977)),
-        ((2625855, 3330422), (2977835, 3592946)),
-        ((3476946, 2445098), (3172577, 2521434)),
-        ((2915568, 1714113), (2348729, 1239977)),
-        ((729668, 3723377), (-997494, 3617758)),
-        ((3631681, 3801747), (3772294, 3485243)),
-        ((2270816, 3197807), (2017966, 3158243)),
-        ((3999999, 2810929), (3998497, 2812428)),
-        ((3978805, 3296024), (3772294, 3485243)),
-        ((1054910, 811769), (2348729, 1239977)),
-    ];
-
     #[test]
     fn test_part1() {
-        let res = part1(&INPUT, 2000000);
+        let input = parse_input(&read(15).unwrap()).unwrap();
+        assert_eq!(search_bound(&input), 4_000_000);
+        // A sensor outside the real search area doesn't make it any bigger.
+        assert_eq!(search_bound(&[((5_000_000, 0), (0, 0))]), 4_000_000);
+        let res = part1(&input, None);
         println!("{}", res);
         assert_eq!(res, 5083287);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(&EXAMPLE, 20), 56000011);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap(), None), 56000011);
+        assert_eq!(
+            reference_part2(&parse_input(EXAMPLE).unwrap(), None),
+            56000011
+        );
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(&INPUT, 4_000_000);
+        let res = part2(&parse_input(&read(15).unwrap()).unwrap(), None);
         println!("{}", res);
         assert_eq!(res, 13134039205729);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("Sensor at x=2, y=18: closest beacon at x=-2, y=15\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 20));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`: closest beacon is at `".to_string())
+        );
+
+        let err = parse_input("Sensor at x=2, y=18: closest beacon is at x=-2,y=15\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 47));
+        assert_eq!(err.kind, ErrorKind::Expected("`, y=`".to_string()));
+
+        let err = parse_input("").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EndOfInput("a sensor".to_string()));
+    }
+
+    /// There is exactly one place for the distress beacon, in the example's search area or the real one.
+    ///
+    #[test]
+    fn test_generate() {
+        for (size, seed) in [(4, 0), (8, 1), (12, 2), (13, 3), (30, 4)] {
+            let input = parse_input(&generate(&mut Rng::new(seed), size)).unwrap();
+            let bound = search_bound(&input);
+            assert_eq!(bound, if size <= 12 { 20 } else { 4_000_000 });
+            let found = part2(&input, None);
+            let beacon = (found / 4_000_000, found % 4_000_000);
+            assert!((0..=bound).contains(&beacon.0) && (0..=bound).contains(&beacon.1));
+            assert!(input
+                .iter()
+                .all(|(sensor, closest)| distance(sensor, &beacon) > distance(sensor, closest)));
+            if bound == 20 {
+                let uncovered = (0..=bound)
+                    .flat_map(|x| (0..=bound).map(move |y| (x, y)))
+                    .filter(|c| input.iter().all(|(s, b)| distance(s, c) > distance(s, b)))
+                    .count();
+                assert_eq!(uncovered, 1);
+            }
+        }
+    }
 }
@@ -1,72 +1,94 @@
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::search;
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
-use std::collections::{HashMap, VecDeque};
-use std::str::FromStr;
+use std::collections::HashMap;
 
+#[derive(Debug)]
 struct Tunnel {
     target: String,
     steps: i64,
 }
 
-struct Valve {
+#[derive(Debug)]
+pub struct Valve {
     bit: u64,
     flow: i64,
     tunnels: Vec<Tunnel>,
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> HashMap<String, Valve> {
+/// The valves from lines like `Valve GJ has flow rate=14; tunnels lead to valves UV, AO`, or
+/// `tunnel leads to valve UV` when there is only one. Each valve gets its own bit for tracking which are open, so
+/// there can be at most 64 of them.
+///
+fn parse_input(input: &str) -> Result<HashMap<String, Valve>, ParseError> {
     let mut res = HashMap::new();
-    for (i, line) in input.enumerate() {
-        let mut parts = line.split_whitespace();
-        let name = parts.next().unwrap().to_string();
-        let flow = i64::from_str(parts.next().unwrap()).unwrap();
-        let tunnels = parts
-            .map(|s| Tunnel {
-                target: s.to_string(),
-                steps: 1,
-            })
-            .collect_vec();
+    // The valves tunnels lead to, with the error to report if they turn out not to exist.
+    let mut references = Vec::new();
+    for (i, mut cursor) in cursors(16, input).enumerate() {
+        if i == 64 {
+            return Err(cursor.error(ErrorKind::Expected("at most 64 valves".to_string())));
+        }
+        cursor.literal("Valve ")?;
+        let name = cursor.word("a valve name")?.to_string();
+        cursor.literal(" has flow rate=")?;
+        let flow = cursor.number()?;
+        if cursor.rest().starts_with("; tunnel leads") {
+            cursor.literal("; tunnel leads to valve ")?;
+        } else {
+            cursor.literal("; tunnels lead to valves ")?;
+        }
+
+        let mut tunnels = vec![parse_tunnel(&mut cursor, &mut references)?];
+        while !cursor.is_empty() {
+            cursor.literal(", ")?;
+            tunnels.push(parse_tunnel(&mut cursor, &mut references)?);
+        }
 
         let bit = 1 << i;
         res.insert(name, Valve { bit, flow, tunnels });
     }
-    res
+
+    if let Some((err, _)) = references.into_iter().find(|(_, v)| !res.contains_key(v)) {
+        return Err(err);
+    }
+    if !res.contains_key("AA") {
+        return Err(ParseError::end_of_input(16, input, "valve `AA`"));
+    }
+    Ok(res)
 }
 
-struct CollapseState {
-    loc: String,
-    steps: i64,
+fn parse_tunnel(
+    cursor: &mut Cursor,
+    references: &mut Vec<(ParseError, String)>,
+) -> Result<Tunnel, ParseError> {
+    let err = cursor.error(ErrorKind::Expected("a known valve".to_string()));
+    let target = cursor.word("a valve name")?.to_string();
+    references.push((err, target.clone()));
+    Ok(Tunnel { target, steps: 1 })
 }
 
-fn collapse_system(system: HashMap<String, Valve>) -> HashMap<String, Valve> {
+/// Keeps only the start and the valves worth opening, with tunnels straight to each of the others that are the
+/// length of the shortest way there.
+///
+fn collapse_system(system: &HashMap<String, Valve>) -> HashMap<String, Valve> {
     let mut new_system = HashMap::new();
     for (valve_name, valve) in system.iter() {
         if valve_name.as_str() == "AA" || valve.flow > 0 {
-            let mut q = VecDeque::new();
-            let mut distances = HashMap::new();
-            distances.insert(valve_name.clone(), 0);
-            q.push_back(CollapseState {
-                loc: valve_name.clone(),
-                steps: 0,
+            let distances = search::bfs_distances([valve_name.clone()], |loc: &String| {
+                system[loc]
+                    .tunnels
+                    .iter()
+                    .map(|tunnel| tunnel.target.clone())
             });
-            while let Some(CollapseState { loc, steps }) = q.pop_back() {
-                let new_steps = steps + 1;
-                for Tunnel { target, .. } in &system.get(&loc).unwrap().tunnels {
-                    if *distances.get(target).unwrap_or(&i64::MAX) > new_steps {
-                        distances.insert(target.clone(), new_steps);
-                        q.push_back(CollapseState {
-                            loc: target.clone(),
-                            steps: new_steps,
-                        })
-                    }
-                }
-            }
 
             let mut tunnels = Vec::new();
             for (target, steps) in distances {
                 if system.get(&target).unwrap().flow > 0 {
                     tunnels.push(Tunnel {
                         target: target.clone(),
-                        steps,
+                        steps: steps as i64,
                     })
                 }
             }
@@ -102,15 +124,15 @@ fn solve_part1(system: &HashMap<String, Valve>, state: StatePart1) -> i64 {
                 opened: state.opened | target_valve.bit,
                 minutes: new_minutes,
             };
-            let score = solve_part1(&system, new_state);
+            let score = solve_part1(system, new_state);
             best_score = best_score.max(score);
         }
     }
     valve.flow * (30 - state.minutes) + best_score
 }
 
-fn part1(input: impl Iterator<Item = String>) -> i64 {
-    let system = collapse_system(parse_input(input));
+pub fn part1(system: &HashMap<String, Valve>) -> i64 {
+    let system = collapse_system(system);
     let initial_state = StatePart1 {
         loc: "AA".to_string(),
         opened: 0,
@@ -126,6 +148,7 @@ struct StatePart2 {
     opened: u64,
 }
 
+#[allow(clippy::only_used_in_recursion)]
 fn solve_part2(
     system: &HashMap<String, Valve>,
     state: StatePart2,
@@ -151,7 +174,7 @@ fn solve_part2(
         let target_valve = system.get(target).unwrap();
         let new_remaining_minutes = state.remaining_minutes[turn] - steps - 1;
         if new_remaining_minutes >= 0 && state.opened & target_valve.bit == 0 {
-            let mut remaining_minutes = state.remaining_minutes.clone();
+            let mut remaining_minutes = state.remaining_minutes;
             remaining_minutes[turn] = new_remaining_minutes;
 
             let mut locs = state.locs.clone();
@@ -162,7 +185,7 @@ fn solve_part2(
                 locs,
                 opened: state.opened | target_valve.bit,
             };
-            let score = solve_part2(&system, new_state, turn, best_scores);
+            let score = solve_part2(system, new_state, turn, best_scores);
             best_score = best_score.max(score);
         }
     }
@@ -183,8 +206,8 @@ fn solve_part2(
     score
 }
 
-fn part2(input: impl Iterator<Item = String>) -> i64 {
-    let system = collapse_system(parse_input(input));
+pub fn part2(system: &HashMap<String, Valve>) -> i64 {
+    let system = collapse_system(system);
     let initial_state = StatePart2 {
         locs: ["AA".to_string(), "AA".to_string()],
         remaining_minutes: [26, 26],
@@ -194,103 +217,135 @@ fn part2(input: impl Iterator<Item = String>) -> i64 {
     solve_part2(&system, initial_state, 0, &mut best_scores)
 }
 
+/// `size` valves (between 2 and 64) joined up so they can all be reached from `AA`, a third of them with a flow
+/// (at most 15, like the real inputs).
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n_valves = size.clamp(2, 64);
+    let mut names = vec!["AA".to_string()];
+    while names.len() < n_valves {
+        let name = (0..2)
+            .map(|_| (b'A' + rng.below(26) as u8) as char)
+            .collect::<String>();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    // A tree through all the valves and then a few more tunnels.
+    fn join(tunnels: &mut [Vec<usize>], i: usize, j: usize) {
+        if i != j && !tunnels[i].contains(&j) {
+            tunnels[i].push(j);
+            tunnels[j].push(i);
+        }
+    }
+    let mut tunnels = vec![Vec::new(); n_valves];
+    for i in 1..n_valves {
+        join(&mut tunnels, i, rng.below(i));
+    }
+    for _ in 0..n_valves / 4 {
+        join(&mut tunnels, rng.below(n_valves), rng.below(n_valves));
+    }
+    let mut flows = vec![0; n_valves];
+    let mut with_flow = (1..n_valves).collect::<Vec<_>>();
+    rng.shuffle(&mut with_flow);
+    for &i in with_flow.iter().take((n_valves / 3).clamp(1, 15)) {
+        flows[i] = rng.range(1, 25);
+    }
+
+    let mut lines = (0..n_valves)
+        .map(|i| {
+            let targets = tunnels[i].iter().map(|&j| names[j].as_str()).join(", ");
+            let tunnels = if tunnels[i].len() == 1 {
+                "tunnel leads to valve"
+            } else {
+                "tunnels lead to valves"
+            };
+            format!(
+                "Valve {} has flow rate={}; {} {}\n",
+                names[i], flows[i], tunnels, targets
+            )
+        })
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut lines);
+    lines.concat()
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Input = HashMap<String, Valve>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
-
-    const EXAMPLE: &str = "AA 0 DD II BB
-BB 13 CC AA
-CC 2 DD BB
-DD 20 CC AA EE
-EE 3 FF DD
-FF 0 EE GG
-GG 0 FF HH
-HH 22 GG
-II 0 AA JJ
-JJ 21 II
-";
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
-    const INPUT: &str = "GJ 14 UV AO MM UD GM
-HE 0 QE SV
-ET 0 LU SB
-SG 0 FF SB
-LC 0 QJ GM
-EE 13 RE BR
-AA 0 QC ZR NT JG FO
-TF 0 LU MM
-GO 0 LB AH
-QE 24 LG HE
-MI 0 KU FF
-BR 0 HY EE
-UV 0 GP GJ
-EH 0 UU FF
-WK 0 HY EL
-NT 0 FF AA
-KI 0 OQ AO
-AH 22 GO RE
-EL 0 WK SQ
-GP 0 SB UV
-GM 0 LC GJ
-LU 9 UU DW TF ET ML
-LB 0 GO VI
-QC 0 ML AA
-JJ 0 QJ DV
-MM 0 TF GJ
-VI 18 LB
-NV 0 SB KU
-VT 0 HY JG
-RE 0 AH EE
-FO 0 SB AA
-DV 10 JH UD JJ
-SQ 12 EL QA
-OQ 23 KI IV JS
-FF 3 EU NT SG MI EH
-IV 0 LG OQ
-HY 8 VT BR WK
-ML 0 LU QC
-JS 0 EM OQ
-KU 5 MI VL NV HU DW
-QA 0 OS SQ
-EU 0 FF OS
-SV 0 QJ HE
-JG 0 AA VT
-DW 0 LU KU
-UD 0 DV GJ
-QJ 17 JJ SV LC EM YA
-HU 0 JH KU
-ZR 0 AA VL
-YA 0 QJ OS
-JH 0 HU DV
-OS 15 EU YA QA
-LG 0 QE IV
-SB 4 FO SG NV GP ET
-UU 0 EH LU
-VL 0 ZR KU
-AO 0 GJ KI
-EM 0 QJ JS
+    const EXAMPLE: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II
 ";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 1651);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 1651);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(INPUT.lines().map(|v| v.to_string()));
+        let res = part1(&parse_input(&read(16).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 1728);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 1707);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 1707);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(INPUT.lines().map(|v| v.to_string()));
+        let res = part2(&parse_input(&read(16).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 2304);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("Valve AA has flow rate=0; tunnels lead to valve BB\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 25));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`; tunnels lead to valves `".to_string())
+        );
+
+        let err = parse_input("Valve AA has flow rate=0; tunnel leads to valve BB\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 49));
+        assert_eq!(err.kind, ErrorKind::Expected("a known valve".to_string()));
+
+        let err = parse_input("Valve BB has flow rate=0; tunnel leads to valve BB\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.kind, ErrorKind::EndOfInput("valve `AA`".to_string()));
+    }
 }
@@ -1,298 +1,315 @@
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::Read;
-
-fn read_file() -> String {
-    let mut file = File::open("input/day17.txt").unwrap();
-    let mut line = String::new();
-    file.read_to_string(&mut line).unwrap();
-    line
-}
+use crate::cycle;
+use crate::generate::Rng;
+use crate::grid::{Grid, Pos};
+use crate::parse::{cursors, ParseError};
+use crate::render::Frames;
+use crate::solution::{Answer, Solution};
+use std::collections::HashSet;
 
-type Coord = (i64, i64);
-
-fn new_rock(rock_type: i32, left_x: i64, bottom_y: i64) -> Vec<Coord> {
-    match rock_type {
-        0 => (0..4).map(|i| (left_x + i, bottom_y)).collect_vec(),
-        1 => vec![
-            (left_x, bottom_y + 1),
-            (left_x + 1, bottom_y + 1),
-            (left_x + 2, bottom_y + 1),
-            (left_x + 1, bottom_y),
-            (left_x + 1, bottom_y + 2),
-        ],
-        2 => vec![
-            (left_x + 2, bottom_y + 2),
-            (left_x + 2, bottom_y + 1),
-            (left_x + 2, bottom_y),
-            (left_x + 1, bottom_y),
-            (left_x, bottom_y),
-        ],
-        3 => (0..4).map(|i| (left_x, bottom_y + i)).collect_vec(),
-        4 => vec![
-            (left_x, bottom_y),
-            (left_x + 1, bottom_y),
-            (left_x, bottom_y + 1),
-            (left_x + 1, bottom_y + 1),
-        ],
-        _ => panic!(),
-    }
-}
+/// The rocks in the order they fall, as the rows they fill from the bottom up when they appear: two units from the
+/// left wall. Bit 6 is the leftmost of the 7 columns and bit 0 the rightmost.
+///
+const ROCKS: [&[u8]; 5] = [
+    &[0b0011110],
+    &[0b0001000, 0b0011100, 0b0001000],
+    &[0b0011100, 0b0000100, 0b0000100],
+    &[0b0010000, 0b0010000, 0b0010000, 0b0010000],
+    &[0b0011000, 0b0011000],
+];
 
-fn part1(directions: String) -> i64 {
-    let initial_state = State {
-        rock_type: 0,
-        dir_pos: 0,
-        chamber: "".to_string(),
-    };
-    solve(&initial_state, &directions, 2022).extra_height
-}
+/// How many rows from the top of the tower are compared to find the rocks falling the same way again. Rocks don't
+/// fall further than this into the tower.
+///
+const TOP_ROWS: usize = 50;
 
-// fn print_chamber(chamber: &HashSet<Coord>) {
-//     let height = chamber.iter().map(|(_x, y)| *y).max().unwrap();
-//     for y in (0..=height).rev() {
-//         print!("|");
-//         for x in 0..7 {
-//             if chamber.contains(&(x, y)) {
-//                 print!("#");
-//             } else {
-//                 print!(".");
-//             }
-//         }
-//         println!("|");
-//     }
-//     println!("+-------+");
-//     println!();
-// }
-
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
-struct State {
-    rock_type: i32,
-    dir_pos: usize,
-    chamber: String,
+/// The tower as it grows: a row of bits for each level, the floor first, and which rock and jet come next.
+///
+#[derive(Clone, Debug)]
+struct Chamber<'a> {
+    rows: Vec<u8>,
+    jets: &'a [u8],
+    rock: usize,
+    jet: usize,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Solution {
-    new_state: State,
-    extra_height: i64,
-    num_rocks: i64,
-}
+impl<'a> Chamber<'a> {
+    fn new(jets: &'a str) -> Chamber<'a> {
+        Chamber {
+            rows: vec![0b1111111],
+            jets: jets.as_bytes(),
+            rock: 0,
+            jet: 0,
+        }
+    }
+
+    /// The height of the tower above the floor.
+    ///
+    fn height(&self) -> usize {
+        self.rows.len() - 1
+    }
 
-fn solve(state: &State, directions: &String, num_rocks: i64) -> Solution {
-    let mut chamber = restore(&state.chamber);
-    let initial_height = chamber.iter().map(|&(_x, y)| y).max().unwrap_or(0);
-    let mut height = initial_height;
-    let width = 7;
-    let mut rock_type = state.rock_type;
-    let mut dir_iter = directions.chars().enumerate().cycle().skip(state.dir_pos);
-    let mut dir_pos = state.dir_pos;
-
-    for _i in 0..num_rocks {
-        let mut rock = new_rock(rock_type, 3, height + 4);
-        let mut turn = 0;
+    fn collides(&self, rock: &[u8], bottom: usize) -> bool {
+        rock.iter()
+            .enumerate()
+            .any(|(i, &row)| self.rows.get(bottom + i).is_some_and(|&r| r & row != 0))
+    }
+
+    /// Drops the next rock, pushed by the jets, until it comes to rest on the tower. Returns how much taller the
+    /// tower got.
+    ///
+    fn drop_rock(&mut self) -> usize {
+        let height = self.height();
+        let mut rock = ROCKS[self.rock].to_vec();
+        self.rock = (self.rock + 1) % ROCKS.len();
+        let mut bottom = height + 4;
         loop {
-            let dir = if turn == 0 {
-                let (pos, dir) = dir_iter.next().unwrap();
-                dir_pos = pos;
-                dir
-            } else {
-                'v'
-            };
-            turn = (turn + 1) % 2;
-
-            rock = match dir {
-                'v' => {
-                    let new_rock = rock.iter().map(|&(x, y)| (x, y - 1)).collect_vec();
-                    if new_rock
-                        .iter()
-                        .any(|c @ (_x, y)| *y == 0 || chamber.contains(c))
-                    {
-                        break;
-                    }
-                    new_rock
+            let pushed = match self.jets[self.jet] {
+                b'<' if rock.iter().all(|&row| row & 0b1000000 == 0) => {
+                    rock.iter().map(|&row| row << 1).collect()
                 }
-                c @ '<' | c @ '>' => {
-                    let dx = if c == '>' { 1 } else { -1 };
-                    let new_rock = rock.iter().map(|&(x, y)| (x + dx, y)).collect_vec();
-                    if new_rock
-                        .iter()
-                        .any(|c @ (x, _y)| *x <= 0 || *x > width || chamber.contains(c))
-                    {
-                        continue;
-                    }
-                    new_rock
+                b'>' if rock.iter().all(|&row| row & 1 == 0) => {
+                    rock.iter().map(|&row| row >> 1).collect()
                 }
-                _ => panic!(),
+                _ => rock.clone(),
             };
+            self.jet = (self.jet + 1) % self.jets.len();
+            if !self.collides(&pushed, bottom) {
+                rock = pushed;
+            }
+            if self.collides(&rock, bottom - 1) {
+                break;
+            }
+            bottom -= 1;
         }
 
-        for c @ (_x, y) in rock {
-            chamber.insert(c);
-            height = height.max(y);
+        for (i, row) in rock.into_iter().enumerate() {
+            match self.rows.get_mut(bottom + i) {
+                Some(r) => *r |= row,
+                None => self.rows.push(row),
+            }
         }
-        rock_type = (rock_type + 1) % 5;
+        self.height() - height
     }
 
-    Solution {
-        new_state: State {
-            rock_type,
-            dir_pos: (dir_pos + 1) % directions.len(),
-            chamber: memoize(&chamber),
-        },
-        extra_height: height - initial_height,
-        num_rocks,
+    /// What decides how the rest of the rocks fall: the next rock and jet, and the top of the tower.
+    ///
+    fn key(&self) -> (usize, usize, Vec<u8>) {
+        let top = self.rows.len().saturating_sub(TOP_ROWS);
+        (self.rock, self.jet, self.rows[top..].to_vec())
     }
 }
 
-fn solve_n(directions: &String, n: i64) -> i64 {
-    let initial_state = State {
-        rock_type: 0,
-        dir_pos: 0,
-        chamber: "".to_string(),
+pub fn part1(directions: &str) -> i64 {
+    solve_n(directions, 2022)
+}
+
+/// The height of the tower after `n` rocks, skipping ahead once the rocks start falling the same way again.
+///
+pub fn solve_n(directions: &str, n: u64) -> i64 {
+    cycle::extrapolate(Chamber::new(directions), n, Chamber::key, |chamber| {
+        chamber.drop_rock() as i64
+    })
+}
+
+pub fn part2(directions: &str) -> i64 {
+    solve_n(directions, 1_000_000_000_000)
+}
+
+/// The height of the tower after `n` rocks, dropping every one of them as the puzzle describes, a unit at a time among
+/// the points of the rocks already at rest. A check on the chamber and the cycle skipping in `solve_n`.
+///
+pub fn reference_height(directions: &str, n: usize) -> i64 {
+    // Each rock's points from its bottom left corner, `(x, y)` with y going up.
+    let rocks: [&[(i64, i64)]; 5] = [
+        &[(0, 0), (1, 0), (2, 0), (3, 0)],
+        &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
+        &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
+        &[(0, 0), (0, 1), (0, 2), (0, 3)],
+        &[(0, 0), (1, 0), (0, 1), (1, 1)],
+    ];
+    let fits = |at_rest: &HashSet<(i64, i64)>, rock: &[(i64, i64)], x: i64, y: i64| {
+        rock.iter().all(|&(dx, dy)| {
+            (0..7).contains(&(x + dx)) && y + dy >= 0 && !at_rest.contains(&(x + dx, y + dy))
+        })
     };
 
-    // Sanity checks.
-    // let sol1 = solve(&initial_state, &directions, 1000);
-    // let sol2 = solve(&sol1.new_state, &directions, 1000);
-    // let sol3 = solve(&initial_state, &directions, 2000);
-    // let sol4 = solve(&sol3.new_state, &directions, 2000);
-    // let sol5 = solve(&initial_state, &directions, 4000);
-    // assert_eq!(
-    //     memoize(&restore(&sol2.new_state.chamber)),
-    //     sol2.new_state.chamber
-    // );
-    // assert_eq!(sol2.new_state, sol3.new_state);
-    // assert_eq!(sol1.extra_height + sol2.extra_height, sol3.extra_height);
-    // assert_eq!(sol4.new_state, sol5.new_state);
-    // assert_eq!(sol3.extra_height + sol4.extra_height, sol5.extra_height);
-    //
-    // let sol1 = solve(&initial_state, &directions, 1);
-    // let sol2 = solve(&sol1.new_state, &directions, 1);
-    // let sol3 = solve(&initial_state, &directions, 2);
-    // assert_eq!(sol2.new_state, sol3.new_state);
-    // assert_eq!(sol1.extra_height + sol2.extra_height, sol3.extra_height);
-    //
-    // let sol1 = solve(&initial_state, &directions, 14);
-    // let sol2 = solve(&sol1.new_state, &directions, 23);
-    // let sol3 = solve(&sol2.new_state, &directions, 12);
-    // let sol4 = solve(&initial_state, &directions, 49);
-    // assert_eq!(sol3.new_state, sol4.new_state);
-    // assert_eq!(
-    //     sol1.extra_height + sol2.extra_height + sol3.extra_height,
-    //     sol4.extra_height
-    // );
-    // assert_eq!(sol1.num_rocks + sol2.num_rocks + sol3.num_rocks, 49);
-
-    let mut rocks_remaining: i64 = n;
+    let mut at_rest = HashSet::new();
     let mut height = 0;
-    let mut state = initial_state.clone();
-    let mut memo: HashMap<State, Solution> = HashMap::new();
-
-    while rocks_remaining > 0 {
-        let solution = match memo.get(&state) {
-            Some(solution) if solution.num_rocks <= rocks_remaining => solution.clone(),
-            _ => {
-                let solution = solve(&state, &directions, 1);
-                memo.insert(state.clone(), solution.clone());
-                solution
+    let mut jets = directions.chars().cycle();
+    for rock in rocks.iter().cycle().take(n) {
+        let (mut x, mut y) = (2, height + 3);
+        loop {
+            let dx = if jets.next() == Some('<') { -1 } else { 1 };
+            if fits(&at_rest, rock, x + dx, y) {
+                x += dx;
             }
-        };
-
-        // Transitively combine a chain of two solutions into a new solution.
-        if let Some(next_solution) = memo.get(&solution.new_state) {
-            let combined_solution = Solution {
-                new_state: next_solution.new_state.clone(),
-                extra_height: solution.extra_height + next_solution.extra_height,
-                num_rocks: solution.num_rocks + next_solution.num_rocks,
-            };
-            // let solution2 = solve(&state, &directions, combined_solution.num_rocks.clone());
-            // assert_eq!(next_solution.new_state, solution2.new_state);
-            // assert_eq!(combined_solution.extra_height, solution2.extra_height);
-            memo.insert(state.clone(), combined_solution);
+            if !fits(&at_rest, rock, x, y - 1) {
+                break;
+            }
+            y -= 1;
+        }
+        for &(dx, dy) in rock.iter() {
+            at_rest.insert((x + dx, y + dy));
+            height = height.max(y + dy + 1);
         }
-
-        // let solution = solve(&state, &directions, 1);
-        // println!("{}", n - rocks_remaining);
-        // println!("{:?}", state);
-        // println!("{:?}", solution.new_state);
-        height += solution.extra_height;
-        rocks_remaining -= solution.num_rocks;
-        state = solution.new_state.clone();
-        // let solution2 = solve(&initial_state, &directions, n - rocks_remaining);
-        // assert_eq!(solution.new_state, solution2.new_state);
-        // assert_eq!(height, solution2.extra_height);
     }
-    assert_eq!(rocks_remaining, 0);
     height
 }
 
-fn part2(directions: String) -> i64 {
-    solve_n(&directions, 1_000_000_000_000)
-}
-
-fn memoize(chamber: &HashSet<Coord>) -> String {
-    let mut buf = String::new();
-    let max_height = chamber.iter().map(|(_x, y)| *y).max().unwrap_or(0);
-    let min_height = (max_height - 50).max(1);
-    for y in (min_height..=max_height).rev() {
-        buf.push('|');
-        for x in 1..=7 {
-            if chamber.contains(&(x, y)) {
-                buf.push('#');
-            } else {
-                buf.push('.');
+/// The chamber after each of the first 2022 rocks comes to rest, for either part: the part two tower is far too tall
+/// to draw. Every frame is as tall as the final tower, with the walls `|`, the floor `+-------+` and rock `#`.
+///
+pub fn draw(directions: &str, frames: &mut Frames) {
+    let n_rocks = 2022;
+    let total_height = part1(directions) as usize;
+    let picture = |chamber: &Chamber| {
+        let mut frame = Grid::new(total_height + 1, 9, '|');
+        for row in 0..total_height {
+            let bits = chamber.rows.get(total_height - row).copied().unwrap_or(0);
+            for col in 0..7 {
+                let rock = bits & (0b1000000 >> col) != 0;
+                frame[Pos::new(row, col + 1)] = if rock { '#' } else { '.' };
             }
         }
-        buf.push('|');
-        buf.push('\n');
+        for (col, c) in "+-------+".chars().enumerate() {
+            frame[Pos::new(total_height, col)] = c;
+        }
+        frame
+    };
+
+    let mut chamber = Chamber::new(directions);
+    for _ in 0..n_rocks {
+        chamber.drop_rock();
+        frames.step(|| picture(&chamber));
+    }
+    frames.finish(|| picture(&chamber));
+}
+
+/// The jet pattern is a single line of `<` and `>`.
+///
+pub fn parse_input(input: &str) -> Result<String, ParseError> {
+    let mut cursors = cursors(17, input);
+    let mut cursor = cursors
+        .next()
+        .ok_or_else(|| ParseError::end_of_input(17, input, "a jet pattern"))?;
+    let jets = cursor.take_while(|c| c == '<' || c == '>');
+    if jets.is_empty() || !cursor.is_empty() {
+        return Err(cursor.expected("`<` or `>`"));
     }
-    buf
+    if let Some(cursor) = cursors.next() {
+        return Err(cursor.expected("the end of the input"));
+    }
+    Ok(jets.to_string())
 }
 
-fn restore(memo: &String) -> HashSet<Coord> {
-    let mut res = HashSet::new();
+/// A jet pattern of `size` random jets (at least one).
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| *rng.choose(&['<', '>']))
+        .chain(['\n'])
+        .collect()
+}
 
-    for (y, line) in memo.lines().rev().enumerate() {
-        for (x, c) in line.chars().dropping_back(1).skip(1).enumerate() {
-            if c == '#' {
-                res.insert(((x + 1) as i64, (y + 1) as i64));
-            }
-        }
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = String;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
     }
-    res
 }
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
-    use crate::day17::solve_n;
+    use super::{draw, parse_input, part1, part2, reference_height, solve_n, Chamber};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
 
     const EXAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
 
+    #[test]
+    fn test_chamber() {
+        let mut chamber = Chamber::new(EXAMPLE);
+        let growth = (0..5).map(|_| chamber.drop_rock()).collect::<Vec<_>>();
+        assert_eq!(growth, vec![1, 3, 2, 1, 2]);
+        // The tower in the puzzle after five rocks, from the floor up.
+        assert_eq!(
+            chamber.rows,
+            vec![
+                0b1111111, 0b0011110, 0b0001000, 0b0011100, 0b1111100, 0b0010100, 0b0010100,
+                0b0000100, 0b0000110, 0b0000110,
+            ]
+        );
+        assert_eq!((chamber.rock, chamber.key().2.len()), (0, 10));
+    }
+
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.to_string()), 3068);
+        assert_eq!(part1(EXAMPLE), 3068);
+        assert_eq!(reference_height(EXAMPLE, 2022), 3068);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&read(17).unwrap());
         println!("{}", res);
         assert_eq!(res, 3109);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(solve_n(&EXAMPLE.to_string(), 2022), 3068);
-        assert_eq!(solve_n(&read_file(), 2022), 3109);
-        assert_eq!(part2(EXAMPLE.to_string()), 1514285714288);
+        assert_eq!(solve_n(EXAMPLE, 2022), 3068);
+        assert_eq!(solve_n(&read(17).unwrap(), 2022), 3109);
+        assert_eq!(part2(EXAMPLE), 1514285714288);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&read(17).unwrap());
         println!("{}", res);
         assert_eq!(res, 1541449275365);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input(">>><<>=<\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 7));
+        assert_eq!(err.kind, ErrorKind::Expected("`<` or `>`".to_string()));
+
+        let err = parse_input(">>><<\n<<\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("the end of the input".to_string())
+        );
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut frames = Vec::new();
+        let mut sink = |step: usize, frame: &Frame| frames.push((step, frame.render(|&c| c)));
+        draw(
+            &parse_input(EXAMPLE).unwrap(),
+            &mut Frames::new(1000, &mut sink),
+        );
+        assert_eq!(
+            frames.iter().map(|(step, _)| *step).collect::<Vec<_>>(),
+            vec![1000, 2000, 2022]
+        );
+        let (_, last) = frames.last().unwrap();
+        assert_eq!(last.lines().count(), 3068 + 1);
+        assert!(last.starts_with("|...#...|\n|..###..|\n"));
+        assert!(last.ends_with("|..####.|\n+-------+\n"));
+    }
 }
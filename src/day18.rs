@@ -1,36 +1,35 @@
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day18.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use crate::generate::Rng;
+use crate::parse::{parse_lines, ParseError};
+use crate::search;
+use crate::solution::{Answer, Solution};
+use std::collections::{HashMap, HashSet};
 
-type Coord = [i32; 3];
+pub type Coord = [i32; 3];
 type Side = [Coord; 4];
 
-fn parse_input(iter: impl Iterator<Item = String>) -> Vec<Coord> {
-    iter.map(|s| {
-        let (x, y, z) = s
-            .split(",")
-            .map(|c| i32::from_str(c).unwrap())
-            .collect_tuple()
-            .unwrap();
-        [x, y, z]
-    })
-    .collect_vec()
+/// The cubes of lava, at least one, from lines like `2,2,2`.
+///
+fn parse_input(input: &str) -> Result<Vec<Coord>, ParseError> {
+    let cubes = parse_lines(18, input, |cursor| {
+        let x = cursor.number()?;
+        cursor.literal(",")?;
+        let y = cursor.number()?;
+        cursor.literal(",")?;
+        let z = cursor.number()?;
+        Ok([x, y, z])
+    })?;
+    if cubes.is_empty() {
+        return Err(ParseError::end_of_input(18, input, "a cube"));
+    }
+    Ok(cubes)
 }
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    let points = parse_input(input);
-    let sides = count_sides(&points);
+pub fn part1(points: &[Coord]) -> usize {
+    let sides = count_sides(points);
     sides.values().filter(|&&v| v == 1).sum()
 }
 
-fn count_sides(points: &Vec<Coord>) -> HashMap<Side, usize> {
+fn count_sides(points: &[Coord]) -> HashMap<Side, usize> {
     let mut sides = HashMap::new();
     for &[x, y, z] in points {
         let cube = sides_for_cube(x, y, z);
@@ -74,19 +73,12 @@ fn sides_for_cube(x: i32, y: i32, z: i32) -> [Side; 6] {
     cube
 }
 
-fn part2(input: impl Iterator<Item = String>) -> usize {
+pub fn part2(cubes: &[Coord]) -> usize {
     // Assume Cube 0,0,0 is air. If any of the sides of the air cube are sides of the lava cubes, add those to the
     // seen_sides set. Otherwise, the air can expand to the unblocked cubes.
-    let cubes = parse_input(input);
-    let sides: HashSet<Side> = count_sides(&cubes)
+    let sides: HashSet<Side> = count_sides(cubes)
         .iter()
-        .filter_map(|(side, count)| {
-            if *count == 1 {
-                Some(side.clone())
-            } else {
-                None
-            }
-        })
+        .filter_map(|(side, count)| if *count == 1 { Some(*side) } else { None })
         .collect();
     let min = cubes
         .iter()
@@ -101,43 +93,78 @@ fn part2(input: impl Iterator<Item = String>) -> usize {
         .unwrap()
         + 1;
 
-    let mut sides_seen = HashSet::new();
-    let mut air_cubes_seen = HashSet::new();
-    let mut q = VecDeque::new();
-    q.push_back((min, min, min));
-
-    while let Some(air_cube @ (x, y, z)) = q.pop_front() {
-        if air_cubes_seen.contains(&air_cube) {
-            continue;
-        } else {
-            air_cubes_seen.insert(air_cube.clone());
-        }
-
-        let air_sides = sides_for_cube(x, y, z);
-        for air_side in air_sides.iter() {
+    // The air spreads through every side that isn't one of the lava's, as long as it stays in the bounding box.
+    let neighbours = |&(x, y, z): &(i32, i32, i32)| {
+        let mut air_cubes = Vec::new();
+        for air_side in sides_for_cube(x, y, z).iter() {
             if sides.contains(air_side) {
-                sides_seen.insert(air_side.clone());
+                continue;
             } else if x > min && air_side.iter().all(|&c| c[0] == x - 1) {
-                q.push_back((x - 1, y, z))
+                air_cubes.push((x - 1, y, z))
             } else if x < max && air_side.iter().all(|&c| c[0] == x) {
-                q.push_back((x + 1, y, z))
+                air_cubes.push((x + 1, y, z))
             } else if y > min && air_side.iter().all(|&c| c[1] == y - 1) {
-                q.push_back((x, y - 1, z))
+                air_cubes.push((x, y - 1, z))
             } else if y < max && air_side.iter().all(|&c| c[1] == y) {
-                q.push_back((x, y + 1, z))
+                air_cubes.push((x, y + 1, z))
             } else if z > min && air_side.iter().all(|&c| c[2] == z - 1) {
-                q.push_back((x, y, z - 1))
+                air_cubes.push((x, y, z - 1))
             } else if z < max && air_side.iter().all(|&c| c[2] == z) {
-                q.push_back((x, y, z + 1))
+                air_cubes.push((x, y, z + 1))
             }
         }
-    }
+        air_cubes
+    };
+    let air_cubes = search::bfs_distances([(min, min, min)], neighbours);
+
+    let sides_seen: HashSet<Side> = air_cubes
+        .keys()
+        .flat_map(|&(x, y, z)| sides_for_cube(x, y, z))
+        .filter(|air_side| sides.contains(air_side))
+        .collect();
     sides_seen.len()
 }
 
+/// `size` different cubes (at least one) packed into a box about twice their volume, so that they touch and trap
+/// some air.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n_cubes = size.max(1);
+    let side = ((2 * n_cubes) as f64).cbrt().ceil() as i64;
+    let mut cubes = HashSet::new();
+    let mut lines = Vec::new();
+    while cubes.len() < n_cubes {
+        let cube = [0; 3].map(|_| rng.range(1, side));
+        if cubes.insert(cube) {
+            lines.push(format!("{},{},{}\n", cube[0], cube[1], cube[2]));
+        }
+    }
+    lines.concat()
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = Vec<Coord>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
     // use crate::day18::sides_for_cube;
 
     const EXAMPLE1: &str = "1,1,1
@@ -159,13 +186,13 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE1.lines().map(|v| v.to_string())), 10);
-        assert_eq!(part1(EXAMPLE2.lines().map(|v| v.to_string())), 64);
+        assert_eq!(part1(&parse_input(EXAMPLE1).unwrap()), 10);
+        assert_eq!(part1(&parse_input(EXAMPLE2).unwrap()), 64);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(18).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 4320);
     }
@@ -179,13 +206,27 @@ mod tests {
         // [(1, 1, 5), (1, 2, 5), (2, 1, 5), (2, 2, 5)],
         // [(1, 2, 4), (1, 2, 5), (2, 2, 4), (2, 2, 5)],
         // [(2, 1, 4), (2, 1, 5), (2, 2, 4), (2, 2, 5)]]
-        assert_eq!(part2(EXAMPLE2.lines().map(|v| v.to_string())), 58);
+        assert_eq!(part2(&parse_input(EXAMPLE2).unwrap()), 58);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(18).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 2456);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("1,1,1\n2,1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(err.kind, ErrorKind::EndOfLine("`,`".to_string()));
+
+        let err = parse_input("1,1,1\n2,1,x\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(err.kind, ErrorKind::Expected("a number".to_string()));
+
+        let err = parse_input("").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EndOfInput("a cube".to_string()));
+    }
 }
@@ -1,20 +1,14 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day19.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use crate::generate::Rng;
+use crate::parse::{parse_lines, Cursor, ParseError};
+use crate::solution::{Answer, Solution};
 
 // const ORE: usize = 0;
 // const CLAY: usize = 1;
 const OBSIDIAN: usize = 2;
 const GEODE: usize = 3;
-use itertools::Itertools;
-use std::str::FromStr;
 
 type Costs = [i32; 4];
-type BluePrint = [Costs; 4];
+pub type BluePrint = [Costs; 4];
 
 #[derive(Eq, PartialEq, Hash, Debug, Clone)]
 struct State {
@@ -33,21 +27,32 @@ impl State {
     }
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> Vec<BluePrint> {
-    input.map(|line| parse_line(line)).collect_vec()
+fn parse_input(input: &str) -> Result<Vec<BluePrint>, ParseError> {
+    parse_lines(19, input, parse_line)
 }
 
-fn parse_line(line: String) -> BluePrint {
-    let parts = line
-        .split(" ")
-        .filter_map(|v| i32::from_str(v).ok())
-        .collect_vec();
-    [
-        [parts[0], 0, 0, 0],
-        [parts[1], 0, 0, 0],
-        [parts[2], parts[3], 0, 0],
-        [parts[4], 0, parts[5], 0],
-    ]
+fn parse_line(cursor: &mut Cursor) -> Result<BluePrint, ParseError> {
+    cursor.literal("Blueprint ")?;
+    cursor.number::<u32>()?;
+    cursor.literal(": Each ore robot costs ")?;
+    let ore_ore = cursor.number()?;
+    cursor.literal(" ore. Each clay robot costs ")?;
+    let clay_ore = cursor.number()?;
+    cursor.literal(" ore. Each obsidian robot costs ")?;
+    let obsidian_ore = cursor.number()?;
+    cursor.literal(" ore and ")?;
+    let obsidian_clay = cursor.number()?;
+    cursor.literal(" clay. Each geode robot costs ")?;
+    let geode_ore = cursor.number()?;
+    cursor.literal(" ore and ")?;
+    let geode_obsidian = cursor.number()?;
+    cursor.literal(" obsidian.")?;
+    Ok([
+        [ore_ore, 0, 0, 0],
+        [clay_ore, 0, 0, 0],
+        [obsidian_ore, obsidian_clay, 0, 0],
+        [geode_ore, 0, geode_obsidian, 0],
+    ])
 }
 
 fn evaluate_blueprint(blue_print: &BluePrint, state: State, max_minutes: i32) -> i32 {
@@ -64,7 +69,7 @@ fn evaluate_blueprint(blue_print: &BluePrint, state: State, max_minutes: i32) ->
     for (i, costs) in blue_print.iter().enumerate().rev() {
         if let Some(n) = turns_before_purchase(&state, costs) {
             if n < max_minutes - state.minutes {
-                let mut new_robots = state.robots.clone();
+                let mut new_robots = state.robots;
                 new_robots[i] += 1;
                 let new_score = evaluate_blueprint(
                     blue_print,
@@ -101,7 +106,7 @@ fn evaluate_blueprint(blue_print: &BluePrint, state: State, max_minutes: i32) ->
                     state.inventory[2] + state.robots[2] * remaining_minutes,
                     state.inventory[3] + state.robots[3] * remaining_minutes,
                 ],
-                robots: state.robots.clone(),
+                robots: state.robots,
                 minutes: max_minutes,
             },
             max_minutes,
@@ -135,61 +140,113 @@ fn turns_before_purchase(state: &State, costs: &Costs) -> Option<i32> {
     }
 }
 
-fn part1(input: impl Iterator<Item = String>) -> i32 {
-    let blueprints = parse_input(input);
+pub fn part1(blueprints: &[BluePrint]) -> i32 {
     let mut res = 0;
 
     for (i, blueprint) in blueprints.iter().enumerate() {
         let initial_state = State::new();
-        let score = evaluate_blueprint(&blueprint, initial_state, 24);
-        println!("{}: {}", i, score);
+        let score = evaluate_blueprint(blueprint, initial_state, 24);
         res += (i + 1) as i32 * score;
     }
     res
 }
 
-fn part2(input: impl Iterator<Item = String>) -> i32 {
-    let blueprints = parse_input(input);
+pub fn part2(blueprints: &[BluePrint]) -> i32 {
     let mut res = 1;
 
-    for (i, blueprint) in blueprints.iter().take(3).enumerate() {
+    for blueprint in blueprints.iter().take(3) {
         let initial_state = State::new();
-        let score = evaluate_blueprint(&blueprint, initial_state, 32);
-        println!("{}: {}", i, score);
+        let score = evaluate_blueprint(blueprint, initial_state, 32);
         res *= score;
     }
     res
 }
 
+/// `size` blueprints (at least one) with robot costs in the ranges of the real inputs.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (1..=size.max(1))
+        .map(|i| {
+            format!(
+                "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.\n",
+                i,
+                rng.range(2, 4),
+                rng.range(2, 4),
+                rng.range(2, 4),
+                rng.range(4, 20),
+                rng.range(2, 4),
+                rng.range(4, 20)
+            )
+        })
+        .collect()
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = Vec<BluePrint>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
 Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 33);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 33);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(19).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 1023);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 62);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 62);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(19).unwrap()).unwrap());
         println!("{}", res);
         // Too low: 13260
         assert_eq!(res, 13520);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = EXAMPLE.replace("costs 3 ore and 8 clay", "costs 3 ore and 8 clays");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 108));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("` clay. Each geode robot costs `".to_string())
+        );
+
+        let err = parse_input("Blueprint 1: Each ore robot costs 4").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfLine("` ore. Each clay robot costs `".to_string())
+        );
+    }
 }
@@ -1,47 +1,532 @@
+use crate::generate::Rng;
+use crate::input::{self, InputError};
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
+use itertools::Itertools;
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day2.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-fn part1(input: impl Iterator<Item = String>) -> u32 {
-    let scores = HashMap::from([
-        ("A X".to_string(), 1 + 3),
-        ("A Y".to_string(), 2 + 6),
-        ("A Z".to_string(), 3 + 0),
-        ("B X".to_string(), 1 + 0),
-        ("B Y".to_string(), 2 + 3),
-        ("B Z".to_string(), 3 + 6),
-        ("C X".to_string(), 1 + 6),
-        ("C Y".to_string(), 2 + 0),
-        ("C Z".to_string(), 3 + 3),
-    ]);
-
-    input.map(|s| scores.get(&s).unwrap()).sum()
-}
-
-fn part2(input: impl Iterator<Item = String>) -> u32 {
-    let scores = HashMap::from([
-        ("A X".to_string(), 3 + 0), // A - Rock, X - Lose => Scissors
-        ("A Y".to_string(), 1 + 3), // A - Rock, Y - Draw => Rock
-        ("A Z".to_string(), 2 + 6), // A - Rock, Z - Win => Paper
-        ("B X".to_string(), 1 + 0), // B - Paper, X - Lose => Rock
-        ("B Y".to_string(), 2 + 3), // B - Paper, Y - Draw => Paper
-        ("B Z".to_string(), 3 + 6), // B - Paper, Z - Win => Scissors
-        ("C X".to_string(), 2 + 0), // C - Scissors, X - Lose => Paper
-        ("C Y".to_string(), 3 + 3), // C - Scissors, Y - Draw => Scissors
-        ("C Z".to_string(), 1 + 6), // C - Scissors, Z - Win => Rock
-    ]);
-
-    input.map(|s| scores.get(&s).unwrap()).sum()
+use std::fmt;
+use std::path::Path;
+
+/// The rules of the puzzle's rock paper scissors, in the format `Game::parse` reads.
+///
+pub const STANDARD: &str = "# shape <name> <points> <their letter> <our letter>
+shape rock 1 A X
+shape paper 2 B Y
+shape scissors 3 C Z
+
+# <shape> beats <shape>
+rock beats scissors
+paper beats rock
+scissors beats paper
+
+# <outcome> <points> <letter>
+loss 0 X
+draw 3 Y
+win 6 Z
+";
+
+/// Rock paper scissors lizard Spock. The opponent plays `A`-`E` and we play `V`-`Z`, and part two's `X`, `Y` and `Z`
+/// still mean lose, draw and win.
+///
+pub const RPSLS: &str = "shape rock 1 A V
+shape paper 2 B W
+shape scissors 3 C X
+shape lizard 4 D Y
+shape spock 5 E Z
+
+scissors beats paper
+paper beats rock
+rock beats lizard
+lizard beats spock
+spock beats scissors
+scissors beats lizard
+lizard beats paper
+paper beats spock
+spock beats rock
+rock beats scissors
+
+loss 0 X
+draw 3 Y
+win 6 Z
+";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Loss => "loss",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
+        }
+    }
+}
+
+/// A shape, the points for playing it and the letters for it in the strategy guide's two columns.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Shape {
+    pub name: String,
+    pub points: u32,
+    pub theirs: char,
+    pub ours: char,
+}
+
+/// What a letter in the second column of the strategy guide tells us to do.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Column {
+    Shape(usize),
+    Outcome(Outcome),
+}
+
+/// A round of the strategy guide: the index of the opponent's shape and the letter in the second column, which means
+/// a shape or an outcome depending on how the guide is read.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Round {
+    pub theirs: usize,
+    pub column: char,
+}
+
+/// The rules of a game like rock paper scissors: the shapes, which shapes beat which, and the points for each outcome.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Game {
+    pub shapes: Vec<Shape>,
+    /// Pairs of a shape and a shape it beats, in the order they were given.
+    beats: Vec<(usize, usize)>,
+    /// The points and the letter for a loss, a draw and a win.
+    outcomes: [(u32, char); 3],
+}
+
+/// Why a game's rules couldn't be loaded.
+///
+#[derive(Debug)]
+pub enum RulesError {
+    Read(InputError),
+    Parse(ParseError),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Read(error) => write!(f, "{}", error),
+            RulesError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RulesError::Read(error) => Some(error),
+            RulesError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl Game {
+    pub fn standard() -> Game {
+        Game::parse(STANDARD).unwrap()
+    }
+
+    pub fn rpsls() -> Game {
+        Game::parse(RPSLS).unwrap()
+    }
+
+    /// Reads the rules from a file in the format `Game::parse` reads.
+    ///
+    pub fn load(path: &Path) -> Result<Game, RulesError> {
+        let config = input::read_path(path).map_err(RulesError::Read)?;
+        Game::parse(&config).map_err(RulesError::Parse)
+    }
+
+    /// Reads rules with one statement to a line, in any order except that a shape must be given before it is used:
+    ///
+    /// - `shape <name> <points> <their letter> <our letter>` for each shape,
+    /// - `<shape> beats <shape>` for each pair of shapes that aren't a draw,
+    /// - `<loss|draw|win> <points> <letter>` for each outcome.
+    ///
+    /// Words are separated by spaces or tabs, and blank lines and lines starting with `#` are skipped.
+    ///
+    pub fn parse(config: &str) -> Result<Game, ParseError> {
+        let mut shapes: Vec<Shape> = Vec::new();
+        let mut beats = Vec::new();
+        let mut outcomes: [Option<(u32, char)>; 3] = [None; 3];
+        for mut cursor in cursors(2, config) {
+            if cursor.is_empty() || cursor.peek() == Some('#') {
+                continue;
+            }
+            let column = cursor.column();
+            let find = |name: &str| shapes.iter().position(|shape| shape.name == name);
+            match cursor.word("`shape`, an outcome or a shape")? {
+                "shape" => {
+                    gap(&mut cursor)?;
+                    let column = cursor.column();
+                    let name = cursor.word("a shape name")?;
+                    if find(name).is_some() {
+                        return Err(cursor.error_at(
+                            column,
+                            ErrorKind::Expected("a shape not given already".to_string()),
+                        ));
+                    }
+                    gap(&mut cursor)?;
+                    let points = cursor.number()?;
+                    gap(&mut cursor)?;
+                    let theirs = new_letter(&mut cursor, shapes.iter().map(|s| s.theirs))?;
+                    gap(&mut cursor)?;
+                    let ours = new_letter(&mut cursor, shapes.iter().map(|s| s.ours))?;
+                    shapes.push(Shape {
+                        name: name.to_string(),
+                        points,
+                        theirs,
+                        ours,
+                    });
+                }
+                word if Outcome::ALL.iter().any(|o| o.name() == word) => {
+                    let outcome = Outcome::ALL.into_iter().find(|o| o.name() == word).unwrap();
+                    if outcomes[outcome as usize].is_some() {
+                        return Err(cursor.error_at(
+                            column,
+                            ErrorKind::Expected("an outcome not given already".to_string()),
+                        ));
+                    }
+                    gap(&mut cursor)?;
+                    let points = cursor.number()?;
+                    gap(&mut cursor)?;
+                    let letter = new_letter(&mut cursor, outcomes.iter().flatten().map(|o| o.1))?;
+                    outcomes[outcome as usize] = Some((points, letter));
+                }
+                word => {
+                    let winner = find(word).ok_or_else(|| {
+                        cursor.error_at(
+                            column,
+                            ErrorKind::Expected("`shape`, an outcome or a shape".to_string()),
+                        )
+                    })?;
+                    gap(&mut cursor)?;
+                    cursor.literal("beats")?;
+                    gap(&mut cursor)?;
+                    let column = cursor.column();
+                    let loser = find(cursor.word("a shape")?);
+                    let loser = match loser {
+                        Some(loser) if loser != winner && !beats.contains(&(loser, winner)) => {
+                            loser
+                        }
+                        _ => {
+                            let expected = format!("a shape that `{}` can beat", word);
+                            return Err(cursor.error_at(column, ErrorKind::Expected(expected)));
+                        }
+                    };
+                    if !beats.contains(&(winner, loser)) {
+                        beats.push((winner, loser));
+                    }
+                }
+            }
+            cursor.end()?;
+        }
+
+        if shapes.is_empty() {
+            return Err(ParseError::end_of_input(2, config, "a shape"));
+        }
+        let mut outcome_rules = [(0, ' '); 3];
+        for (outcome, rule) in Outcome::ALL.into_iter().zip(outcomes) {
+            outcome_rules[outcome as usize] = rule.ok_or_else(|| {
+                ParseError::end_of_input(2, config, format!("the points for a {}", outcome.name()))
+            })?;
+        }
+        Ok(Game {
+            shapes,
+            beats,
+            outcomes: outcome_rules,
+        })
+    }
+
+    pub fn beats(&self, shape: usize, other: usize) -> bool {
+        self.beats.contains(&(shape, other))
+    }
+
+    /// The outcome of playing `ours` against `theirs`. Shapes that neither beat the other draw.
+    ///
+    pub fn outcome(&self, ours: usize, theirs: usize) -> Outcome {
+        if self.beats(ours, theirs) {
+            Outcome::Win
+        } else if self.beats(theirs, ours) {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn points(&self, outcome: Outcome) -> u32 {
+        self.outcomes[outcome as usize].0
+    }
+
+    /// The shape to play against `theirs` for `outcome`: the first one given that does it, if any does.
+    ///
+    pub fn shape_for(&self, theirs: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.shapes.len()).find(|&ours| self.outcome(ours, theirs) == outcome)
+    }
+
+    /// Our score for a round, or `None` if there is no shape for the outcome asked for.
+    ///
+    pub fn score_round(&self, theirs: usize, column: Column) -> Option<u32> {
+        let ours = match column {
+            Column::Shape(ours) => ours,
+            Column::Outcome(outcome) => self.shape_for(theirs, outcome)?,
+        };
+        Some(self.shapes[ours].points + self.points(self.outcome(ours, theirs)))
+    }
+
+    /// Our total score over `rounds`, reading the second column with `meaning`. `None` if the meaning leaves out a
+    /// letter or asks for an outcome that can't be had.
+    ///
+    pub fn score(&self, rounds: &[Round], meaning: &HashMap<char, Column>) -> Option<u32> {
+        rounds
+            .iter()
+            .map(|round| self.score_round(round.theirs, *meaning.get(&round.column)?))
+            .sum()
+    }
+
+    /// Part one's reading of the second column, as the shape we play.
+    ///
+    pub fn as_shapes(&self) -> HashMap<char, Column> {
+        let shapes = self.shapes.iter().enumerate();
+        shapes
+            .map(|(i, shape)| (shape.ours, Column::Shape(i)))
+            .collect()
+    }
+
+    /// Part two's reading of the second column, as how the round has to end.
+    ///
+    pub fn as_outcomes(&self) -> HashMap<char, Column> {
+        let outcomes = Outcome::ALL.into_iter();
+        outcomes
+            .map(|o| (self.outcomes[o as usize].1, Column::Outcome(o)))
+            .collect()
+    }
+
+    /// Reads a strategy guide: one round to a line, the opponent's letter and a letter of ours or of an outcome,
+    /// separated by spaces or tabs.
+    ///
+    pub fn parse_rounds(&self, input: &str) -> Result<Vec<Round>, ParseError> {
+        let theirs = self
+            .shapes
+            .iter()
+            .map(|shape| shape.theirs)
+            .collect::<Vec<_>>();
+        let mut columns = self.as_shapes().into_keys().collect::<Vec<_>>();
+        columns.extend(self.as_outcomes().into_keys());
+        columns.sort();
+        columns.dedup();
+        let (theirs_expected, columns_expected) = (one_of(&theirs), one_of(&columns));
+
+        let mut rounds = Vec::new();
+        for mut cursor in cursors(2, input) {
+            let letter = cursor.char(&theirs_expected, |c| theirs.contains(&c))?;
+            gap(&mut cursor)?;
+            let column = cursor.char(&columns_expected, |c| columns.contains(&c))?;
+            cursor.end()?;
+            rounds.push(Round {
+                theirs: theirs.iter().position(|&c| c == letter).unwrap(),
+                column,
+            });
+        }
+        Ok(rounds)
+    }
+}
+
+/// Skips one or more spaces or tabs.
+///
+fn gap(cursor: &mut Cursor) -> Result<(), ParseError> {
+    if cursor.take_while(|c| c == ' ' || c == '\t').is_empty() {
+        return Err(cursor.expected("a space"));
+    }
+    Ok(())
+}
+
+/// Reads a capital letter that isn't one of `used`.
+///
+fn new_letter(
+    cursor: &mut Cursor,
+    mut used: impl Iterator<Item = char>,
+) -> Result<char, ParseError> {
+    let column = cursor.column();
+    let letter = cursor.char("a capital letter", |c| c.is_ascii_uppercase())?;
+    if used.any(|c| c == letter) {
+        let expected = "a letter not used already".to_string();
+        return Err(cursor.error_at(column, ErrorKind::Expected(expected)));
+    }
+    Ok(letter)
+}
+
+/// The letters as they are listed in an error, e.g. "`A`, `B` or `C`".
+///
+fn one_of(letters: &[char]) -> String {
+    let quoted = letters
+        .iter()
+        .map(|c| format!("`{}`", c))
+        .collect::<Vec<_>>();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => quoted.concat(),
+    }
+}
+
+/// A way of reading the second column of a strategy guide: the meaning of each letter, and the total score it gives.
+/// The score is `None` if the reading asks for an outcome that can't be had.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Interpretation {
+    pub meaning: Vec<(char, Column)>,
+    pub score: Option<u32>,
+}
+
+impl Interpretation {
+    /// The meaning as text, e.g. `X=rock Y=paper Z=scissors`.
+    ///
+    pub fn describe(&self, game: &Game) -> String {
+        let meanings = self.meaning.iter().map(|&(letter, column)| {
+            let name = match column {
+                Column::Shape(shape) => &game.shapes[shape].name,
+                Column::Outcome(outcome) => outcome.name(),
+            };
+            format!("{}={}", letter, name)
+        });
+        meanings.collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Every way of reading the second column, as each of our letters meaning a different shape or each outcome letter
+/// a different outcome, ranked by the score it gives for `rounds`, highest first. The first is the reading to choose
+/// for the most points and the last scored one for the fewest. Readings that score the same keep the order they are
+/// made in, shapes before outcomes.
+///
+pub fn rank_interpretations(game: &Game, rounds: &[Round]) -> Vec<Interpretation> {
+    let letters = |meaning: HashMap<char, Column>| meaning.into_keys().sorted().collect_vec();
+    let shape_letters = letters(game.as_shapes());
+    let outcome_letters = letters(game.as_outcomes());
+
+    let shapes = (0..game.shapes.len())
+        .permutations(shape_letters.len())
+        .map(|shapes| {
+            let columns = shapes.into_iter().map(Column::Shape);
+            shape_letters.iter().copied().zip(columns).collect_vec()
+        });
+    let outcomes = Outcome::ALL
+        .into_iter()
+        .permutations(outcome_letters.len())
+        .map(|outcomes| {
+            let columns = outcomes.into_iter().map(Column::Outcome);
+            outcome_letters.iter().copied().zip(columns).collect_vec()
+        });
+
+    let mut ranking = shapes
+        .chain(outcomes)
+        .map(|meaning| Interpretation {
+            score: game.score(rounds, &meaning.iter().copied().collect()),
+            meaning,
+        })
+        .collect_vec();
+    ranking.sort_by_key(|interpretation| Reverse(interpretation.score));
+    ranking
+}
+
+/// The ranking as a table, a line for each reading with its place and score, marking the two readings of the puzzle.
+///
+pub fn report(game: &Game, ranking: &[Interpretation]) -> String {
+    let puzzle = [(game.as_shapes(), "part 1"), (game.as_outcomes(), "part 2")];
+    let described = ranking.iter().map(|i| i.describe(game)).collect_vec();
+    let width = described.iter().map(|d| d.len()).max().unwrap_or(0);
+    let mut res = String::new();
+    for (place, (interpretation, description)) in ranking.iter().zip(described.iter()).enumerate() {
+        let score = interpretation
+            .score
+            .map_or("-".to_string(), |score| score.to_string());
+        let meaning = interpretation
+            .meaning
+            .iter()
+            .copied()
+            .collect::<HashMap<_, _>>();
+        let part = puzzle.iter().find(|(reading, _)| *reading == meaning);
+        let line = format!(
+            "{:>3}  {:<width$}  {:>8}  {}",
+            place + 1,
+            description,
+            score,
+            part.map_or("", |(_, part)| part),
+        );
+        res.push_str(line.trim_end());
+        res.push('\n');
+    }
+    res
+}
+
+pub fn part1(rounds: &[Round]) -> u32 {
+    let game = Game::standard();
+    game.score(rounds, &game.as_shapes()).unwrap()
+}
+
+pub fn part2(rounds: &[Round]) -> u32 {
+    let game = Game::standard();
+    game.score(rounds, &game.as_outcomes()).unwrap()
+}
+
+/// Checks that every round is an opponent's shape `A`-`C` and a column `X`-`Z`.
+///
+pub fn parse_input(input: &str) -> Result<Vec<Round>, ParseError> {
+    Game::standard().parse_rounds(input)
+}
+
+/// `size` rounds (at least one) of random moves.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            format!(
+                "{} {}\n",
+                rng.choose(&['A', 'B', 'C']),
+                rng.choose(&['X', 'Y', 'Z'])
+            )
+        })
+        .collect()
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<Round>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day2::{part1, part2, read_file};
+    use crate::day2::{
+        parse_input, part1, part2, rank_interpretations, report, Column, Game, Outcome, RulesError,
+    };
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use std::path::Path;
 
     const EXAMPLE: &str = "A Y
 B X
@@ -49,25 +534,141 @@ C Z";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 15);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 15);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(2).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 13565);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 12);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 12);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(2).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 12424);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("A Y\nB W\n").unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (2, 2, 3));
+        assert_eq!(err.kind, ErrorKind::Expected("`X`, `Y` or `Z`".to_string()));
+
+        let err = parse_input("A Y \n").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+
+        let err = parse_input("AY\n").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Expected("a space".to_string()));
+        assert_eq!(parse_input("A  Y\nB\tX\n"), parse_input("A Y\nB X\n"));
+    }
+
+    #[test]
+    fn test_game() {
+        let game = Game::standard();
+        let [rock, paper, scissors] = [0, 1, 2];
+        assert!(game.beats(paper, rock) && !game.beats(rock, paper));
+        assert_eq!(game.outcome(scissors, paper), Outcome::Win);
+        assert_eq!(game.outcome(rock, paper), Outcome::Loss);
+        assert_eq!(game.outcome(rock, rock), Outcome::Draw);
+        assert_eq!(game.shape_for(rock, Outcome::Win), Some(paper));
+        assert_eq!(game.shape_for(rock, Outcome::Loss), Some(scissors));
+        assert_eq!(game.score_round(rock, Column::Shape(paper)), Some(8));
+        assert_eq!(
+            game.score_round(scissors, Column::Outcome(Outcome::Win)),
+            Some(7)
+        );
+
+        // Against Spock, lizard wins and scissors loses. Read as outcomes we draw, then lose with rock as the first
+        // shape given that does.
+        let game = Game::rpsls();
+        let rounds = game.parse_rounds("E Y\nE X\n").unwrap();
+        assert_eq!(game.score(&rounds, &game.as_shapes()), Some(10 + 3));
+        assert_eq!(game.score(&rounds, &game.as_outcomes()), Some(8 + 1));
+        let rounds = game.parse_rounds("E V\n").unwrap();
+        assert_eq!(game.score(&rounds, &game.as_outcomes()), None);
+        let err = game.parse_rounds("F X\n").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`A`, `B`, `C`, `D` or `E`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        // Rock paper scissors where a draw scores nothing and shapes score nothing either.
+        let game = Game::parse(
+            "shape rock 0 A X\nshape paper 0 B Y\nshape scissors 0 C Z\n\
+             rock beats scissors\npaper beats rock\nscissors beats paper\n\
+             win 1 Z\ndraw 0 Y\nloss 0 X\n",
+        )
+        .unwrap();
+        let rounds = game.parse_rounds(EXAMPLE).unwrap();
+        assert_eq!(game.score(&rounds, &game.as_shapes()), Some(1));
+        assert_eq!(game.score(&rounds, &game.as_outcomes()), Some(1));
+
+        let err = Game::parse("shape rock 1 A X\nshape paper 2 A Y\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 15));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a letter not used already".to_string())
+        );
+        let err = Game::parse("shape rock 1 A X\nrock beats rock\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 12));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a shape that `rock` can beat".to_string())
+        );
+        let err = Game::parse("shape rock 1 A X\npaper beats rock\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = Game::parse("shape rock 1 A X\nloss 0 X\ndraw 3 Y\n").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 1));
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfInput("the points for a win".to_string())
+        );
+
+        let err = Game::load(Path::new("no such rules.txt")).unwrap_err();
+        assert!(matches!(err, RulesError::Read(_)));
+    }
+
+    #[test]
+    fn test_rank_interpretations() {
+        let game = Game::standard();
+        let rounds = parse_input(EXAMPLE).unwrap();
+        let ranking = rank_interpretations(&game, &rounds);
+        assert_eq!(ranking.len(), 12);
+        assert_eq!(
+            ranking.iter().map(|i| i.score.unwrap()).collect::<Vec<_>>(),
+            vec![24, 18, 15, 15, 15, 15, 15, 15, 15, 15, 12, 6]
+        );
+        assert_eq!(ranking[0].describe(&game), "X=scissors Y=paper Z=rock");
+        assert_eq!(ranking[11].describe(&game), "X=rock Y=scissors Z=paper");
+        assert_eq!(
+            report(&game, &ranking).lines().collect::<Vec<_>>()[..4],
+            [
+                "  1  X=scissors Y=paper Z=rock        24",
+                "  2  X=win Y=loss Z=draw              18",
+                "  3  X=rock Y=paper Z=scissors        15  part 1",
+                "  4  X=paper Y=rock Z=scissors        15",
+            ]
+        );
+        assert!(report(&game, &ranking).contains("X=loss Y=draw Z=win              12  part 2\n"));
+
+        // RPSLS has five shapes for the five letters `V`-`Z` but only three outcomes for `X`-`Z`, so reading the
+        // columns as outcomes leaves `V` without a meaning.
+        let game = Game::rpsls();
+        let rounds = game.parse_rounds("A X\nE V\n").unwrap();
+        let ranking = rank_interpretations(&game, &rounds);
+        assert_eq!(ranking.len(), 120 + 6);
+        assert_eq!(ranking.last().unwrap().score, None);
+        assert_eq!(ranking[0].score, Some(5 + 6 + 4 + 6));
+    }
 }
@@ -1,32 +1,35 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
+use crate::generate::Rng;
+use crate::parse::{parse_lines, ParseError};
+use crate::solution::{Answer, Solution};
 
 use itertools::Itertools;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day20.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
 type Id = usize;
 
-fn parse_input(input: impl Iterator<Item = String>) -> Vec<i64> {
-    input
-        .map(|line| i64::from_str(line.as_str()).unwrap())
-        .collect_vec()
+/// The encrypted numbers, one per line. The grove coordinates are found relative to the 0, so there must be one.
+///
+fn parse_input(input: &str) -> Result<Vec<i64>, ParseError> {
+    let seq = parse_lines(20, input, |cursor| cursor.number())?;
+    if seq.contains(&0) {
+        Ok(seq)
+    } else {
+        Err(ParseError::end_of_input(20, input, "a 0"))
+    }
 }
 
-fn part1(input: impl Iterator<Item = String>, zero_index: Id) -> i64 {
-    let seq = parse_input(input);
+/// The position of the 0 in `seq` can be given, otherwise it is found.
+///
+pub fn part1(seq: &[i64], zero_index: Option<Id>) -> i64 {
+    let zero_index = zero_index.unwrap_or_else(|| find_zero(seq));
     let mut res = (0..seq.len()).collect_vec();
-    mix(&seq, &mut res);
-    coordinates(&seq, &res, zero_index)
+    mix(seq, &mut res);
+    coordinates(seq, &res, zero_index)
 }
 
-fn part2(input: impl Iterator<Item = String>, zero_index: usize) -> i64 {
+pub fn part2(seq: &[i64], zero_index: Option<Id>) -> i64 {
+    let zero_index = zero_index.unwrap_or_else(|| find_zero(seq));
     let key = 811589153;
-    let seq = parse_input(input).iter().map(|&v| v * key).collect_vec();
+    let seq = seq.iter().map(|&v| v * key).collect_vec();
     let mut res = (0..seq.len()).collect_vec();
 
     for _i in 0..10 {
@@ -35,21 +38,21 @@ fn part2(input: impl Iterator<Item = String>, zero_index: usize) -> i64 {
     coordinates(&seq, &res, zero_index)
 }
 
-fn coordinates(seq: &Vec<i64>, res: &Vec<Id>, zero_index: Id) -> i64 {
-    let index = index_of(&res, zero_index);
+fn coordinates(seq: &[i64], res: &[Id], zero_index: Id) -> i64 {
+    let index = index_of(res, zero_index);
     let v1 = seq[res[(index + 1000) as usize % res.len()]];
     let v2 = seq[res[(index + 2000) as usize % res.len()]];
     let v3 = seq[res[(index + 3000) as usize % res.len()]];
     v1 + v2 + v3
 }
 
-fn mix(seq: &Vec<i64>, res: &mut Vec<Id>) {
+fn mix(seq: &[i64], res: &mut Vec<Id>) {
     for (id, &offset) in seq.iter().enumerate() {
         if offset == 0 {
             continue;
         }
 
-        let index = index_of(&res, id);
+        let index = index_of(res, id);
         res.remove(index as usize);
 
         let len = res.len() as i64;
@@ -62,7 +65,7 @@ fn mix(seq: &Vec<i64>, res: &mut Vec<Id>) {
     }
 }
 
-fn index_of(res: &Vec<Id>, target_id: Id) -> i64 {
+fn index_of(res: &[Id], target_id: Id) -> i64 {
     res.iter()
         .enumerate()
         .find_map(|(new_i, &id)| {
@@ -75,9 +78,94 @@ fn index_of(res: &Vec<Id>, target_id: Id) -> i64 {
         .unwrap()
 }
 
+/// The numbers after `rounds` of mixing as the puzzle describes it, moving each number one place at a time around the
+/// circle. Going all the way round the other `n - 1` numbers puts a number back where it was, so only the remainder
+/// of its moves are made. A check on `mix`.
+///
+fn reference_mix(seq: &[i64], rounds: usize) -> Vec<i64> {
+    let n = seq.len();
+    let mut order = (0..n).collect_vec();
+    for _round in 0..rounds {
+        for (id, &v) in seq.iter().enumerate() {
+            if n < 2 {
+                break;
+            }
+            let mut i = order.iter().position(|&o| o == id).unwrap();
+            for _ in 0..v.unsigned_abs() % (n as u64 - 1) {
+                let j = if v > 0 { (i + 1) % n } else { (i + n - 1) % n };
+                order.swap(i, j);
+                i = j;
+            }
+        }
+    }
+    order.into_iter().map(|id| seq[id]).collect()
+}
+
+/// The sum of the numbers 1000, 2000 and 3000 after the 0, going round the circle.
+///
+fn reference_coordinates(mixed: &[i64]) -> i64 {
+    let zero = mixed.iter().position(|&v| v == 0).unwrap();
+    [1000, 2000, 3000]
+        .iter()
+        .map(|offset| mixed[(zero + offset) % mixed.len()])
+        .sum()
+}
+
+pub fn reference_part1(seq: &[i64]) -> i64 {
+    reference_coordinates(&reference_mix(seq, 1))
+}
+
+pub fn reference_part2(seq: &[i64]) -> i64 {
+    let seq = seq.iter().map(|&v| v * 811589153).collect_vec();
+    reference_coordinates(&reference_mix(&seq, 10))
+}
+
+/// The index of the 0 in the original sequence, which the grove coordinates are relative to.
+///
+fn find_zero(seq: &[i64]) -> Id {
+    seq.iter().position(|&v| v == 0).unwrap()
+}
+
+/// `size` numbers (at least one) between -10000 and 10000, with repeats but only the one 0.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n = size.max(1);
+    let zero = rng.below(n);
+    (0..n)
+        .map(|i| {
+            let v = if i == zero {
+                0
+            } else {
+                rng.range(1, 10000) * if rng.chance(0.5) { 1 } else { -1 }
+            };
+            format!("{}\n", v)
+        })
+        .collect()
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = Vec<i64>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input, None).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input, None).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{find_zero, parse_input, part1, part2, reference_part1, reference_part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "1
 2
@@ -89,25 +177,42 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string()), 5), 3);
+        let seq = parse_input(EXAMPLE).unwrap();
+        assert_eq!(find_zero(&seq), 5);
+        assert_eq!(part1(&seq, None), 3);
+        assert_eq!(part1(&seq, Some(5)), 3);
+        assert_eq!(reference_part1(&seq), 3);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file(), 4094);
+        let res = part1(&parse_input(&read(20).unwrap()).unwrap(), None);
         println!("{}", res);
         assert_eq!(res, 8372);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string()), 5), 1623178306);
+        let seq = parse_input(EXAMPLE).unwrap();
+        assert_eq!(part2(&seq, None), 1623178306);
+        assert_eq!(reference_part2(&seq), 1623178306);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file(), 4094);
+        let res = part2(&parse_input(&read(20).unwrap()).unwrap(), None);
         println!("{}", res);
         assert_eq!(res, 7865110481723);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("1\n2\n+3\n0\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.kind, ErrorKind::Expected("a number".to_string()));
+
+        let err = parse_input("1\n2\n-3\n").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 1));
+        assert_eq!(err.kind, ErrorKind::EndOfInput("a 0".to_string()));
+    }
 }
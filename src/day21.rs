@@ -1,37 +1,59 @@
-use itertools::Itertools;
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day21.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-enum Job {
+#[derive(Debug)]
+pub enum Job {
     Number(i64),
     Formula(String, String, String),
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> HashMap<String, Job> {
+fn parse_input(input: &str) -> Result<HashMap<String, Job>, ParseError> {
     let mut res = HashMap::new();
-    for line in input {
-        let (monkey, job_str) = line.split(": ").collect_tuple().unwrap();
-        let words = job_str.split_whitespace().collect_vec();
-        let job = match words.as_slice() {
-            [m1, op, m2] => Job::Formula(op.to_string(), m1.to_string(), m2.to_string()),
-            [n] => Job::Number(i64::from_str(n).unwrap()),
-            _ => panic!(),
+    // The monkeys waited on, with the error to report if they turn out not to have a job.
+    let mut references = Vec::new();
+    for mut cursor in cursors(21, input) {
+        let monkey = cursor.word("a monkey name")?;
+        cursor.literal(": ")?;
+        let job = if cursor
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '-')
+        {
+            Job::Number(cursor.number()?)
+        } else {
+            let m1 = parse_reference(&mut cursor, &mut references)?;
+            cursor.literal(" ")?;
+            let op = cursor.char("`+`, `-`, `*` or `/`", |c| "+-*/".contains(c))?;
+            cursor.literal(" ")?;
+            let m2 = parse_reference(&mut cursor, &mut references)?;
+            Job::Formula(op.to_string(), m1, m2)
         };
+        cursor.end()?;
         res.insert(monkey.to_string(), job);
     }
-    res
+
+    if let Some((err, _)) = references.into_iter().find(|(_, m)| !res.contains_key(m)) {
+        return Err(err);
+    }
+    if !res.contains_key("root") {
+        return Err(ParseError::end_of_input(21, input, "a job for `root`"));
+    }
+    Ok(res)
 }
 
-fn part1(input: impl Iterator<Item = String>) -> i64 {
-    let jobs = parse_input(input);
-    evaluate(&jobs, &"root".to_string())
+fn parse_reference(
+    cursor: &mut Cursor,
+    references: &mut Vec<(ParseError, String)>,
+) -> Result<String, ParseError> {
+    let err = cursor.error(ErrorKind::Expected("a monkey with a job".to_string()));
+    let monkey = cursor.word("a number or a monkey name")?.to_string();
+    references.push((err, monkey.clone()));
+    Ok(monkey)
+}
+
+pub fn part1(jobs: &HashMap<String, Job>) -> i64 {
+    evaluate(jobs, &"root".to_string())
 }
 
 fn evaluate(jobs: &HashMap<String, Job>, monkey: &String) -> i64 {
@@ -51,16 +73,15 @@ fn evaluate(jobs: &HashMap<String, Job>, monkey: &String) -> i64 {
     }
 }
 
-fn part2(input: impl Iterator<Item = String>) -> i64 {
-    let jobs = parse_input(input);
-    match jobs.get(&"root".to_string()).unwrap() {
+pub fn part2(jobs: &HashMap<String, Job>) -> i64 {
+    match jobs.get("root").unwrap() {
         Job::Formula(_, m1, m2) => {
-            if has_human(&jobs, m1) {
-                let value = evaluate(&jobs, m2);
-                solve_for_human(&jobs, m1, value)
+            if has_human(jobs, m1) {
+                let value = evaluate(jobs, m2);
+                solve_for_human(jobs, m1, value)
             } else {
-                let value = evaluate(&jobs, m1);
-                solve_for_human(&jobs, m2, value)
+                let value = evaluate(jobs, m1);
+                solve_for_human(jobs, m2, value)
             }
         }
         _ => panic!(),
@@ -73,8 +94,8 @@ fn solve_for_human(jobs: &HashMap<String, Job>, monkey: &String, value: i64) ->
     } else {
         match jobs.get(monkey).unwrap() {
             Job::Formula(op, m1, m2) => {
-                if has_human(&jobs, m1) {
-                    let operand = evaluate(&jobs, m2);
+                if has_human(jobs, m1) {
+                    let operand = evaluate(jobs, m2);
                     let new_value = match op.as_str() {
                         "+" => value - operand,
                         "-" => value + operand,
@@ -82,9 +103,9 @@ fn solve_for_human(jobs: &HashMap<String, Job>, monkey: &String, value: i64) ->
                         "*" => value / operand,
                         _ => panic!(),
                     };
-                    solve_for_human(&jobs, m1, new_value)
+                    solve_for_human(jobs, m1, new_value)
                 } else {
-                    let operand = evaluate(&jobs, m1);
+                    let operand = evaluate(jobs, m1);
                     let new_value = match op.as_str() {
                         "+" => value - operand,
                         "-" => -(value - operand),
@@ -92,7 +113,7 @@ fn solve_for_human(jobs: &HashMap<String, Job>, monkey: &String, value: i64) ->
                         "*" => value / operand,
                         _ => panic!(),
                     };
-                    solve_for_human(&jobs, m2, new_value)
+                    solve_for_human(jobs, m2, new_value)
                 }
             }
             _ => panic!(),
@@ -111,9 +132,138 @@ fn has_human(jobs: &HashMap<String, Job>, monkey: &String) -> bool {
     }
 }
 
+/// Monkeys for an equation with `humn` on one side of `root`, about `size` of them (at least three). The side with
+/// `humn` is built up from the answer to part two one operation at a time, dividing only when it comes out exact and
+/// never dividing by `humn`'s side, so part one works with any number for `humn` and part two has a whole answer.
+/// The other side is made to come out equal.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    struct Monkeys<'a> {
+        rng: &'a mut Rng,
+        jobs: Vec<String>,
+        names: Vec<String>,
+    }
+
+    impl Monkeys<'_> {
+        fn add(&mut self, job: String) -> String {
+            let name = loop {
+                let name = (0..4)
+                    .map(|_| (b'a' + self.rng.below(26) as u8) as char)
+                    .collect::<String>();
+                if !self.names.contains(&name) && name != "root" && name != "humn" {
+                    break name;
+                }
+            };
+            self.jobs.push(format!("{}: {}\n", name, job));
+            self.names.push(name.clone());
+            name
+        }
+
+        /// A monkey that yells `v`.
+        ///
+        fn number(&mut self, v: i64) -> String {
+            self.add(v.to_string())
+        }
+
+        /// A few monkeys working out a small number between them, with its value.
+        ///
+        fn constant(&mut self, depth: usize) -> (String, i64) {
+            if depth == 0 || self.rng.chance(0.3) {
+                let v = self.rng.range(1, 20);
+                return (self.number(v), v);
+            }
+            let (m1, v1) = self.constant(depth - 1);
+            let (m2, v2) = self.constant(depth - 1);
+            let (op, v) = match *self.rng.choose(&['+', '-', '*', '/']) {
+                '-' => ('-', v1 - v2),
+                '*' if (v1 * v2).abs() <= 1000 => ('*', v1 * v2),
+                '/' if v2 != 0 && v1 % v2 == 0 => ('/', v1 / v2),
+                _ => ('+', v1 + v2),
+            };
+            (self.add(format!("{} {} {}", m1, op, m2)), v)
+        }
+    }
+
+    let mut monkeys = Monkeys {
+        rng,
+        jobs: Vec::new(),
+        names: Vec::new(),
+    };
+    // What `humn` yells for part one, and the answer to part two.
+    let humn = monkeys.rng.range(1, 1000);
+    let mut value = monkeys.rng.range(1, 1000);
+    monkeys.jobs.push(format!("humn: {}\n", humn));
+    let mut side = "humn".to_string();
+    for _ in 0..(size / 3).max(1) {
+        let op = *monkeys.rng.choose(&['+', '-', '*', '/']);
+        let divisors = (2..=9).filter(|d| value % d == 0).collect::<Vec<_>>();
+        let (op, operand, v) = if op == '/' && !divisors.is_empty() {
+            let d = *monkeys.rng.choose(&divisors);
+            ('/', monkeys.number(d), d)
+        } else {
+            let depth = monkeys.rng.below(3);
+            let (operand, v) = monkeys.constant(depth);
+            match op {
+                '*' if v != 0 && (value * v).abs() <= 1_000_000_000 => ('*', operand, v),
+                '-' => ('-', operand, v),
+                _ => ('+', operand, v),
+            }
+        };
+        // Only the side with `humn` can be divided.
+        let humn_first = op == '/' || monkeys.rng.chance(0.5);
+        value = match (op, humn_first) {
+            ('+', _) => value + v,
+            ('-', true) => value - v,
+            ('-', false) => v - value,
+            ('*', _) => value * v,
+            _ => value / v,
+        };
+        let job = if humn_first {
+            format!("{} {} {}", side, op, operand)
+        } else {
+            format!("{} {} {}", operand, op, side)
+        };
+        side = monkeys.add(job);
+    }
+
+    let (part, v) = monkeys.constant(2);
+    let rest = monkeys.number(value - v);
+    let other = monkeys.add(format!("{} + {}", part, rest));
+    let root = if monkeys.rng.chance(0.5) {
+        format!("root: {} + {}\n", side, other)
+    } else {
+        format!("root: {} + {}\n", other, side)
+    };
+    let Monkeys { rng, mut jobs, .. } = monkeys;
+    jobs.push(root);
+    rng.shuffle(&mut jobs);
+    jobs.concat()
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Input = HashMap<String, Job>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{evaluate, generate, parse_input, part1, part2, Job};
+    use crate::generate::Rng;
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "root: pppw + sjmn
 dbpl: 5
@@ -133,25 +283,63 @@ hmdt: 32";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 152);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 152);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(21).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 331319379445180);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 301);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 301);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(21).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 3715799488132);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("root: pppw % sjmn\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 12));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`+`, `-`, `*` or `/`".to_string())
+        );
+
+        let err = parse_input("root: pppw + sjmn\npppw: 3\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 14));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a monkey with a job".to_string())
+        );
+
+        let err = parse_input("pppw: 3\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfInput("a job for `root`".to_string())
+        );
+    }
+
+    /// Yelling the answer to part two makes both sides of `root` equal.
+    ///
+    #[test]
+    fn test_generate() {
+        for seed in 0..20 {
+            let mut jobs = parse_input(&generate(&mut Rng::new(seed), 12)).unwrap();
+            jobs.insert("humn".to_string(), Job::Number(part2(&jobs)));
+            let Job::Formula(_, m1, m2) = &jobs["root"] else {
+                panic!("root has no formula");
+            };
+            assert_eq!(evaluate(&jobs, m1), evaluate(&jobs, m2), "seed {}", seed);
+        }
+    }
 }
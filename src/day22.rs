@@ -1,100 +1,117 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day22.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-type Coord = (usize, usize);
+use crate::generate::Rng;
+use crate::grid::{Grid, Pos};
+use crate::parse::{cursors, Cursor, ParseError};
+use crate::render::Frames;
+use crate::solution::{Answer, Solution};
+use std::collections::HashMap;
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
-enum Dir {
+pub enum Dir {
     Left,
     Right,
     Up,
     Down,
 }
 
+#[derive(Debug)]
 enum Instruction {
     TurnLeft,
     TurnRight,
     Move(u32),
 }
 
-struct Map {
-    walls: HashSet<Coord>,
+#[derive(Debug)]
+pub struct Map {
+    walls: Grid<bool>,
     row_limits: Vec<[usize; 2]>,
     col_limits: Vec<[usize; 2]>,
     instructions: Vec<Instruction>,
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> Map {
-    let mut walls = HashSet::new();
+/// The board, a blank line and then the path, which alternates between numbers of tiles and `L` or `R` turns.
+///
+fn parse_input(input: &str) -> Result<Map, ParseError> {
+    let mut walls = Vec::new();
     let mut row_limits = Vec::new();
     let mut col_limits = Vec::new();
-    let mut instructions = Vec::new();
-    let mut next_section = false;
+    let mut cursors = cursors(22, input);
 
-    for (row, line) in input.enumerate() {
-        if line.is_empty() {
-            next_section = true;
-            continue;
+    for (row, mut cursor) in cursors.by_ref().enumerate() {
+        if cursor.is_empty() && row > 0 {
+            break;
         }
 
-        if next_section {
-            parse_instructions(&line, &mut instructions);
-        } else {
-            let mut row_limit = [usize::MAX, usize::MIN];
-            for (col, c) in line.chars().enumerate() {
-                if col == col_limits.len() {
-                    col_limits.push([usize::MAX, usize::MIN]);
-                }
-
-                if c != ' ' {
-                    row_limit[0] = row_limit[0].min(col);
-                    row_limit[1] = row_limit[1].max(col);
-                    col_limits[col][0] = col_limits[col][0].min(row);
-                    col_limits[col][1] = col_limits[col][1].max(row);
-                }
-                if c == '#' {
-                    walls.insert((row, col));
-                }
+        let mut row_limit = [usize::MAX, usize::MIN];
+        let mut row_walls = Vec::new();
+        while !cursor.is_empty() {
+            let col = cursor.column() - 1;
+            if col == col_limits.len() {
+                col_limits.push([usize::MAX, usize::MIN]);
             }
 
-            row_limits.push(row_limit);
+            let c = cursor.char("`.`, `#` or a space", |c| ".# ".contains(c))?;
+            if c != ' ' {
+                row_limit[0] = row_limit[0].min(col);
+                row_limit[1] = row_limit[1].max(col);
+                col_limits[col][0] = col_limits[col][0].min(row);
+                col_limits[col][1] = col_limits[col][1].max(row);
+            }
+            row_walls.push(c == '#');
+        }
+        if row_limit[0] == usize::MAX {
+            return Err(cursor.expected("`.` or `#`"));
         }
+
+        row_limits.push(row_limit);
+        walls.push(row_walls);
+    }
+
+    let mut cursor = cursors
+        .next()
+        .ok_or_else(|| ParseError::end_of_input(22, input, "the path"))?;
+    let instructions = parse_instructions(&mut cursor)?;
+    cursor.end()?;
+    if let Some(cursor) = cursors.next() {
+        return Err(cursor.expected("the end of the input"));
     }
 
-    Map {
-        walls,
+    // Rows end after their last tile, so pad them out to the widest one.
+    let n_cols = col_limits.len();
+    let cells = walls
+        .into_iter()
+        .flat_map(|mut row| {
+            row.resize(n_cols, false);
+            row
+        })
+        .collect();
+    Ok(Map {
+        walls: Grid::from_vec(row_limits.len(), n_cols, cells),
         row_limits,
         col_limits,
         instructions,
-    }
+    })
 }
 
-fn parse_instructions(line: &String, instructions: &mut Vec<Instruction>) {
-    let mut num = 0;
-    for c in line.chars() {
-        if c.is_digit(10) {
-            num = num * 10 + c.to_digit(10).unwrap();
-        } else {
-            instructions.push(Instruction::Move(num));
-            if c == 'L' {
-                instructions.push(Instruction::TurnLeft);
-            } else {
-                instructions.push(Instruction::TurnRight);
-            }
-            num = 0;
+fn parse_instructions(cursor: &mut Cursor) -> Result<Vec<Instruction>, ParseError> {
+    let mut instructions = vec![Instruction::Move(cursor.number()?)];
+    while !cursor.is_empty() {
+        match cursor.char("`L` or `R`", |c| c == 'L' || c == 'R')? {
+            'L' => instructions.push(Instruction::TurnLeft),
+            _ => instructions.push(Instruction::TurnRight),
         }
+        instructions.push(Instruction::Move(cursor.number()?));
     }
-    instructions.push(Instruction::Move(num));
+    Ok(instructions)
+}
+
+pub fn part1(map: &Map) -> usize {
+    walk_flat(map, |_, _| {})
 }
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    let map = parse_input(input);
+/// Follows the path on the flat map, calling `visit` with every tile stepped onto and the facing on it, and after
+/// every turn. Returns the final password.
+///
+fn walk_flat(map: &Map, mut visit: impl FnMut(Pos, Dir)) -> usize {
     let mut row = 0;
     let mut col = map.row_limits[row][0];
     let mut dir = Dir::Right;
@@ -116,10 +133,11 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
                     } else {
                         col + 1
                     };
-                    if map.walls.contains(&(row, new_col)) {
+                    if map.walls[Pos::new(row, new_col)] {
                         break;
                     } else {
                         col = new_col;
+                        visit(Pos::new(row, col), dir);
                     }
                 }
             }
@@ -130,10 +148,11 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
                     } else {
                         col - 1
                     };
-                    if map.walls.contains(&(row, new_col)) {
+                    if map.walls[Pos::new(row, new_col)] {
                         break;
                     } else {
                         col = new_col;
+                        visit(Pos::new(row, col), dir);
                     }
                 }
             }
@@ -144,10 +163,11 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
                     } else {
                         row + 1
                     };
-                    if map.walls.contains(&(new_row, col)) {
+                    if map.walls[Pos::new(new_row, col)] {
                         break;
                     } else {
                         row = new_row;
+                        visit(Pos::new(row, col), dir);
                     }
                 }
             }
@@ -158,15 +178,19 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
                     } else {
                         row - 1
                     };
-                    if map.walls.contains(&(new_row, col)) {
+                    if map.walls[Pos::new(new_row, col)] {
                         break;
                     } else {
                         row = new_row;
+                        visit(Pos::new(row, col), dir);
                     }
                 }
             }
             _ => panic!(),
         }
+        if !matches!(instruction, Instruction::Move(_)) {
+            visit(Pos::new(row, col), dir);
+        }
     }
 
     let facing = match dir {
@@ -179,7 +203,9 @@ fn part1(input: impl Iterator<Item = String>) -> usize {
     1000 * (row + 1) + 4 * (col + 1) + facing
 }
 
-#[derive(Copy, Clone)]
+/// The edge of a side that is entered when moving onto it.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum Entrance {
     Top,
     Bottom,
@@ -187,26 +213,179 @@ enum Entrance {
     Right,
 }
 
+/// A face of the cube: where it is in the map, in faces, and the side and edge reached by walking off each of its
+/// edges.
+///
 struct Side {
     row_offset: usize,
     col_offset: usize,
     neighbors: HashMap<Dir, (usize, Entrance)>,
 }
 
-fn part2(input: impl Iterator<Item = String>, dim: usize, sides: [Side; 6]) -> usize {
-    let map = parse_input(input);
+/// The length of the edge of the cube the map folds into: the six faces cover all of the open tiles and walls.
+///
+pub fn face_size(map: &Map) -> usize {
+    let tiles: usize = map.row_limits.iter().map(|[min, max]| max - min + 1).sum();
+    (1..).find(|dim| 6 * dim * dim >= tiles).unwrap()
+}
+
+type Vec3 = [i32; 3];
+
+fn neg([x, y, z]: Vec3) -> Vec3 {
+    [-x, -y, -z]
+}
+
+/// Where a face ends up when the net is folded: the directions its columns and rows run in and the direction it
+/// faces out of the cube.
+///
+#[derive(Copy, Clone)]
+struct Orientation {
+    right: Vec3,
+    down: Vec3,
+    normal: Vec3,
+}
+
+impl Orientation {
+    /// The orientation of the face next to this one in the net, folded over the shared edge.
+    ///
+    fn fold(self, dir: Dir) -> Orientation {
+        let Orientation {
+            right,
+            down,
+            normal,
+        } = self;
+        match dir {
+            Dir::Right => Orientation {
+                right: neg(normal),
+                down,
+                normal: right,
+            },
+            Dir::Left => Orientation {
+                right: normal,
+                down,
+                normal: neg(right),
+            },
+            Dir::Down => Orientation {
+                right,
+                down: neg(normal),
+                normal: down,
+            },
+            Dir::Up => Orientation {
+                right,
+                down: normal,
+                normal: neg(down),
+            },
+        }
+    }
+
+    /// The direction the edge on the `dir` side of the face points in.
+    ///
+    fn edge(self, dir: Dir) -> Vec3 {
+        match dir {
+            Dir::Right => self.right,
+            Dir::Left => neg(self.right),
+            Dir::Down => self.down,
+            Dir::Up => neg(self.down),
+        }
+    }
+}
+
+/// Folds the map into a cube with faces of `dim` tiles, working out which sides meet at each edge. Every way of
+/// laying out a cube's faces in a net works. Returns `None` if the map isn't a cube net.
+///
+fn fold_cube(map: &Map, dim: usize) -> Option<[Side; 6]> {
+    // The faces in the map, in reading order so the first one has the start on it.
+    let on_map = |row: usize, col: usize| {
+        map.row_limits
+            .get(row * dim)
+            .is_some_and(|&[min, max]| min <= col * dim && col * dim <= max)
+    };
+    let faces = (0..map.row_limits.len().div_ceil(dim))
+        .flat_map(|row| (0..map.walls.n_cols().div_ceil(dim)).map(move |col| (row, col)))
+        .filter(|&(row, col)| on_map(row, col))
+        .collect::<Vec<_>>();
+    if faces.len() != 6 {
+        return None;
+    }
+
+    // Fold each face over from one next to it in the net that is already in place.
+    let mut orientations = vec![None; 6];
+    orientations[0] = Some(Orientation {
+        right: [1, 0, 0],
+        down: [0, 1, 0],
+        normal: [0, 0, 1],
+    });
+    let mut q = vec![0];
+    while let Some(i) = q.pop() {
+        let (row, col) = faces[i];
+        for (dir, next) in [
+            (Dir::Up, (row.wrapping_sub(1), col)),
+            (Dir::Down, (row + 1, col)),
+            (Dir::Left, (row, col.wrapping_sub(1))),
+            (Dir::Right, (row, col + 1)),
+        ] {
+            if let Some(j) = faces.iter().position(|&face| face == next) {
+                if orientations[j].is_none() {
+                    orientations[j] = Some(orientations[i].unwrap().fold(dir));
+                    q.push(j);
+                }
+            }
+        }
+    }
+    let orientations = orientations.into_iter().collect::<Option<Vec<_>>>()?;
+
+    // Walking off an edge leads to the side facing the way the edge points, onto its edge that points back the way
+    // the first side faces.
+    let mut sides = Vec::new();
+    for (i, &(row_offset, col_offset)) in faces.iter().enumerate() {
+        let mut neighbors = HashMap::new();
+        for dir in [Dir::Up, Dir::Right, Dir::Down, Dir::Left] {
+            let edge = orientations[i].edge(dir);
+            let j = orientations.iter().position(|o| o.normal == edge)?;
+            let back = orientations[i].normal;
+            let entrance = [
+                (Dir::Up, Entrance::Top),
+                (Dir::Down, Entrance::Bottom),
+                (Dir::Left, Entrance::Left),
+                (Dir::Right, Entrance::Right),
+            ]
+            .into_iter()
+            .find(|&(dir, _)| orientations[j].edge(dir) == back)?
+            .1;
+            neighbors.insert(dir, (j, entrance));
+        }
+        sides.push(Side {
+            row_offset,
+            col_offset,
+            neighbors,
+        });
+    }
+    sides.try_into().ok()
+}
+
+/// The cube's face size can be given, otherwise it is worked out from the map. `None` if the map doesn't fold into a
+/// cube with faces of that size.
+///
+pub fn part2(map: &Map, dim: Option<usize>) -> Option<usize> {
+    walk_cube(map, dim, |_, _| {})
+}
+
+/// Follows the path around the cube, calling `visit` like `walk_flat` with the positions on the map. `None` without
+/// walking if the map doesn't fold into a cube.
+///
+fn walk_cube(map: &Map, dim: Option<usize>, mut visit: impl FnMut(Pos, Dir)) -> Option<usize> {
+    let dim = dim.unwrap_or_else(|| face_size(map));
+    let sides = fold_cube(map, dim)?;
     let mut side = 0;
     let mut row = 0;
     let mut col = 0;
     let mut dir = Dir::Right;
-    let mut seen = HashMap::from([(
-        (
+    let map_pos = |side: usize, row: usize, col: usize| {
+        Pos::new(
             row + sides[side].row_offset * dim,
             col + sides[side].col_offset * dim,
-        ),
-        0,
-    )]);
-    let mut counter = 2;
+        )
+    };
 
     for instruction in map.instructions.iter() {
         match instruction {
@@ -227,14 +406,7 @@ fn part2(input: impl Iterator<Item = String>, dim: usize, sides: [Side; 6]) -> u
                         side = new_side;
                         row = new_row;
                         col = new_col;
-                        seen.insert(
-                            (
-                                row + sides[side].row_offset * dim,
-                                col + sides[side].col_offset * dim,
-                            ),
-                            counter,
-                        );
-                        counter += 1;
+                        visit(map_pos(side, row, col), dir);
                     } else {
                         break;
                     }
@@ -242,10 +414,11 @@ fn part2(input: impl Iterator<Item = String>, dim: usize, sides: [Side; 6]) -> u
             }
             _ => panic!(),
         }
+        if !matches!(instruction, Instruction::Move(_)) {
+            visit(map_pos(side, row, col), dir);
+        }
     }
 
-    print_map(&map, &seen);
-
     let facing = match dir {
         Dir::Right => 0,
         Dir::Down => 1,
@@ -253,26 +426,8 @@ fn part2(input: impl Iterator<Item = String>, dim: usize, sides: [Side; 6]) -> u
         Dir::Up => 3,
     };
 
-    1000 * (row + sides[side].row_offset * dim + 1)
-        + 4 * (col + sides[side].col_offset * dim + 1)
-        + facing
-}
-
-fn print_map(map: &Map, seen: &HashMap<(usize, usize), i32>) {
-    for (row, &[row_min, row_max]) in map.row_limits.iter().enumerate() {
-        for col in 0..=row_max {
-            if col < row_min {
-                print!(" ");
-            } else if map.walls.contains(&(row, col)) {
-                print!("#");
-            } else if let Some(_counter) = seen.get(&(row, col)) {
-                print!("*");
-            } else {
-                print!(".");
-            }
-        }
-        println!();
-    }
+    let pos = map_pos(side, row, col);
+    Some(1000 * (pos.row + 1) + 4 * (pos.col + 1) + facing)
 }
 
 fn move1(
@@ -282,7 +437,7 @@ fn move1(
     col: usize,
     dim: usize,
     sides: &[Side; 6],
-    walls: &HashSet<Coord>,
+    walls: &Grid<bool>,
 ) -> Option<(Dir, usize, usize, usize)> {
     // println!("Move1 {:?}", dir);
     let (new_dir, new_side, new_row, new_col) = match dir {
@@ -324,10 +479,10 @@ fn move1(
         }
     };
 
-    if walls.contains(&(
+    if walls[Pos::new(
         new_row + sides[new_side].row_offset * dim,
         new_col + sides[new_side].col_offset * dim,
-    )) {
+    )] {
         // println!("Wall");
         None
     } else {
@@ -344,30 +499,156 @@ fn change_sides(
     dim: usize,
 ) -> (Dir, usize, usize) {
     match (dir, entrance) {
-        (Dir::Right, Entrance::Right) => (Dir::Left, dim - row - 1, dim - 1), // Confirmed
-        (Dir::Right, Entrance::Top) => (Dir::Down, 0, dim - row - 1),         // Doesn't occur
-        (Dir::Right, Entrance::Bottom) => (Dir::Up, dim - 1, row),            // Confirmed
-        (Dir::Right, Entrance::Left) => (Dir::Right, row, 0),                 // Confirmed
-        (Dir::Left, Entrance::Right) => (Dir::Left, row, dim - 1),            // Confirmed
-        (Dir::Left, Entrance::Top) => (Dir::Down, 0, row),                    // Confirmed
-        (Dir::Left, Entrance::Bottom) => (Dir::Up, dim - 1, dim - row - 1),   // Doesn't occur
-        (Dir::Left, Entrance::Left) => (Dir::Right, dim - row - 1, 0),        // Confirmed
-        (Dir::Up, Entrance::Right) => (Dir::Left, dim - col - 1, dim - 1),    // Doesn't occur
-        (Dir::Up, Entrance::Top) => (Dir::Down, 0, dim - col - 1),            // Doesn't occur
-        (Dir::Up, Entrance::Bottom) => (Dir::Up, dim - 1, col),               // Confirmed
-        (Dir::Up, Entrance::Left) => (Dir::Right, col, 0),                    // Confirmed
-        (Dir::Down, Entrance::Right) => (Dir::Left, col, dim - 1),            // Confirmed
-        (Dir::Down, Entrance::Top) => (Dir::Down, 0, col),                    // Confirmed
-        (Dir::Down, Entrance::Bottom) => (Dir::Up, dim - 1, dim - col - 1),   // Doesn't occur
-        (Dir::Down, Entrance::Left) => (Dir::Right, dim - col - 1, 0),        // Doesn't occur
+        (Dir::Right, Entrance::Right) => (Dir::Left, dim - row - 1, dim - 1),
+        (Dir::Right, Entrance::Top) => (Dir::Down, 0, dim - row - 1),
+        (Dir::Right, Entrance::Bottom) => (Dir::Up, dim - 1, row),
+        (Dir::Right, Entrance::Left) => (Dir::Right, row, 0),
+        (Dir::Left, Entrance::Right) => (Dir::Left, row, dim - 1),
+        (Dir::Left, Entrance::Top) => (Dir::Down, 0, row),
+        (Dir::Left, Entrance::Bottom) => (Dir::Up, dim - 1, dim - row - 1),
+        (Dir::Left, Entrance::Left) => (Dir::Right, dim - row - 1, 0),
+        (Dir::Up, Entrance::Right) => (Dir::Left, dim - col - 1, dim - 1),
+        (Dir::Up, Entrance::Top) => (Dir::Down, 0, dim - col - 1),
+        (Dir::Up, Entrance::Bottom) => (Dir::Up, dim - 1, col),
+        (Dir::Up, Entrance::Left) => (Dir::Right, col, 0),
+        (Dir::Down, Entrance::Right) => (Dir::Left, col, dim - 1),
+        (Dir::Down, Entrance::Top) => (Dir::Down, 0, col),
+        (Dir::Down, Entrance::Bottom) => (Dir::Up, dim - 1, dim - col - 1),
+        (Dir::Down, Entrance::Left) => (Dir::Right, dim - col - 1, 0),
+    }
+}
+
+/// The map after each step, with the trail walked so far drawn as in the puzzle: `>`, `v`, `<` or `^` for the last
+/// facing on each tile. Part two walks around the cube, with the face size worked out from the map, and only shows the
+/// board if it doesn't fold into one.
+///
+pub fn draw(map: &Map, part: u32, frames: &mut Frames) {
+    let mut frame = Grid::new(map.walls.n_rows(), map.walls.n_cols(), ' ');
+    for (row, &[min, max]) in map.row_limits.iter().enumerate() {
+        for col in min..=max {
+            let pos = Pos::new(row, col);
+            frame[pos] = if map.walls[pos] { '#' } else { '.' };
+        }
+    }
+    frame[Pos::new(0, map.row_limits[0][0])] = '>';
+
+    let step = |pos: Pos, dir: Dir| {
+        frame[pos] = match dir {
+            Dir::Right => '>',
+            Dir::Down => 'v',
+            Dir::Left => '<',
+            Dir::Up => '^',
+        };
+        frames.step(|| frame.clone());
+    };
+    if part == 1 {
+        walk_flat(map, step);
+    } else {
+        walk_cube(map, None, step);
+    }
+    frames.finish(|| frame.clone());
+}
+
+/// A few of the ways to lay out a cube's faces, as rows of faces, including the example's and the real inputs'.
+///
+const NETS: [&[&str]; 4] = [
+    &["..#.", "###.", "..##"],
+    &[".##", ".#.", "##.", "#.."],
+    &[".#..", "####", ".#.."],
+    &["#...", "####", "...#"],
+];
+
+/// A board folding into a cube with faces `size` tiles across (at least 2), with walls on a random eighth of the
+/// tiles but never on the start, and a path of `size` moves (at least one).
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let dim = size.max(2);
+    let net = *rng.choose(&NETS);
+    let mut buf = String::new();
+    for faces in net.iter() {
+        for _ in 0..dim {
+            let row = faces
+                .chars()
+                .flat_map(|face| (0..dim).map(move |_| face))
+                .map(|face| match face {
+                    '#' if rng.chance(0.125) => '#',
+                    '#' => '.',
+                    _ => ' ',
+                })
+                .collect::<String>();
+            buf.push_str(row.trim_end());
+            buf.push('\n');
+        }
+    }
+    let start = buf.find(['.', '#']).unwrap();
+    buf.replace_range(start..start + 1, ".");
+
+    buf.push('\n');
+    for i in 0..size.max(1) {
+        if i > 0 {
+            buf.push(*rng.choose(&['L', 'R']));
+        }
+        buf.push_str(&rng.range(1, 2 * dim as i64).to_string());
+    }
+    buf.push('\n');
+    buf
+}
+
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input = Map;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        match part2(input, None) {
+            Some(password) => password.into(),
+            None => Answer::Unsolvable("the board doesn't fold into a cube".to_string()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
-    use crate::day22::{Dir, Entrance, Side};
-    use std::collections::HashMap;
+    use super::{
+        draw, face_size, fold_cube, move1, parse_input, part1, part2, Day22, Dir, Entrance,
+    };
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
+    use crate::solution::{Answer, Solution};
+
+    /// All 11 ways of laying out a cube's faces, a character to a face.
+    ///
+    const NETS: [&str; 11] = [
+        ".\n....\n.",
+        ".\n....\n .",
+        ".\n....\n  .",
+        ".\n....\n   .",
+        " .\n....\n .",
+        " .\n....\n  .",
+        "..\n ...\n .",
+        "..\n ...\n  .",
+        "..\n ...\n   .",
+        "..\n ..\n  ..",
+        "...\n  ...",
+    ];
+
+    fn opposite(dir: Dir) -> Dir {
+        match dir {
+            Dir::Left => Dir::Right,
+            Dir::Right => Dir::Left,
+            Dir::Up => Dir::Down,
+            Dir::Down => Dir::Up,
+        }
+    }
 
     const EXAMPLE: &str = "        ...#
         .#..
@@ -387,167 +668,175 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 6032);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 6032);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(22).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 93226);
     }
 
     #[test]
     fn test_part2_example() {
-        let sides = [
-            Side {
-                // 1
-                row_offset: 0,
-                col_offset: 2,
-                neighbors: HashMap::from([
-                    (Dir::Up, (2 - 1, Entrance::Top)),
-                    (Dir::Right, (6 - 1, Entrance::Right)),
-                    (Dir::Down, (4 - 1, Entrance::Top)),
-                    (Dir::Left, (3 - 1, Entrance::Top)),
-                ]),
-            },
-            Side {
-                // 2
-                row_offset: 1,
-                col_offset: 0,
-                neighbors: HashMap::from([
-                    (Dir::Up, (1 - 1, Entrance::Top)),
-                    (Dir::Right, (3 - 1, Entrance::Left)),
-                    (Dir::Down, (5 - 1, Entrance::Bottom)),
-                    (Dir::Left, (6 - 1, Entrance::Bottom)),
-                ]),
-            },
-            Side {
-                // 3
-                row_offset: 1,
-                col_offset: 1,
-                neighbors: HashMap::from([
-                    (Dir::Up, (1 - 1, Entrance::Left)),
-                    (Dir::Right, (4 - 1, Entrance::Left)),
-                    (Dir::Down, (5 - 1, Entrance::Left)),
-                    (Dir::Left, (2 - 1, Entrance::Right)),
-                ]),
-            },
-            Side {
-                // 4
-                row_offset: 1,
-                col_offset: 2,
-                neighbors: HashMap::from([
-                    (Dir::Up, (1 - 1, Entrance::Bottom)),
-                    (Dir::Right, (6 - 1, Entrance::Top)),
-                    (Dir::Down, (5 - 1, Entrance::Top)),
-                    (Dir::Left, (3 - 1, Entrance::Right)),
-                ]),
-            },
-            Side {
-                // 5
-                row_offset: 2,
-                col_offset: 2,
-                neighbors: HashMap::from([
-                    (Dir::Up, (4 - 1, Entrance::Bottom)),
-                    (Dir::Right, (6 - 1, Entrance::Left)),
-                    (Dir::Down, (2 - 1, Entrance::Bottom)),
-                    (Dir::Left, (3 - 1, Entrance::Bottom)),
-                ]),
-            },
-            Side {
-                // 6
-                row_offset: 2,
-                col_offset: 3,
-                neighbors: HashMap::from([
-                    (Dir::Up, (4 - 1, Entrance::Right)),
-                    (Dir::Right, (1 - 1, Entrance::Right)),
-                    (Dir::Down, (2 - 1, Entrance::Left)),
-                    (Dir::Left, (5 - 1, Entrance::Right)),
-                ]),
-            },
-        ];
+        let map = parse_input(EXAMPLE).unwrap();
+        assert_eq!(face_size(&map), 4);
+        assert_eq!(part2(&map, None), Some(5031));
+        assert_eq!(part2(&map, Some(4)), Some(5031));
+        assert_eq!(part2(&map, Some(3)), None);
+    }
+
+    #[test]
+    fn test_fold_cube() {
+        let map = parse_input(EXAMPLE).unwrap();
+        let sides = fold_cube(&map, 4).unwrap();
+        assert_eq!((sides[1].row_offset, sides[1].col_offset), (1, 0));
+        assert_eq!(sides[0].neighbors[&Dir::Up], (1, Entrance::Top));
+        assert_eq!(sides[3].neighbors[&Dir::Right], (5, Entrance::Top));
+        assert_eq!(sides[4].neighbors[&Dir::Down], (1, Entrance::Bottom));
+
+        // All 11 nets fold, with every edge leading back the way it came.
+        for net in NETS {
+            let map = parse_input(&format!("{}\n\n1\n", net)).unwrap();
+            assert_eq!(face_size(&map), 1);
+            let sides = fold_cube(&map, 1).unwrap_or_else(|| panic!("{:?} doesn't fold", net));
+            for (i, side) in sides.iter().enumerate() {
+                for (dir, &(j, entrance)) in side.neighbors.iter() {
+                    let back = match entrance {
+                        Entrance::Top => Dir::Up,
+                        Entrance::Bottom => Dir::Down,
+                        Entrance::Left => Dir::Left,
+                        Entrance::Right => Dir::Right,
+                    };
+                    assert_eq!(sides[j].neighbors[&back].0, i, "{:?} {:?}", net, dir);
+                }
+            }
+        }
+        let map = parse_input("....\n....\n\n1\n").unwrap();
+        assert!(fold_cube(&map, 1).is_none());
+    }
+
+    /// Walking off any tile on any edge of any net, in every way the faces can meet, and turning round leads back to
+    /// the same tile facing the other way.
+    ///
+    #[test]
+    fn test_walk_off_edges() {
+        let dim = 3;
+        for net in NETS {
+            let board = net
+                .lines()
+                .flat_map(|line| {
+                    let row = line
+                        .chars()
+                        .map(|c| c.to_string().repeat(dim))
+                        .collect::<String>();
+                    vec![row; dim]
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let map = parse_input(&format!("{}\n\n1\n", board)).unwrap();
+            let sides = fold_cube(&map, dim).unwrap();
+            for side in 0..6 {
+                for dir in [Dir::Up, Dir::Right, Dir::Down, Dir::Left] {
+                    for i in 0..dim {
+                        let (row, col) = match dir {
+                            Dir::Up => (0, i),
+                            Dir::Down => (dim - 1, i),
+                            Dir::Left => (i, 0),
+                            Dir::Right => (i, dim - 1),
+                        };
+                        let (new_dir, new_side, new_row, new_col) =
+                            move1(dir, side, row, col, dim, &sides, &map.walls).unwrap();
+                        assert_ne!(new_side, side);
+                        let back = move1(
+                            opposite(new_dir),
+                            new_side,
+                            new_row,
+                            new_col,
+                            dim,
+                            &sides,
+                            &map.walls,
+                        );
+                        assert_eq!(
+                            back,
+                            Some((opposite(dir), side, row, col)),
+                            "{:?} side {} {:?} {}",
+                            net,
+                            side,
+                            dir,
+                            i
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_a_cube() {
+        let input = "....\n....\n\n1R1\n";
+        assert_eq!(part1(&parse_input(input).unwrap()), 2000 + 4 * 2 + 1);
+        let map = Day22::parse(input).unwrap();
+        assert_eq!(Day22::part1(&map), Answer::Number(2009));
         assert_eq!(
-            part2(EXAMPLE.lines().map(|v| v.to_string()), 4, sides),
-            5031
+            Day22::part2(&map),
+            Answer::Unsolvable("the board doesn't fold into a cube".to_string())
         );
     }
 
     #[test]
     fn test_part2() {
-        let sides = [
-            Side {
-                // 1
-                row_offset: 0,
-                col_offset: 1,
-                neighbors: HashMap::from([
-                    (Dir::Up, (6 - 1, Entrance::Left)),
-                    (Dir::Right, (2 - 1, Entrance::Left)),
-                    (Dir::Down, (3 - 1, Entrance::Top)),
-                    (Dir::Left, (4 - 1, Entrance::Left)),
-                ]),
-            },
-            Side {
-                // 2
-                row_offset: 0,
-                col_offset: 2,
-                neighbors: HashMap::from([
-                    (Dir::Up, (6 - 1, Entrance::Bottom)),
-                    (Dir::Right, (5 - 1, Entrance::Right)),
-                    (Dir::Down, (3 - 1, Entrance::Right)),
-                    (Dir::Left, (1 - 1, Entrance::Right)),
-                ]),
-            },
-            Side {
-                // 3
-                row_offset: 1,
-                col_offset: 1,
-                neighbors: HashMap::from([
-                    (Dir::Up, (1 - 1, Entrance::Bottom)),
-                    (Dir::Right, (2 - 1, Entrance::Bottom)),
-                    (Dir::Down, (5 - 1, Entrance::Top)),
-                    (Dir::Left, (4 - 1, Entrance::Top)),
-                ]),
-            },
-            Side {
-                // 4
-                row_offset: 2,
-                col_offset: 0,
-                neighbors: HashMap::from([
-                    (Dir::Up, (3 - 1, Entrance::Left)),
-                    (Dir::Right, (5 - 1, Entrance::Left)),
-                    (Dir::Down, (6 - 1, Entrance::Top)),
-                    (Dir::Left, (1 - 1, Entrance::Left)),
-                ]),
-            },
-            Side {
-                // 5
-                row_offset: 2,
-                col_offset: 1,
-                neighbors: HashMap::from([
-                    (Dir::Up, (3 - 1, Entrance::Bottom)),
-                    (Dir::Right, (2 - 1, Entrance::Right)),
-                    (Dir::Down, (6 - 1, Entrance::Right)),
-                    (Dir::Left, (4 - 1, Entrance::Right)),
-                ]),
-            },
-            Side {
-                // 6
-                row_offset: 3,
-                col_offset: 0,
-                neighbors: HashMap::from([
-                    (Dir::Up, (4 - 1, Entrance::Bottom)),
-                    (Dir::Right, (5 - 1, Entrance::Bottom)),
-                    (Dir::Down, (2 - 1, Entrance::Top)),
-                    (Dir::Left, (1 - 1, Entrance::Top)),
-                ]),
-            },
-        ];
-
-        let res = part2(read_file(), 50, sides);
-        println!("{}", res);
-        assert_eq!(res, 37415);
+        let map = parse_input(&read(22).unwrap()).unwrap();
+        assert_eq!(face_size(&map), 50);
+        let res = part2(&map, None);
+        println!("{:?}", res);
+        assert_eq!(res, Some(37415));
         // Too high: 79399
         // Too high: 90288
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = EXAMPLE.replace("..#....#....", "..#....X....");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (7, 8));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`.`, `#` or a space".to_string())
+        );
+
+        let input = EXAMPLE.replace("10R5L5R10L4R5L5", "10R5L5X10");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (14, 7));
+        assert_eq!(err.kind, ErrorKind::Expected("`L` or `R`".to_string()));
+
+        let input = EXAMPLE.replace("10R5L5R10L4R5L5", "10R5L");
+        let err = parse_input(&input).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EndOfLine("a number".to_string()));
+    }
+
+    #[test]
+    fn test_draw() {
+        let map = parse_input(EXAMPLE).unwrap();
+        let mut last = None;
+        let mut sink = |_: usize, frame: &Frame| last = Some(frame.render(|&c| c));
+        draw(&map, 1, &mut Frames::new(0, &mut sink));
+        assert_eq!(
+            last.unwrap(),
+            "        >>v#    
+        .#v.    
+        #.v.    
+        ..v.    
+...#...v..v#    
+>>>v...>#.>>    
+..#v...#....    
+...>>>>v..#.    
+        ...#....
+        .....#..
+        .#......
+        ......#.
+"
+        );
+    }
 }
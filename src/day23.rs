@@ -1,47 +1,248 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::generate::Rng;
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::render::{bounding_frame, Frames};
+use crate::solution::{Answer, Solution};
+use std::collections::{HashMap, HashSet};
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day23.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+type Coord = (i32, i32);
+
+/// The elves' positions as `(row, col)`. They spread out past the edges of the scan, so they aren't kept in a grid.
+///
+pub type Elves = HashSet<Coord>;
+
+/// The directions in the order elves first consider them: the step to take and the three positions on that side
+/// that must be empty to take it.
+///
+const DIRECTIONS: [(Coord, [Coord; 3]); 4] = [
+    ((-1, 0), [(-1, -1), (-1, 0), (-1, 1)]),
+    ((1, 0), [(1, -1), (1, 0), (1, 1)]),
+    ((0, -1), [(-1, -1), (0, -1), (1, -1)]),
+    ((0, 1), [(-1, 1), (0, 1), (1, 1)]),
+];
+
+fn parse_input(input: &str) -> Result<Elves, ParseError> {
+    let grid = Grid::parse(23, input, "`#` or `.`", |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+    Ok(grid
+        .iter()
+        .filter(|&(_, &elf)| elf)
+        .map(|(pos, _)| (pos.row as i32, pos.col as i32))
+        .collect())
+}
+
+/// One round of spreading out, counting rounds from 0. Returns `None` if no elf moved.
+///
+fn spread(elves: &Elves, round: usize) -> Option<Elves> {
+    let is_empty = |(row, col): Coord, (dr, dc): Coord| !elves.contains(&(row + dr, col + dc));
+
+    // Where each elf would like to go, with the elves that stay put proposing their own position.
+    let proposals = elves
+        .iter()
+        .map(|&elf| {
+            if DIRECTIONS
+                .iter()
+                .all(|(_, checks)| checks.iter().all(|&check| is_empty(elf, check)))
+            {
+                return (elf, elf);
+            }
+            let proposal = (0..4)
+                .map(|i| DIRECTIONS[(round + i) % 4])
+                .find(|(_, checks)| checks.iter().all(|&check| is_empty(elf, check)))
+                .map_or(elf, |((dr, dc), _)| (elf.0 + dr, elf.1 + dc));
+            (elf, proposal)
+        })
+        .collect::<Vec<_>>();
+
+    let mut counts = HashMap::new();
+    for &(_, proposal) in proposals.iter() {
+        *counts.entry(proposal).or_insert(0) += 1;
+    }
+
+    let mut moved = false;
+    let new_elves = proposals
+        .into_iter()
+        .map(|(elf, proposal)| {
+            if proposal != elf && counts[&proposal] == 1 {
+                moved = true;
+                proposal
+            } else {
+                elf
+            }
+        })
+        .collect();
+    moved.then_some(new_elves)
+}
+
+/// The empty ground in the smallest rectangle that contains all the elves.
+///
+fn empty_ground(elves: &Elves) -> usize {
+    let min_row = elves.iter().map(|&(row, _)| row).min().unwrap_or(0);
+    let max_row = elves.iter().map(|&(row, _)| row).max().unwrap_or(-1);
+    let min_col = elves.iter().map(|&(_, col)| col).min().unwrap_or(0);
+    let max_col = elves.iter().map(|&(_, col)| col).max().unwrap_or(-1);
+    ((max_row - min_row + 1) * (max_col - min_col + 1)) as usize - elves.len()
 }
 
-fn part1(_input: impl Iterator<Item = String>) -> u32 {
-    unimplemented!()
+pub fn part1(elves: &Elves) -> usize {
+    let mut elves = elves.clone();
+    for round in 0..10 {
+        if let Some(new_elves) = spread(&elves, round) {
+            elves = new_elves;
+        }
+    }
+    empty_ground(&elves)
+}
+
+/// The number of the first round, counting from 1, in which no elf moves.
+///
+pub fn part2(elves: &Elves) -> usize {
+    let mut elves = elves.clone();
+    let mut round = 0;
+    while let Some(new_elves) = spread(&elves, round) {
+        elves = new_elves;
+        round += 1;
+    }
+    round + 1
 }
 
-fn part2(_input: impl Iterator<Item = String>) -> u32 {
-    unimplemented!()
+/// The elves after each round, `#` on `.`, in the bounds of everywhere they go. Part one stops after 10 rounds and
+/// part two once no elf moves.
+///
+pub fn draw(elves: &Elves, part: u32, frames: &mut Frames) {
+    let max_rounds = if part == 1 { 10 } else { usize::MAX };
+    let mut rounds = vec![elves.clone()];
+    while rounds.len() <= max_rounds {
+        match spread(rounds.last().unwrap(), rounds.len() - 1) {
+            Some(new_elves) => rounds.push(new_elves),
+            None if part == 1 => rounds.push(rounds.last().unwrap().clone()),
+            None => break,
+        }
+    }
+
+    let (empty, pos) = bounding_frame(rounds.iter().flatten().copied(), '.');
+    let picture = |elves: &Elves| {
+        let mut frame = empty.clone();
+        for &elf in elves {
+            frame[pos(elf)] = '#';
+        }
+        frame
+    };
+    for elves in rounds[1..].iter() {
+        frames.step(|| picture(elves));
+    }
+    frames.finish(|| picture(rounds.last().unwrap()));
+}
+
+/// A square `size` across (at least one) with an elf on about a third of the ground, and at least one elf.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let side = size.max(1);
+    let mut cells = (0..side * side)
+        .map(|_| if rng.chance(0.3) { '#' } else { '.' })
+        .collect::<Vec<_>>();
+    if !cells.contains(&'#') {
+        cells[rng.below(side * side)] = '#';
+    }
+    Grid::from_vec(side, side, cells).render(|&c| c)
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    type Input = Elves;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{draw, empty_ground, parse_input, part1, part2, spread};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
 
-    const EXAMPLE: &str = "
+    const EXAMPLE: &str = "....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..
 ";
 
+    #[test]
+    fn test_spread() {
+        let elves = parse_input(".....\n..##.\n..#..\n.....\n..##.\n.....\n").unwrap();
+        let elves = spread(&elves, 0).unwrap();
+        assert_eq!(
+            elves,
+            parse_input("..##.\n.....\n..#..\n...#.\n..#..\n.....\n").unwrap()
+        );
+        let elves = spread(&elves, 1).unwrap();
+        let elves = spread(&elves, 2).unwrap();
+        assert_eq!(spread(&elves, 3), None);
+        assert_eq!(empty_ground(&elves), 25);
+    }
+
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 0);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 110);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(23).unwrap()).unwrap());
         println!("{}", res);
-        // assert_eq!(res, 0);
+        assert_eq!(res, 3947);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 0);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 20);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(23).unwrap()).unwrap());
         println!("{}", res);
-        // assert_eq!(res, 0);
+        assert_eq!(res, 1012);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("..#\n.o.\n").unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (23, 2, 2));
+        assert_eq!(err.kind, ErrorKind::Expected("`#` or `.`".to_string()));
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut frames = Vec::new();
+        let mut sink = |step: usize, frame: &Frame| frames.push((step, frame.clone()));
+        draw(
+            &parse_input(EXAMPLE).unwrap(),
+            2,
+            &mut Frames::new(5, &mut sink),
+        );
+        assert_eq!(
+            frames.iter().map(|(step, _)| *step).collect::<Vec<_>>(),
+            vec![5, 10, 15, 19]
+        );
+        let (_, last) = frames.last().unwrap();
+        assert_eq!(last.iter().filter(|&(_, &c)| c == '#').count(), 22);
     }
 }
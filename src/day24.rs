@@ -1,100 +1,98 @@
-use itertools::Itertools;
-use std::cmp::{Ordering, Reverse};
-use std::collections::{BinaryHeap, HashMap};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::iter;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day24.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use crate::generate::Rng;
+use crate::grid::{Grid, Offset, Pos, DOWN, LEFT, RIGHT, UP};
+use crate::parse::{cursors, ParseError};
+use crate::render::Frames;
+use crate::search::{self, Path};
+use crate::solution::{Answer, Solution};
 
 const EMPTY: u8 = 0;
-const LEFT: u8 = 1;
-const RIGHT: u8 = 1 << 1;
-const UP: u8 = 1 << 2;
-const DOWN: u8 = 1 << 3;
+
+/// The blizzards moving in each direction, as bits of a cell's value.
+///
+const BLIZZARDS: [(u8, Offset); 4] = [(1, LEFT), (1 << 1, RIGHT), (1 << 2, UP), (1 << 3, DOWN)];
 
 type Coord = (i32, i32);
 
-#[derive(Clone, Hash, Eq, PartialEq)]
-struct State {
-    blizzards: Vec<u8>,
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct State {
+    blizzards: Grid<u8>,
     coord: Coord,
 }
 
-impl Ord for State {
-    fn cmp(&self, _other: &Self) -> Ordering {
-        Ordering::Equal
-    }
+/// The valley inside the walls and the blizzards in it at minute 0.
+///
+#[derive(Debug)]
+pub struct Valley {
+    initial_state: State,
+    n_rows: i32,
+    n_cols: i32,
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
-        Some(Ordering::Equal)
+/// The size of the valley inside the walls, `(n_rows, n_cols)`, can be given, otherwise it is taken from the input.
+///
+fn parse_input(input: &str, size: Option<(i32, i32)>) -> Result<Valley, ParseError> {
+    let (n_rows, n_cols) = size.unwrap_or_else(|| valley_size(input));
+    if n_rows < 1 || n_cols < 1 {
+        return Err(ParseError::end_of_input(
+            24,
+            input,
+            "a valley inside the walls",
+        ));
     }
-}
 
-fn parse_input(input: impl Iterator<Item = String>, n_rows: i32, n_cols: i32) -> State {
     let mut blizzards = Vec::new();
-    for (r, line) in input.enumerate() {
-        if r == 0 || r == (n_rows + 1) as usize {
-            continue;
-        }
-        for (c, ch) in line.chars().enumerate() {
-            if c == 0 || c == (n_cols + 1) as usize {
-                continue;
+    let mut cursors = cursors(24, input);
+    for r in 0..n_rows + 2 {
+        let mut cursor = cursors
+            .next()
+            .ok_or_else(|| ParseError::end_of_input(24, input, format!("{} rows", n_rows + 2)))?;
+        if r == 0 || r == n_rows + 1 {
+            for _ in 0..n_cols + 2 {
+                cursor.char("`#`, `.` or `E`", |c| "#.E".contains(c))?;
             }
-            let value = match ch {
-                '>' => RIGHT,
-                '<' => LEFT,
-                '^' => UP,
-                'v' => DOWN,
-                _ => EMPTY,
-            };
-            blizzards.push(value);
+        } else {
+            cursor.literal("#")?;
+            for _ in 0..n_cols {
+                let value = match cursor.char("a blizzard or `.`", |c| "<>^v.".contains(c))? {
+                    '<' => BLIZZARDS[0].0,
+                    '>' => BLIZZARDS[1].0,
+                    '^' => BLIZZARDS[2].0,
+                    'v' => BLIZZARDS[3].0,
+                    _ => EMPTY,
+                };
+                blizzards.push(value);
+            }
+            cursor.literal("#")?;
         }
+        cursor.end()?;
     }
-    State {
-        blizzards,
-        coord: (-1, 0),
+    if let Some(cursor) = cursors.next() {
+        return Err(cursor.expected("the end of the input"));
     }
-}
 
-fn part1(input: impl Iterator<Item = String>, n_rows: i32, n_cols: i32) -> usize {
-    let initial_state = parse_input(input, n_rows, n_cols);
-    let dest = (n_rows - 1 as i32, n_cols - 1 as i32);
-    let (_, minutes) = solve_quickest_path(initial_state, n_rows, n_cols, dest);
-    minutes
+    Ok(Valley {
+        initial_state: State {
+            blizzards: Grid::from_vec(n_rows as usize, n_cols as usize, blizzards),
+            coord: (-1, 0),
+        },
+        n_rows,
+        n_cols,
+    })
 }
 
-fn solve_quickest_path(
-    initial_state: State,
-    n_rows: i32,
-    n_cols: i32,
-    dest: Coord,
-) -> (Option<State>, usize) {
-    let mut distance = HashMap::new();
-    let mut q = BinaryHeap::new();
-    q.push((Reverse(0), initial_state));
-
-    while let Some((Reverse(minutes), state)) = q.pop() {
-        if state.coord == dest {
-            return (Some(state), minutes + 1);
-        }
-
-        if let Some(&old_minutes) = distance.get(&state) {
-            if old_minutes <= minutes {
-                continue;
-            }
-        } else {
-            distance.insert(state.clone(), minutes);
-        }
+pub fn part1(valley: &Valley) -> usize {
+    let &Valley { n_rows, n_cols, .. } = valley;
+    let dest = (n_rows - 1, n_cols - 1);
+    solve_quickest_path(valley.initial_state.clone(), dest).map_or(usize::MAX, |path| path.cost + 1)
+}
 
-        let new_blizzards = advance(&state.blizzards, n_rows, n_cols);
+/// The quickest way to `dest`, minute by minute. It takes one more minute to step out of the valley from there.
+///
+fn solve_quickest_path(initial_state: State, dest: Coord) -> Option<Path<State>> {
+    let neighbours = |state: &State| {
+        let new_blizzards = advance(&state.blizzards);
         let (row, col) = state.coord;
-
+        let mut new_states = Vec::new();
         for new_coord @ (new_row, new_col) in [
             (row - 1, col),
             (row + 1, col),
@@ -102,81 +100,174 @@ fn solve_quickest_path(
             (row, col + 1),
             (row, col),
         ] {
-            if new_coord == state.coord
-                || (new_row >= 0 && new_row < n_rows && new_col >= 0 && new_col < n_cols)
+            // Waiting is the only way to stay outside the valley, at the entrance or the exit.
+            let pos = new_blizzards.pos(new_row as isize, new_col as isize);
+            if (new_coord == state.coord || pos.is_some())
+                && pos.is_none_or(|pos| new_blizzards[pos] == EMPTY)
             {
-                let i = new_row * n_cols + new_col;
-                if i < 0 || i >= new_blizzards.len() as i32 || new_blizzards[i as usize] == EMPTY {
-                    let new_state = State {
-                        blizzards: new_blizzards.clone(),
-                        coord: new_coord,
-                    };
-                    q.push((Reverse(minutes + 1), new_state));
-                }
+                new_states.push(State {
+                    blizzards: new_blizzards.clone(),
+                    coord: new_coord,
+                });
             }
         }
-    }
+        new_states
+    };
 
-    (None, usize::MAX)
+    search::bfs([initial_state], neighbours, |state| state.coord == dest)
 }
 
-fn advance(blizzards: &Vec<u8>, n_rows: i32, n_cols: i32) -> Vec<u8> {
-    let mut new_blizzards = iter::repeat(EMPTY).take(blizzards.len()).collect_vec();
-
-    let n_rows = n_rows as usize;
-    let n_cols = n_cols as usize;
-    for (i, dirs) in blizzards.iter().enumerate() {
-        let row = i / n_cols;
-        let col = i % n_cols;
-        if LEFT & dirs != 0 {
-            let new_col = if col == 0 { n_cols - 1 } else { col - 1 };
-            let new_i = row * n_cols + new_col;
-            new_blizzards[new_i] |= LEFT;
-        }
-        if RIGHT & dirs != 0 {
-            let new_col = if col == n_cols - 1 { 0 } else { col + 1 };
-            let new_i = row * n_cols + new_col;
-            new_blizzards[new_i] |= RIGHT;
-        }
-        if UP & dirs != 0 {
-            let new_row = if row == 0 { n_rows - 1 } else { row - 1 };
-            let new_i = new_row * n_cols + col;
-            new_blizzards[new_i] |= UP;
-        }
-        if DOWN & dirs != 0 {
-            let new_row = if row == n_rows - 1 { 0 } else { row + 1 };
-            let new_i = new_row * n_cols + col;
-            new_blizzards[new_i] |= DOWN;
+fn advance(blizzards: &Grid<u8>) -> Grid<u8> {
+    let mut new_blizzards = Grid::new(blizzards.n_rows(), blizzards.n_cols(), EMPTY);
+    for (pos, &dirs) in blizzards.iter() {
+        for (bit, offset) in BLIZZARDS {
+            if bit & dirs != 0 {
+                new_blizzards[blizzards.wrapping_offset(pos, offset)] |= bit;
+            }
         }
     }
-
     new_blizzards
 }
 
-fn part2(input: impl Iterator<Item = String>, n_rows: i32, n_cols: i32) -> usize {
-    let initial_state = parse_input(input, n_rows, n_cols);
-    let dest = (n_rows - 1 as i32, n_cols - 1 as i32);
-    let (state1, minutes1) = solve_quickest_path(initial_state, n_rows, n_cols, dest);
+pub fn part2(valley: &Valley) -> usize {
+    trips(valley).iter().map(|path| path.cost).sum::<usize>() + 1
+}
+
+/// The three trips of part two: to the goal, back to the start and to the goal again. Each trip after the first
+/// starts outside the valley at the minute the last one reached its goal.
+///
+fn trips(valley: &Valley) -> Vec<Path<State>> {
+    let &Valley { n_rows, n_cols, .. } = valley;
+    let goal = (n_rows - 1, n_cols - 1);
+    let mut trips = Vec::new();
+    let mut state = valley.initial_state.clone();
+    for (dest, out) in [
+        (goal, (n_rows, n_cols - 1)),
+        ((0, 0), (-1, 0)),
+        (goal, (n_rows, n_cols - 1)),
+    ] {
+        let path = solve_quickest_path(state, dest).expect("no way through the valley");
+        state = State {
+            blizzards: path.goal().blizzards.clone(),
+            coord: out,
+        };
+        trips.push(path);
+    }
+    trips
+}
+
+/// The valley each minute along the quickest way, with the walls `#`, the expedition `E` and each blizzard drawn as
+/// its direction, or the number of blizzards where several share a place.
+///
+pub fn draw(valley: &Valley, part: u32, frames: &mut Frames) {
+    let &Valley { n_rows, n_cols, .. } = valley;
+    let trips = match part {
+        1 => vec![
+            solve_quickest_path(valley.initial_state.clone(), (n_rows - 1, n_cols - 1))
+                .expect("no way through the valley"),
+        ],
+        _ => trips(valley),
+    };
+    let picture = |state: &State| {
+        let mut frame = Grid::new(n_rows as usize + 2, n_cols as usize + 2, '#');
+        frame[Pos::new(0, 1)] = '.';
+        frame[Pos::new(n_rows as usize + 1, n_cols as usize)] = '.';
+        for (pos, &dirs) in state.blizzards.iter() {
+            let blizzards = BLIZZARDS
+                .iter()
+                .zip("<>^v".chars())
+                .filter(|((bit, _), _)| dirs & bit != 0)
+                .map(|(_, c)| c)
+                .collect::<Vec<_>>();
+            frame[Pos::new(pos.row + 1, pos.col + 1)] = match blizzards[..] {
+                [] => '.',
+                [c] => c,
+                _ => char::from_digit(blizzards.len() as u32, 10).unwrap(),
+            };
+        }
+        let (row, col) = state.coord;
+        frame[Pos::new((row + 1) as usize, (col + 1) as usize)] = 'E';
+        frame
+    };
 
-    let state1 = State {
-        blizzards: state1.unwrap().blizzards,
+    // A trip starts outside the valley in the same minute the last one reached its goal, so that minute is only
+    // drawn once.
+    let states = trips
+        .iter()
+        .enumerate()
+        .flat_map(|(i, path)| &path.states[(i > 0) as usize..])
+        .collect::<Vec<_>>();
+    for state in states[1..].iter() {
+        frames.step(|| picture(state));
+    }
+    // The last minute steps out of the valley.
+    let last = states.last().unwrap();
+    let out = State {
+        blizzards: advance(&last.blizzards),
         coord: (n_rows, n_cols - 1),
     };
+    frames.step(|| picture(&out));
+    frames.finish(|| picture(&out));
+}
 
-    let (state2, minutes2) = solve_quickest_path(state1, n_rows, n_cols, (0, 0));
+/// The number of rows and columns of the valley inside the walls.
+///
+fn valley_size(input: &str) -> (i32, i32) {
+    let n_rows = input.lines().filter(|line| !line.is_empty()).count();
+    let n_cols = input.lines().next().map(|line| line.len()).unwrap_or(0);
+    (n_rows as i32 - 2, n_cols as i32 - 2)
+}
 
-    let state2 = State {
-        blizzards: state2.unwrap().blizzards,
-        coord: (-1, 0),
-    };
+/// A valley `size` rows high (at least one) and three times as wide with blizzards on a quarter of it. As in the real
+/// inputs, no blizzard goes up or down the columns of the entrance and the exit.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let (n_rows, n_cols) = (size.max(1), (3 * size).max(2));
+    let mut buf = format!("#.{}\n", "#".repeat(n_cols));
+    for _ in 0..n_rows {
+        buf.push('#');
+        for col in 0..n_cols {
+            let blizzards = if col == 0 || col == n_cols - 1 {
+                &['<', '>'][..]
+            } else {
+                &['<', '>', '^', 'v'][..]
+            };
+            buf.push(if rng.chance(0.25) {
+                *rng.choose(blizzards)
+            } else {
+                '.'
+            });
+        }
+        buf.push_str("#\n");
+    }
+    buf.push_str(&format!("{}.#\n", "#".repeat(n_cols)));
+    buf
+}
 
-    let (_, minutes3) = solve_quickest_path(state2, n_rows, n_cols, dest);
-    minutes1 + minutes2 + minutes3 - 2
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Valley;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input, None)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{draw, parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
 
     const EXAMPLE: &str = "#E######
 #>>.<^<#
@@ -188,25 +279,63 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string()), 4, 6), 18);
+        let valley = parse_input(EXAMPLE, None).unwrap();
+        assert_eq!((valley.n_rows, valley.n_cols), (4, 6));
+        assert_eq!(part1(&valley), 18);
+        assert_eq!(part1(&parse_input(EXAMPLE, Some((4, 6))).unwrap()), 18);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file(), 25, 120);
+        let res = part1(&parse_input(&read(24).unwrap(), None).unwrap());
         println!("{}", res);
         assert_eq!(res, 281);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string()), 4, 6), 54);
+        assert_eq!(part2(&parse_input(EXAMPLE, None).unwrap()), 54);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file(), 25, 120);
+        let res = part2(&parse_input(&read(24).unwrap(), None).unwrap());
         println!("{}", res);
         assert_eq!(res, 807);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = EXAMPLE.replace("#.<..<<#", "#.<..<<.");
+        let err = parse_input(&input, None).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 8));
+        assert_eq!(err.kind, ErrorKind::Expected("`#`".to_string()));
+
+        let input = EXAMPLE.replace("#>v.><>#", "#>v.x<>#");
+        let err = parse_input(&input, None).unwrap_err();
+        assert_eq!((err.line, err.column), (4, 5));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a blizzard or `.`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_draw() {
+        let valley = parse_input(EXAMPLE, None).unwrap();
+        let mut frames = Vec::new();
+        let mut sink = |_: usize, frame: &Frame| frames.push(frame.render(|&c| c));
+        draw(&valley, 1, &mut Frames::new(1, &mut sink));
+        assert_eq!(frames.len(), 18);
+        assert_eq!(
+            frames[0],
+            "#.######\n#E>3.<.#\n#<..<<.#\n#>2.22.#\n#>v..^<#\n######.#\n"
+        );
+        assert!(frames[17].ends_with("######E#\n"));
+
+        let mut steps = 0;
+        let mut sink = |step: usize, _: &Frame| steps = step;
+        draw(&valley, 2, &mut Frames::new(0, &mut sink));
+        assert_eq!(steps, 54);
+    }
 }
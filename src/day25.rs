@@ -1,9 +1,6 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day25.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+use crate::generate::Rng;
+use crate::parse::{parse_lines, ParseError};
+use crate::solution::{Answer, Solution};
 
 fn snafu_to_decimal(snafu: String) -> i64 {
     let places = snafu.len();
@@ -43,15 +40,59 @@ fn decimal_to_snafu(dec: i64) -> String {
     String::from_iter(res.chars().rev())
 }
 
-fn part1(input: impl Iterator<Item = String>) -> String {
-    let res = input.map(|v| snafu_to_decimal(v)).sum();
+pub fn part1(input: impl Iterator<Item = String>) -> String {
+    let res = input.map(snafu_to_decimal).sum();
     decimal_to_snafu(res)
 }
 
+/// Checks that every line is a SNAFU number. The lines are kept as they are since part one converts them itself.
+///
+pub fn parse_input(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_lines(25, input, |cursor| {
+        let snafu = cursor.take_while(|c| "210-=".contains(c));
+        if snafu.is_empty() || !cursor.is_empty() {
+            return Err(cursor.expected("a SNAFU digit `2`, `1`, `0`, `-` or `=`"));
+        }
+        Ok(snafu.to_string())
+    })
+}
+
+/// `size` SNAFU numbers (at least one) of up to 13 digits.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let max = 5_i64.pow(rng.range(1, 12) as u32);
+            format!("{}\n", decimal_to_snafu(rng.range(1, max)))
+        })
+        .collect()
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input.iter().cloned()).into()
+    }
+
+    fn part2(_input: &Self::Input) -> Answer {
+        Answer::None
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, read_file};
+    use super::{parse_input, part1};
     use crate::day25::{decimal_to_snafu, snafu_to_decimal};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::solution::lines;
     use itertools::Itertools;
     use std::str::FromStr;
 
@@ -103,8 +144,18 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(lines(&read(25).unwrap()));
         println!("{}", res);
         assert_eq!(res, "2-=12=2-2-2-=0012==2".to_string());
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("1=-0-2\n12111\n1=3\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a SNAFU digit `2`, `1`, `0`, `-` or `=`".to_string())
+        );
+    }
 }
@@ -1,64 +1,238 @@
-use itertools::Itertools;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::generate::Rng;
+use crate::parse::{parse_lines, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
+use std::ops::RangeInclusive;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day3.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+/// A set of items, with a bit for each of the 52 item types: bit 0 for `a` up to bit 51 for `Z`, one less than the
+/// item's priority.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ItemSet(u64);
+
+/// The priority of an item: 1-26 for `a`-`z` and 27-52 for `A`-`Z`. Anything else isn't an item.
+///
+pub fn priority(item: char) -> Option<u64> {
+    if item.is_ascii_uppercase() {
+        Some(u64::from(item) - u64::from('A') + 27)
+    } else if item.is_ascii_lowercase() {
+        Some(u64::from(item) - u64::from('a') + 1)
+    } else {
+        None
+    }
 }
 
-fn part1(input: impl Iterator<Item = String>) -> u64 {
-    input
-        .map(|rucksack| {
-            let len = rucksack.len();
-            let (compartment1, compartment2) = rucksack.split_at(len / 2);
-            sum(bitset(compartment1) & bitset(compartment2))
-        })
-        .sum()
+impl ItemSet {
+    pub fn new() -> ItemSet {
+        ItemSet(0)
+    }
+
+    /// The set of the items in `items`, or `None` if any of them isn't an item.
+    ///
+    pub fn from_items(items: &str) -> Option<ItemSet> {
+        let mut set = ItemSet::new();
+        for item in items.chars() {
+            set.0 |= 1 << (priority(item)? - 1);
+        }
+        Some(set)
+    }
+
+    pub fn contains(self, item: char) -> bool {
+        priority(item).is_some_and(|priority| self.0 & 1 << (priority - 1) != 0)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & !other.0)
+    }
+
+    /// The items in order of priority.
+    ///
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        ('a'..='z')
+            .chain('A'..='Z')
+            .filter(move |&item| self.contains(item))
+    }
+
+    pub fn priority_sum(self) -> u64 {
+        self.iter().filter_map(priority).sum()
+    }
+
+    /// The items in all of `sets`, or none if there are no sets.
+    ///
+    pub fn common(sets: impl IntoIterator<Item = ItemSet>) -> ItemSet {
+        sets.into_iter()
+            .reduce(ItemSet::intersection)
+            .unwrap_or_default()
+    }
 }
 
-/// Find the 0-56 index of a char.
+/// The items of a rucksack split into `k` compartments of the same size, or `None` if they don't split evenly or the
+/// rucksack holds something that isn't an item.
 ///
-fn index(char: char) -> u64 {
-    if char.is_ascii_uppercase() {
-        u64::from(char) - u64::from('A') + 26
-    } else {
-        u64::from(char) - u64::from('a')
+pub fn compartments(rucksack: &str, k: usize) -> Option<Vec<ItemSet>> {
+    // Checking for items first also means every character is a byte, so the compartments can be sliced by bytes.
+    ItemSet::from_items(rucksack)?;
+    if k == 0 || !rucksack.len().is_multiple_of(k) {
+        return None;
     }
+    let size = rucksack.len() / k;
+    (0..k)
+        .map(|i| ItemSet::from_items(&rucksack[i * size..(i + 1) * size]))
+        .collect()
 }
 
-/// Set a bit for each char in the string. They are encoded 0-56.
+/// The items shared by one or more rucksacks, and the lines of the input they are on, counting from 1.
 ///
-fn bitset(str: &str) -> u64 {
-    let mut bitset = 0;
-    for c in str.chars() {
-        bitset |= 1 << index(c);
-    }
-    bitset
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Shared {
+    pub lines: RangeInclusive<usize>,
+    pub items: ItemSet,
 }
 
-/// Sum the bits in the bit set.
+/// The items in every one of the `k` compartments of each rucksack that splits evenly and holds only items.
 ///
-fn sum(bitset: u64) -> u64 {
-    let mut score = 0;
-    let mut bitset = bitset;
-    while bitset > 0 {
-        bitset >>= 1;
-        score += 1;
-    }
-    score
+pub fn misplaced(rucksacks: &[String], k: usize) -> Vec<Shared> {
+    let with_lines = rucksacks.iter().zip(1..);
+    with_lines
+        .filter_map(|(rucksack, line)| {
+            let items = ItemSet::common(compartments(rucksack, k)?);
+            Some(Shared {
+                lines: line..=line,
+                items,
+            })
+        })
+        .collect()
 }
 
-fn part2(input: impl Iterator<Item = String>) -> u64 {
-    input
-        .tuples()
-        .map(|(bag1, bag2, bag3)| sum(bitset(&bag1[..]) & bitset(&bag2[..]) & bitset(&bag3[..])))
+/// The badges of each group of `size` elves in turn: the items all of the group carry. Rucksacks left over after the
+/// last whole group aren't in a group, and groups with a rucksack holding something that isn't an item are skipped.
+///
+pub fn badges(rucksacks: &[String], size: usize) -> Vec<Shared> {
+    if size == 0 {
+        return Vec::new();
+    }
+    let groups = rucksacks.chunks_exact(size).enumerate();
+    groups
+        .filter_map(|(i, group)| {
+            let sets = group.iter().map(|rucksack| ItemSet::from_items(rucksack));
+            Some(Shared {
+                lines: i * size + 1..=(i + 1) * size,
+                items: ItemSet::common(sets.collect::<Option<Vec<_>>>()?),
+            })
+        })
+        .collect()
+}
+
+pub fn part1(rucksacks: &[String]) -> u64 {
+    let misplaced = misplaced(rucksacks, 2);
+    misplaced
+        .iter()
+        .map(|shared| shared.items.priority_sum())
         .sum()
 }
 
+pub fn part2(rucksacks: &[String]) -> u64 {
+    let badges = badges(rucksacks, 3);
+    badges
+        .iter()
+        .map(|shared| shared.items.priority_sum())
+        .sum()
+}
+
+/// Checks that every rucksack holds only letters and can be split into two equal compartments.
+///
+pub fn parse_input(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_lines(3, input, |cursor| {
+        let items = cursor.take_while(|c| c.is_ascii_alphabetic());
+        if !cursor.is_empty() {
+            return Err(cursor.error(ErrorKind::Expected(
+                "an item `a`-`z` or `A`-`Z`".to_string(),
+            )));
+        }
+        if items.len() % 2 != 0 {
+            return Err(cursor.error(ErrorKind::Expected("an even number of items".to_string())));
+        }
+        Ok(items.to_string())
+    })
+}
+
+/// `size` groups of three rucksacks (at least one). Each elf packs from its own share of the letters plus the group's
+/// badge, so the badge is the only item all three carry, and the two compartments only share the one item put in both.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut letters = ('a'..='z').chain('A'..='Z').collect::<Vec<_>>();
+    let mut buf = String::new();
+    for _ in 0..size.max(1) {
+        rng.shuffle(&mut letters);
+        let badge = letters[0];
+        for share in letters[1..].chunks(17) {
+            let mut items = share.to_vec();
+            items.push(badge);
+            rng.shuffle(&mut items);
+            // The item in both compartments, then what can go in each of them.
+            let (shared, left, right) = (items[0], &items[1..9], &items[9..]);
+            let len = rng.range(2, 12) as usize;
+            for choices in [left, right] {
+                let mut compartment = vec![shared];
+                if choices.contains(&badge) {
+                    compartment.push(badge);
+                }
+                while compartment.len() < len {
+                    compartment.push(*rng.choose(choices));
+                }
+                rng.shuffle(&mut compartment);
+                buf.extend(compartment);
+            }
+            buf.push('\n');
+        }
+    }
+    buf
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day3::{part1, part2, read_file};
+    use crate::day3::{
+        badges, compartments, misplaced, parse_input, part1, part2, priority, ItemSet, Shared,
+    };
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+
+    fn items(items: &str) -> ItemSet {
+        ItemSet::from_items(items).unwrap()
+    }
 
     const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
@@ -69,25 +243,122 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 157);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 157);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(3).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 8085);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 70);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 70);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(3).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 2515);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("vJrwpWtwJgWrhcsFMMfFFhFp\nvJrw pWtw\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("an item `a`-`z` or `A`-`Z`".to_string())
+        );
+
+        let err = parse_input("abc").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("an even number of items".to_string())
+        );
+    }
+
+    #[test]
+    fn test_item_set() {
+        let set = items("vJrwpWtwJg");
+        assert_eq!(set.len(), 8);
+        assert!(set.contains('J') && !set.contains('j') && !set.contains('1'));
+        assert_eq!(set.iter().collect::<String>(), "gprtvwJW");
+        assert_eq!(items("aZ").priority_sum(), 1 + 52);
+
+        let other = items("wWxyz");
+        assert_eq!(set.intersection(other), items("wW"));
+        assert_eq!(set.union(other).len(), 11);
+        assert_eq!(set.difference(other), items("vJrptg"));
+        assert!(set.difference(set).is_empty());
+        assert_eq!(ItemSet::common([set, other, items("Wa")]), items("W"));
+        assert_eq!(ItemSet::common([]), ItemSet::new());
+
+        assert_eq!(ItemSet::from_items("ab1"), None);
+        assert_eq!(ItemSet::from_items("aé"), None);
+        assert_eq!(priority('a'), Some(1));
+        assert_eq!(priority('Z'), Some(52));
+        assert_eq!(priority(' '), None);
+    }
+
+    #[test]
+    fn test_compartments() {
+        assert_eq!(
+            compartments("abcabd", 3),
+            Some(vec![items("ab"), items("ca"), items("bd")])
+        );
+        assert_eq!(compartments("abcabd", 4), None);
+        assert_eq!(compartments("abcabd", 0), None);
+        // Not items, including a letter that takes two bytes and would split in the middle.
+        assert_eq!(compartments("ab1abd", 2), None);
+        assert_eq!(compartments("aébc", 2), None);
+        assert!(misplaced(&["aébc".to_string()], 2).is_empty());
+
+        let rucksacks = parse_input(EXAMPLE).unwrap();
+        let found = misplaced(&rucksacks, 2);
+        assert_eq!(
+            found[1],
+            Shared {
+                lines: 2..=2,
+                items: items("L")
+            }
+        );
+        let items = found.iter().flat_map(|shared| shared.items.iter());
+        assert_eq!(items.collect::<String>(), "pLPvts");
+    }
+
+    #[test]
+    fn test_badges() {
+        let rucksacks = parse_input(EXAMPLE).unwrap();
+        assert_eq!(
+            badges(&rucksacks, 3),
+            vec![
+                Shared {
+                    lines: 1..=3,
+                    items: items("r")
+                },
+                Shared {
+                    lines: 4..=6,
+                    items: items("Z")
+                },
+            ]
+        );
+        // Pairs share more than one item, and the fifth rucksack is left over from groups of four.
+        let pairs = badges(&rucksacks, 2);
+        assert_eq!(pairs[2].lines, 5..=6);
+        assert_eq!(pairs[2].items.iter().collect::<String>(), "GJZ");
+        assert_eq!(badges(&rucksacks, 4).len(), 1);
+        assert_eq!(badges(&rucksacks, 1)[0].items, items(&rucksacks[0]));
+        assert!(badges(&rucksacks, 0).is_empty());
+        let with_other = [
+            "ab".to_string(),
+            "a-".to_string(),
+            "ba".to_string(),
+            "bc".to_string(),
+        ];
+        assert_eq!(badges(&with_other, 2)[0].lines, 3..=4);
+    }
 }
@@ -1,47 +1,78 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
+use crate::generate::Rng;
+use crate::parse::{parse_lines, Cursor, ParseError};
+use crate::solution::{Answer, Solution};
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day4.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
+/// The section ranges of a pair of elves: `(min1, max1, min2, max2)`.
+///
+pub type Pair = (u32, u32, u32, u32);
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    input
-        .filter(|s| {
-            let (min1, max1, min2, max2) = parse_ranges(s);
+pub fn part1(pairs: &[Pair]) -> usize {
+    pairs
+        .iter()
+        .filter(|&&(min1, max1, min2, max2)| {
             (min1 <= min2 && max1 >= max2) || (min2 <= min1 && max2 >= max1)
         })
         .count()
 }
 
-fn parse_ranges(s: &String) -> (u32, u32, u32, u32) {
-    let (range1, range2) = s.split_once(",").unwrap();
-    let (min1, max1) = parse_range(range1);
-    let (min2, max2) = parse_range(range2);
-    (min1, max1, min2, max2)
+pub fn parse_input(input: &str) -> Result<Vec<Pair>, ParseError> {
+    parse_lines(4, input, |cursor| {
+        let (min1, max1) = parse_range(cursor)?;
+        cursor.literal(",")?;
+        let (min2, max2) = parse_range(cursor)?;
+        Ok((min1, max1, min2, max2))
+    })
 }
 
-fn parse_range(range: &str) -> (u32, u32) {
-    let (min, max) = range.split_once("-").unwrap();
-    let min = u32::from_str(min).unwrap();
-    let max = u32::from_str(max).unwrap();
-    (min, max)
+fn parse_range(cursor: &mut Cursor) -> Result<(u32, u32), ParseError> {
+    let min = cursor.number()?;
+    cursor.literal("-")?;
+    let max = cursor.number()?;
+    Ok((min, max))
 }
 
-fn part2(input: impl Iterator<Item = String>) -> usize {
-    input
-        .filter(|s| {
-            let (min1, max1, min2, max2) = parse_ranges(s);
-            max1 >= min2 && max2 >= min1
-        })
+pub fn part2(pairs: &[Pair]) -> usize {
+    pairs
+        .iter()
+        .filter(|&&(min1, max1, min2, max2)| max1 >= min2 && max2 >= min1)
         .count()
 }
 
+/// `size` pairs (at least one) of random section ranges within 1-99.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut range = || {
+        let min = rng.range(1, 99);
+        format!("{}-{}", min, rng.range(min, 99))
+    };
+    (0..size.max(1))
+        .map(|_| format!("{},{}\n", range(), range()))
+        .collect()
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Pair>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "2-4,6-8
 2-3,4-5
@@ -53,25 +84,35 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 2);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 2);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(4).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 538);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 4);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 4);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(4).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 792);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("2-4,6-8\n2-3;4-5\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(err.kind, ErrorKind::Expected("`,`".to_string()));
+
+        let err = parse_input("2-4,6-").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EndOfLine("a number".to_string()));
+    }
 }
@@ -1,62 +1,136 @@
-use itertools::Itertools;
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day5.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-struct Input {
+#[derive(Debug)]
+pub struct Input {
     stacks: Vec<VecDeque<char>>,
     instructions: Vec<(usize, usize, usize)>,
 }
 
 impl Input {
-    fn from_iter(iter: impl Iterator<Item = String>) -> Input {
-        let (iter1, iter2) = iter.tee();
-
-        let mut stacks = Vec::new();
-        for line in iter1.take_while(|line| !line.is_empty()) {
-            for (i, letter) in line.chars().skip(1).step_by(4).enumerate() {
-                if (i + 1) > stacks.len() {
-                    stacks.push(VecDeque::new())
-                }
-                if letter.is_alphabetic() {
+    /// The drawing of the stacks, a line of stack numbers, a blank line and then the rearrangement procedure.
+    ///
+    pub fn parse(input: &str) -> Result<Input, ParseError> {
+        let mut cursors = cursors(5, input);
+        let mut rows = Vec::new();
+        let n_stacks = loop {
+            let mut cursor = cursors
+                .next()
+                .ok_or_else(|| ParseError::end_of_input(5, input, "a line of stack numbers"))?;
+            if cursor
+                .text()
+                .trim_start()
+                .starts_with(|c: char| c.is_ascii_digit())
+            {
+                break parse_stack_numbers(&mut cursor)?;
+            }
+            rows.push(parse_crates(&mut cursor)?);
+        };
+
+        let mut stacks = vec![VecDeque::new(); n_stacks];
+        for (line, row) in rows {
+            if row.len() > n_stacks {
+                return Err(ParseError {
+                    day: 5,
+                    line,
+                    column: 4 * n_stacks + 2,
+                    kind: ErrorKind::Expected(format!("at most {} stacks", n_stacks)),
+                });
+            }
+            for (i, letter) in row.into_iter().enumerate() {
+                if let Some(letter) = letter {
                     stacks[i].push_front(letter);
                 }
             }
         }
 
-        let instructions = iter2
-            .skip_while(|line| !line.is_empty())
-            .skip(1)
-            .map(|line| {
-                line.split(' ')
-                    .skip(1)
-                    .step_by(2)
-                    .map(|c| usize::from_str(c).unwrap())
-                    .collect_tuple()
-                    .unwrap()
+        match cursors.next() {
+            Some(cursor) => cursor.end()?,
+            None => return Err(ParseError::end_of_input(5, input, "a blank line")),
+        }
+
+        let instructions = cursors
+            .map(|mut cursor| {
+                cursor.literal("move ")?;
+                let num = cursor.number()?;
+                cursor.literal(" from ")?;
+                let from = parse_stack(&mut cursor, n_stacks)?;
+                cursor.literal(" to ")?;
+                let to = parse_stack(&mut cursor, n_stacks)?;
+                cursor.end()?;
+                Ok((num, from, to))
             })
-            .collect_vec();
+            .collect::<Result<_, _>>()?;
 
-        Input {
+        Ok(Input {
             stacks,
             instructions,
+        })
+    }
+}
+
+/// One row of the drawing: a crate like `[Z]` or three spaces for each stack, separated by single spaces.
+///
+fn parse_crates(cursor: &mut Cursor) -> Result<(usize, Vec<Option<char>>), ParseError> {
+    let mut row = Vec::new();
+    while !cursor.is_empty() {
+        if !row.is_empty() {
+            cursor.literal(" ")?;
+        }
+        if cursor.peek() == Some('[') {
+            cursor.literal("[")?;
+            row.push(Some(
+                cursor.char("a crate letter", |c| c.is_ascii_alphabetic())?,
+            ));
+            cursor.literal("]")?;
+        } else if cursor.rest().starts_with("   ") {
+            cursor.literal("   ")?;
+            row.push(None);
+        } else {
+            return Err(cursor.expected("a crate or three spaces"));
+        }
+    }
+    Ok((cursor.line(), row))
+}
+
+fn parse_stack_numbers(cursor: &mut Cursor) -> Result<usize, ParseError> {
+    let mut n_stacks = 0;
+    loop {
+        cursor.take_while(|c| c == ' ');
+        if cursor.is_empty() {
+            return Ok(n_stacks);
+        }
+        let column = cursor.column();
+        let number: usize = cursor.number()?;
+        n_stacks += 1;
+        if number != n_stacks {
+            return Err(cursor.error_at(
+                column,
+                ErrorKind::Expected(format!("stack number {}", n_stacks)),
+            ));
         }
     }
 }
 
-fn part1(iter: impl Iterator<Item = String>) -> String {
-    let Input {
-        mut stacks,
-        instructions,
-    } = Input::from_iter(iter);
+fn parse_stack(cursor: &mut Cursor, n_stacks: usize) -> Result<usize, ParseError> {
+    let column = cursor.column();
+    let stack = cursor.number()?;
+    if (1..=n_stacks).contains(&stack) {
+        Ok(stack)
+    } else {
+        Err(cursor.error_at(
+            column,
+            ErrorKind::Expected(format!("a stack between 1 and {}", n_stacks)),
+        ))
+    }
+}
 
-    for &(num, from, to) in instructions.iter() {
+pub fn part1(input: &Input) -> String {
+    let mut stacks = input.stacks.clone();
+
+    for &(num, from, to) in input.instructions.iter() {
         for _i in 0..num {
             let c = stacks[from - 1].pop_back().unwrap();
             stacks[to - 1].push_back(c);
@@ -65,13 +139,10 @@ fn part1(iter: impl Iterator<Item = String>) -> String {
     tops(&stacks)
 }
 
-fn part2(iter: impl Iterator<Item = String>) -> String {
-    let Input {
-        mut stacks,
-        instructions,
-    } = Input::from_iter(iter);
+pub fn part2(input: &Input) -> String {
+    let mut stacks = input.stacks.clone();
 
-    for &(num, from, to) in instructions.iter() {
+    for &(num, from, to) in input.instructions.iter() {
         let len = stacks[from - 1].len();
 
         // Push the last num elements from the `from` stack on to the `to` stack.
@@ -89,18 +160,88 @@ fn part2(iter: impl Iterator<Item = String>) -> String {
     tops(&stacks)
 }
 
-fn tops(stacks: &Vec<VecDeque<char>>) -> String {
+fn tops(stacks: &[VecDeque<char>]) -> String {
     let mut res = String::with_capacity(stacks.len());
-    for i in 0..stacks.len() {
-        res.push(*stacks[i].back().unwrap());
+    for stack in stacks {
+        res.push(*stack.back().unwrap());
     }
     res
 }
 
+/// `size` stacks (between 1 and 9) of up to 8 crates and three times as many moves. No move takes the last crate off a
+/// stack, so there is a crate on top of each at the end.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n_stacks = size.clamp(1, 9);
+    let mut stacks = (0..n_stacks)
+        .map(|_| {
+            (0..rng.range(1, 8))
+                .map(|_| *rng.choose(&('A'..='Z').collect::<Vec<_>>()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut buf = String::new();
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap();
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(c) => format!("[{}]", c),
+                None => "   ".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        buf.push_str(row.trim_end());
+        buf.push('\n');
+    }
+    let numbers = (1..=n_stacks)
+        .map(|i| format!(" {} ", i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    buf.push_str(&format!("{}\n\n", numbers));
+
+    for _ in 0..3 * size.max(1) {
+        let from = (0..n_stacks)
+            .filter(|&i| stacks[i].len() > 1)
+            .collect::<Vec<_>>();
+        if from.is_empty() || n_stacks == 1 {
+            break;
+        }
+        let from = *rng.choose(&from);
+        let to = (from + rng.range(1, n_stacks as i64 - 1) as usize) % n_stacks;
+        let num = rng.range(1, stacks[from].len() as i64 - 1) as usize;
+        let at = stacks[from].len() - num;
+        let crates = stacks[from].split_off(at);
+        stacks[to].extend(crates);
+        buf.push_str(&format!("move {} from {} to {}\n", num, from + 1, to + 1));
+    }
+    buf
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Input;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Input::parse(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::read_file;
-    use crate::day5::{part1, part2};
+    use super::{part1, part2, Input};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "    [D]    
 [N] [C]    
@@ -115,25 +256,46 @@ move 1 from 1 to 2
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), "CMZ");
+        assert_eq!(part1(&Input::parse(EXAMPLE).unwrap()), "CMZ");
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&Input::parse(&read(5).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, "MQTPGLLDN");
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), "MCD");
+        assert_eq!(part2(&Input::parse(EXAMPLE).unwrap()), "MCD");
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&Input::parse(&read(5).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, "LVZPSTTCZ");
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = EXAMPLE.replace("move 3 from 1 to 3", "move 3 from 1 to 4");
+        let err = Input::parse(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (7, 18));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a stack between 1 and 3".to_string())
+        );
+
+        let input = EXAMPLE.replace("move 2 from 2 to 1", "move 2 from 2 to 1 ");
+        let err = Input::parse(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (8, 19));
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+
+        let input = EXAMPLE.replace("[N]", "[N}");
+        let err = Input::parse(&input).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.kind, ErrorKind::Expected("`]`".to_string()));
+    }
 }
@@ -1,15 +1,9 @@
+use crate::generate::Rng;
+use crate::parse::{cursors, ParseError};
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
-use std::fs::File;
-use std::io::Read;
-
-fn read_file() -> String {
-    let mut file = File::open("input/day6.txt").unwrap();
-    let mut str = String::new();
-    file.read_to_string(&mut str).unwrap();
-    str
-}
 
-fn part1(input: String) -> usize {
+pub fn part1(input: &str) -> usize {
     input
         .chars()
         .tuple_windows()
@@ -24,7 +18,7 @@ fn part1(input: String) -> usize {
         .unwrap()
 }
 
-fn part2(input: String) -> usize {
+pub fn part2(input: &str) -> usize {
     // Safe because all chars are bytes here.
     for i in 14..input.len() + 1 {
         // Equivalent to `input[i - 14..i].chars().all_unique()` but doesn't need a HashSet.
@@ -32,7 +26,7 @@ fn part2(input: String) -> usize {
             return i;
         }
     }
-    return 0;
+    0
 }
 
 fn all_unique(iter: impl Iterator<Item = char>) -> bool {
@@ -48,9 +42,66 @@ fn all_unique(iter: impl Iterator<Item = char>) -> bool {
     true
 }
 
+/// The datastream is a single line of lowercase letters.
+///
+pub fn parse_input(input: &str) -> Result<String, ParseError> {
+    let mut cursors = cursors(6, input);
+    let mut cursor = cursors
+        .next()
+        .ok_or_else(|| ParseError::end_of_input(6, input, "a datastream"))?;
+    let datastream = cursor.take_while(|c| c.is_ascii_lowercase());
+    if !cursor.is_empty() {
+        return Err(cursor.expected("a letter `a`-`z`"));
+    }
+    if let Some(cursor) = cursors.next() {
+        return Err(cursor.expected("the end of the input"));
+    }
+    Ok(datastream.to_string())
+}
+
+/// A datastream of `size` letters (at least 14), each part of it drawn from only a few letters so the markers aren't
+/// found straight away. 14 different letters go in somewhere, so there is always a start-of-message marker.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut letters = ('a'..='z').collect::<Vec<_>>();
+    let len = size.max(14);
+    let mut stream = Vec::with_capacity(len);
+    while stream.len() < len {
+        let n_letters = rng.range(2, 13) as usize;
+        for _ in 0..rng.range(1, 50) {
+            stream.push(letters[rng.below(n_letters)]);
+        }
+        rng.shuffle(&mut letters);
+    }
+    stream.truncate(len);
+    let at = rng.below(len - 13);
+    stream[at..at + 14].copy_from_slice(&letters[..14]);
+    stream.into_iter().chain(['\n']).collect()
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = String;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE1: &str = "bvwbjplbgvbhsrlpgdmjqwftvncz";
     const EXAMPLE2: &str = "nppdvjthqldpwncqszvftbrmjlhg";
@@ -59,15 +110,15 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE1.to_string()), 5);
-        assert_eq!(part1(EXAMPLE2.to_string()), 6);
-        assert_eq!(part1(EXAMPLE3.to_string()), 10);
-        assert_eq!(part1(EXAMPLE4.to_string()), 11);
+        assert_eq!(part1(EXAMPLE1), 5);
+        assert_eq!(part1(EXAMPLE2), 6);
+        assert_eq!(part1(EXAMPLE3), 10);
+        assert_eq!(part1(EXAMPLE4), 11);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(6).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 1042);
     }
@@ -80,17 +131,30 @@ mod tests {
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE5.to_string()), 19);
-        assert_eq!(part2(EXAMPLE6.to_string()), 23);
-        assert_eq!(part2(EXAMPLE7.to_string()), 23);
-        assert_eq!(part2(EXAMPLE8.to_string()), 29);
-        assert_eq!(part2(EXAMPLE9.to_string()), 26);
+        assert_eq!(part2(EXAMPLE5), 19);
+        assert_eq!(part2(EXAMPLE6), 23);
+        assert_eq!(part2(EXAMPLE7), 23);
+        assert_eq!(part2(EXAMPLE8), 29);
+        assert_eq!(part2(EXAMPLE9), 26);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(6).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 2980);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("bvwbjplbgv bhsr\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 11));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a letter `a`-`z`".to_string())
+        );
+
+        let err = parse_input("").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EndOfInput("a datastream".to_string()));
+    }
 }
@@ -1,26 +1,29 @@
-use itertools::Itertools;
+use crate::generate::Rng;
+use crate::parse::{cursors, Cursor, ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
 use std::collections::{HashMap, VecDeque};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str::FromStr;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day7.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
-
-fn parse_dir_sizes(input: impl Iterator<Item = String>) -> HashMap<String, usize> {
+/// The total size of each directory, by path from the root. The session has to start in the root, `/`.
+///
+fn parse_dir_sizes(input: &str) -> Result<HashMap<String, usize>, ParseError> {
     let mut stack = VecDeque::new();
     let mut dirs = HashMap::new();
 
-    for line in input {
-        let v = line.split(" ").collect_vec();
-        match v.as_slice() {
-            ["$", "cd", ".."] => {
-                stack.pop_back().unwrap();
-            }
-            ["$", "cd", dir] => {
+    for mut cursor in cursors(7, input) {
+        if cursor.rest().starts_with("$ cd ") {
+            cursor.literal("$ cd ")?;
+            let dir = parse_name(&mut cursor, "a directory name")?;
+            if dir == ".." {
+                if stack.pop_back().is_none() {
+                    return Err(
+                        cursor.error_at(6, ErrorKind::Expected("a directory to enter".to_string()))
+                    );
+                }
+            } else {
                 let path = if stack.is_empty() {
+                    if dir != "/" {
+                        return Err(cursor.error_at(6, ErrorKind::Expected("`/`".to_string())));
+                    }
                     dir.to_string()
                 } else {
                     format!("{}/{}", stack.back().unwrap(), dir)
@@ -28,41 +31,148 @@ fn parse_dir_sizes(input: impl Iterator<Item = String>) -> HashMap<String, usize
                 stack.push_back(path.clone());
                 dirs.insert(path, 0);
             }
-            ["$", "ls"] => {}
-            ["dir", _dir_name] => {}
-            [num, _name] => {
-                let size = usize::from_str(*num).unwrap();
-                for dir in stack.iter() {
-                    *dirs.get_mut(dir).unwrap() += size;
-                }
+        } else if cursor.rest().starts_with('$') {
+            cursor.literal("$ ls")?;
+        } else if cursor.rest().starts_with("dir ") {
+            cursor.literal("dir ")?;
+            parse_name(&mut cursor, "a directory name")?;
+        } else {
+            let size: usize = cursor.number()?;
+            cursor.literal(" ")?;
+            parse_name(&mut cursor, "a file name")?;
+            if stack.is_empty() {
+                return Err(cursor.error_at(
+                    1,
+                    ErrorKind::Expected("`$ cd` before the first file".to_string()),
+                ));
+            }
+            for dir in stack.iter() {
+                *dirs.get_mut(dir).unwrap() += size;
             }
-            _ => {}
         }
+        cursor.end()?;
     }
-    dirs
+    if dirs.is_empty() {
+        return Err(ParseError::end_of_input(7, input, "`$ cd /`"));
+    }
+    Ok(dirs)
 }
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    let dir_sizes = parse_dir_sizes(input);
+fn parse_name<'a>(cursor: &mut Cursor<'a>, expected: &str) -> Result<&'a str, ParseError> {
+    let name = cursor.take_while(|c| !c.is_whitespace());
+    if name.is_empty() {
+        Err(cursor.expected(expected))
+    } else {
+        Ok(name)
+    }
+}
+
+pub fn part1(dir_sizes: &HashMap<String, usize>) -> usize {
     dir_sizes.values().filter(|&&size| size <= 100000).sum()
 }
 
-fn part2(input: impl Iterator<Item = String>) -> usize {
-    let dir_sizes = parse_dir_sizes(input);
-    let used_space = dir_sizes.get(&"/".to_string()).unwrap();
-    let free_space = 70_000_000 - used_space;
-    let needed_space = 30_000_000 - free_space;
+/// The size of the smallest directory that frees up enough space for the update, or `None` if there is enough already
+/// or the files don't fit on the disk.
+///
+pub fn part2(dir_sizes: &HashMap<String, usize>) -> Option<usize> {
+    let used_space = dir_sizes["/"];
+    let free_space = 70_000_000_usize.checked_sub(used_space)?;
+    let needed_space = 30_000_000_usize.checked_sub(free_space)?;
 
-    *dir_sizes
+    dir_sizes
         .values()
         .filter(|&&size| size >= needed_space)
         .min()
-        .unwrap()
+        .copied()
+}
+
+/// A terminal session listing `size` directories (at least one) with up to 5 files each. The files add up to between
+/// 40000000 and 70000000, so the disk is too full for the update without being more than full.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let n_dirs = size.max(1);
+    let mut children = vec![Vec::new(); n_dirs];
+    for dir in 1..n_dirs {
+        children[rng.below(dir)].push(dir);
+    }
+
+    // Small files first, then a few large ones to fill the disk up to the total.
+    let max_small = (40_000_000 / (5 * n_dirs as i64)).min(60_000);
+    let mut files = (0..n_dirs)
+        .map(|_| {
+            (0..rng.range(0, 5))
+                .map(|_| rng.range(1, max_small))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut rest = rng.range(40_000_001, 69_999_999) - files.iter().flatten().sum::<i64>();
+    for i in (0..rng.range(1, 3)).rev() {
+        let size = if i == 0 { rest } else { rng.range(1, rest / 2) };
+        files[rng.below(n_dirs)].push(size);
+        rest -= size;
+    }
+
+    fn name(i: usize) -> String {
+        let letter = (b'a' + (i % 26) as u8) as char;
+        match i / 26 {
+            0 => letter.to_string(),
+            n => format!("{}{}", name(n - 1), letter),
+        }
+    }
+    fn session(dir: usize, children: &[Vec<usize>], files: &[Vec<i64>], buf: &mut String) {
+        let dir_name = if dir == 0 { "/".to_string() } else { name(dir) };
+        buf.push_str(&format!("$ cd {}\n$ ls\n", dir_name));
+        for &child in children[dir].iter() {
+            buf.push_str(&format!("dir {}\n", name(child)));
+        }
+        for (i, size) in files[dir].iter().enumerate() {
+            buf.push_str(&format!(
+                "{} {}.{}\n",
+                size,
+                name(i),
+                ["txt", "dat", "log"][i % 3]
+            ));
+        }
+        for &child in children[dir].iter() {
+            session(child, children, files, buf);
+        }
+        if dir != 0 {
+            buf.push_str("$ cd ..\n");
+        }
+    }
+    let mut buf = String::new();
+    session(0, &children, &files, &mut buf);
+    buf
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = HashMap<String, usize>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_dir_sizes(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        match part2(input) {
+            Some(size) => size.into(),
+            None => Answer::Unsolvable(
+                "the disk has room for the update already, or not even for the files".to_string(),
+            ),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_dir_sizes, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "$ cd /
 $ ls
@@ -91,25 +201,56 @@ $ ls
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 95437);
+        assert_eq!(part1(&parse_dir_sizes(EXAMPLE).unwrap()), 95437);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_dir_sizes(&read(7).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 1391690);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 24933642);
+        assert_eq!(part2(&parse_dir_sizes(EXAMPLE).unwrap()), Some(24933642));
+        assert_eq!(
+            part2(&parse_dir_sizes("$ cd /\n$ ls\n100 a\n").unwrap()),
+            None
+        );
+        let full = "$ cd /\n$ ls\n70000001 a\n";
+        assert_eq!(part2(&parse_dir_sizes(full).unwrap()), None);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
-        println!("{}", res);
-        assert_eq!(res, 5469168);
+        let res = part2(&parse_dir_sizes(&read(7).unwrap()).unwrap());
+        println!("{:?}", res);
+        assert_eq!(res, Some(5469168));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_dir_sizes("$ cd /\n$ dir\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.kind, ErrorKind::Expected("`$ ls`".to_string()));
+
+        let err = parse_dir_sizes("$ cd /\n$ ls\n12x b.txt\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        assert_eq!(err.kind, ErrorKind::Expected("` `".to_string()));
+
+        let err = parse_dir_sizes("$ cd ..\n").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a directory to enter".to_string())
+        );
+
+        let err = parse_dir_sizes("$ cd x\n$ ls\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 6));
+        assert_eq!(err.kind, ErrorKind::Expected("`/`".to_string()));
+
+        let err = parse_dir_sizes("").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        assert_eq!(err.kind, ErrorKind::EndOfInput("`$ cd /`".to_string()));
     }
 }
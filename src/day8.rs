@@ -1,139 +1,105 @@
-use itertools::Itertools;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day8.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+use crate::generate::Rng;
+use crate::grid::{Grid, Offset, Pos, ORTHOGONAL};
+use crate::parse::{ErrorKind, ParseError};
+use crate::solution::{Answer, Solution};
+
+/// The tree heights, which must form a square.
+///
+fn parse_input(input: &str) -> Result<Grid<u32>, ParseError> {
+    let grid = Grid::parse(8, input, "a tree height `0`-`9`", |c| c.to_digit(10))?;
+    let (n_rows, width) = (grid.n_rows(), grid.n_cols());
+    if width < n_rows {
+        Err(ParseError {
+            day: 8,
+            line: width + 1,
+            column: 1,
+            kind: ErrorKind::Expected(format!("{} rows, as many as there are columns", width)),
+        })
+    } else if width > n_rows {
+        Err(ParseError::end_of_input(
+            8,
+            input,
+            format!("{} rows, as many as there are columns", width),
+        ))
+    } else {
+        Ok(grid)
+    }
 }
 
-fn parse_input(iter: impl Iterator<Item = String>) -> Vec<Vec<u32>> {
-    iter.map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect_vec())
-        .collect_vec()
+/// A tree is visible if all the trees between it and the edge are shorter, looking from any of the four directions.
+///
+pub fn part1(grid: &Grid<u32>) -> usize {
+    grid.positions()
+        .filter(|&pos| {
+            ORTHOGONAL
+                .into_iter()
+                .any(|dir| grid.ray(pos, dir).all(|p| grid[p] < grid[pos]))
+        })
+        .count()
 }
 
-/// Visit each element in the matrix looking at it from one of the directions.
-/// Calculate the cumulative max of the trees as we go along to compare against each new tree.
-/// Record the tree coords that are visible in a set.
+/// From each tree, go in each of the four directions, stopping when we reach a tree of the same or greater height.
 ///
-fn part1(input: impl Iterator<Item = String>) -> usize {
-    let grid = parse_input(input);
-    let dim = grid.len();
-    let mut seen = HashSet::new();
-
-    // From the left
-    for row in 0..dim {
-        let mut cum_max = -1;
-        for col in 0..dim {
-            let val = grid[row][col] as i32;
-            if val > cum_max {
-                seen.insert((row, col));
-            }
-            cum_max = cum_max.max(val)
-        }
-    }
+pub fn part2(grid: &Grid<u32>) -> u32 {
+    grid.positions()
+        .map(|pos| {
+            ORTHOGONAL
+                .into_iter()
+                .map(|dir| viewing_distance(grid, pos, dir))
+                .product()
+        })
+        .max()
+        .unwrap_or(0)
+}
 
-    // From the right
-    for row in 0..dim {
-        let mut cum_max = -1;
-        for col in (0..dim).rev() {
-            let val = grid[row][col] as i32;
-            if val > cum_max {
-                seen.insert((row, col));
-            }
-            cum_max = cum_max.max(val)
+fn viewing_distance(grid: &Grid<u32>, pos: Pos, dir: Offset) -> u32 {
+    let mut distance = 0;
+    for p in grid.ray(pos, dir) {
+        distance += 1;
+        if grid[p] >= grid[pos] {
+            break;
         }
     }
+    distance
+}
 
-    // From the top
-    for col in 0..dim {
-        let mut cum_max = -1;
-        for row in 0..dim {
-            let val = grid[row][col] as i32;
-            if val > cum_max {
-                seen.insert((row, col));
-            }
-            cum_max = cum_max.max(val)
-        }
-    }
+/// A square of random trees `size` across (at least 3).
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let side = size.max(3);
+    (0..side)
+        .map(|_| {
+            (0..side)
+                .map(|_| char::from_digit(rng.below(10) as u32, 10).unwrap())
+                .chain(['\n'])
+                .collect::<String>()
+        })
+        .collect()
+}
 
-    // From the bottom
-    for col in 0..dim {
-        let mut cum_max = -1;
-        for row in (0..dim).rev() {
-            let val = grid[row][col] as i32;
-            if val > cum_max {
-                seen.insert((row, col));
-            }
-            cum_max = cum_max.max(val)
-        }
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input = Grid<u32>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
     }
 
-    return seen.len();
-}
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
 
-/// From each tree, go in each of the four directions, stopping when we reach a tree of the same or greater height.
-///
-fn part2(input: impl Iterator<Item = String>) -> u32 {
-    let grid = parse_input(input);
-    let dim = grid.len();
-    let mut max_score = 0;
-
-    for row in 0..dim {
-        for col in 0..dim {
-            let val = grid[row][col];
-
-            // Look left
-            let mut left_score = 0;
-            if col > 0 {
-                for c in (0..col).rev() {
-                    left_score += 1;
-                    if grid[row][c] >= val {
-                        break;
-                    }
-                }
-            }
-
-            // Look right
-            let mut right_score = 0;
-            for c in (col + 1)..dim {
-                right_score += 1;
-                if grid[row][c] >= val {
-                    break;
-                }
-            }
-
-            // Look up
-            let mut up_score = 0;
-            if row > 0 {
-                for r in (0..row).rev() {
-                    up_score += 1;
-                    if grid[r][col] >= val {
-                        break;
-                    }
-                }
-            }
-
-            // Look down
-            let mut down_score = 0;
-            for r in (row + 1)..dim {
-                down_score += 1;
-                if grid[r][col] >= val {
-                    break;
-                }
-            }
-
-            let score = left_score * right_score * up_score * down_score;
-            max_score = max_score.max(score);
-        }
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
     }
-    max_score
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
 
     const EXAMPLE: &str = "30373
 25512
@@ -144,25 +110,52 @@ mod tests {
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 21);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 21);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(8).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 1798);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 8);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 8);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(8).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 259308);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("303\n25a\n653\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("a tree height `0`-`9`".to_string())
+        );
+
+        let err = parse_input("303\n2551\n653\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+
+        let err = parse_input("303\n25\n653\n").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfLine("a tree height `0`-`9`".to_string())
+        );
+
+        let err = parse_input("303\n255\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(
+            err.kind,
+            ErrorKind::EndOfInput("3 rows, as many as there are columns".to_string())
+        );
+    }
 }
@@ -1,54 +1,60 @@
+use crate::generate::Rng;
+use crate::parse::{parse_lines, ParseError};
+use crate::render::{bounding_frame, Frame, Frames};
+use crate::solution::{Answer, Solution};
 use itertools::Itertools;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::iter;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day9.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
-}
 
-#[derive(Clone)]
-enum Dir {
+#[derive(Clone, Copy, Debug)]
+pub enum Dir {
     Left,
     Right,
     Up,
     Down,
 }
 
-fn parse_input(input: impl Iterator<Item = String>) -> impl Iterator<Item = Dir> {
-    input.flat_map(|line| {
-        let (dir_str, num_str) = line.split_ascii_whitespace().collect_tuple().unwrap();
-        let dir = match dir_str {
-            "L" => Dir::Left,
-            "R" => Dir::Right,
-            "U" => Dir::Up,
-            "D" => Dir::Down,
-            _ => panic!("Unknown direction {}", dir_str),
+fn parse_input(input: &str) -> Result<Vec<Dir>, ParseError> {
+    let motions = parse_lines(9, input, |cursor| {
+        let dir = match cursor.char("`L`, `R`, `U` or `D`", |c| "LRUD".contains(c))? {
+            'L' => Dir::Left,
+            'R' => Dir::Right,
+            'U' => Dir::Up,
+            _ => Dir::Down,
         };
-        let num = usize::from_str(num_str).unwrap();
-        iter::repeat(dir).take(num)
-    })
+        cursor.literal(" ")?;
+        let num = cursor.number()?;
+        Ok(std::iter::repeat_n(dir, num))
+    })?;
+    Ok(motions.into_iter().flatten().collect_vec())
+}
+
+type Coord = (i32, i32);
+
+pub fn part1(input: &[Dir]) -> usize {
+    pull_rope(input, 2, |_| {})
 }
 
-fn part1(input: impl Iterator<Item = String>) -> usize {
+/// Moves the head of a rope of `n_knots` knots through the motions, calling `step` with the knots, head first, after
+/// each step. Returns the number of positions the tail visited.
+///
+fn pull_rope(input: &[Dir], n_knots: usize, mut step: impl FnMut(&[Coord])) -> usize {
     let mut visited = HashSet::new();
-    let mut head = (0, 0);
-    let mut tail = (0, 0);
-    visited.insert(tail);
-
-    for dir in parse_input(input) {
-        head = move_head(&head, dir);
-        tail = move_tail(&head, &tail);
-        visited.insert(tail);
+    let mut knots = std::iter::repeat_n((0, 0), n_knots).collect_vec();
+    visited.insert(knots[n_knots - 1]);
+
+    for &dir in input {
+        knots[0] = move_head(&knots[0], dir);
+        for i in 1..knots.len() {
+            knots[i] = move_tail(&knots[i - 1], &knots[i]);
+        }
+        visited.insert(knots[n_knots - 1]);
+        step(&knots);
     }
 
     visited.len()
 }
 
-fn move_head(head: &(i32, i32), dir: Dir) -> (i32, i32) {
+fn move_head(head: &Coord, dir: Dir) -> Coord {
     match dir {
         Dir::Left => (head.0, head.1 - 1),
         Dir::Right => (head.0, head.1 + 1),
@@ -57,7 +63,7 @@ fn move_head(head: &(i32, i32), dir: Dir) -> (i32, i32) {
     }
 }
 
-fn move_tail(head: &(i32, i32), tail: &(i32, i32)) -> (i32, i32) {
+fn move_tail(head: &Coord, tail: &Coord) -> Coord {
     let r_diff = head.0 - tail.0;
     let c_diff = head.1 - tail.1;
 
@@ -68,24 +74,78 @@ fn move_tail(head: &(i32, i32), tail: &(i32, i32)) -> (i32, i32) {
     }
 }
 
-fn part2(input: impl Iterator<Item = String>) -> usize {
-    let mut visited = HashSet::new();
-    let mut knots = iter::repeat((0, 0)).take(10).collect_vec();
+pub fn part2(input: &[Dir]) -> usize {
+    pull_rope(input, 10, |_| {})
+}
 
-    for dir in parse_input(input) {
-        knots[0] = move_head(&knots[0], dir);
-        for i in 1..knots.len() {
-            knots[i] = move_tail(&knots[i - 1], &knots[i]);
+/// The rope after each step, in the bounds of the head's whole path: the knots over the tail's trail (`#`) and the
+/// start (`s`). The knots are `H` and `T` for part one and `H` and `1` to `9` for part two.
+///
+pub fn draw(input: &[Dir], part: u32, frames: &mut Frames) {
+    let n_knots = if part == 1 { 2 } else { 10 };
+    let mut heads = vec![(0, 0)];
+    pull_rope(input, 1, |knots| heads.push(knots[0]));
+    let (mut trail, pos) = bounding_frame(heads, '.');
+    trail[pos((0, 0))] = '#';
+    let mut rope = Vec::new();
+    let picture = |trail: &Frame, knots: &[Coord]| {
+        let mut frame = trail.clone();
+        frame[pos((0, 0))] = 's';
+        for (i, &knot) in knots.iter().enumerate().rev() {
+            frame[pos(knot)] = match i {
+                0 => 'H',
+                _ if n_knots == 2 => 'T',
+                _ => char::from_digit(i as u32, 10).unwrap(),
+            };
         }
-        visited.insert(knots[knots.len() - 1]);
+        frame
+    };
+    pull_rope(input, n_knots, |knots| {
+        trail[pos(knots[n_knots - 1])] = '#';
+        frames.step(|| picture(&trail, knots));
+        rope = knots.to_vec();
+    });
+    frames.finish(|| picture(&trail, &rope));
+}
+
+/// `size` random motions (at least one) of 1 to 20 steps.
+///
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            format!(
+                "{} {}\n",
+                rng.choose(&['L', 'R', 'U', 'D']),
+                rng.range(1, 20)
+            )
+        })
+        .collect()
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<Dir>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
     }
 
-    visited.len()
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
+    use super::{draw, parse_input, part1, part2};
+    use crate::input::read;
+    use crate::parse::ErrorKind;
+    use crate::render::{Frame, Frames};
 
     const EXAMPLE: &str = "R 4
 U 4
@@ -99,12 +159,12 @@ R 2
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 13);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 13);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(9).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 6209);
     }
@@ -120,13 +180,48 @@ U 20
 ";
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE2.lines().map(|v| v.to_string())), 36);
+        assert_eq!(part2(&parse_input(EXAMPLE2).unwrap()), 36);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(9).unwrap()).unwrap());
         println!("{}", res);
         assert_eq!(res, 2460);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_input("R 4\nX 4\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(
+            err.kind,
+            ErrorKind::Expected("`L`, `R`, `U` or `D`".to_string())
+        );
+
+        let err = parse_input("R 4\nU\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.kind, ErrorKind::EndOfLine("` `".to_string()));
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut last = None;
+        let mut sink = |_: usize, frame: &Frame| last = Some(frame.render(|&c| c));
+        draw(
+            &parse_input(EXAMPLE).unwrap(),
+            1,
+            &mut Frames::new(0, &mut sink),
+        );
+        assert_eq!(last.unwrap(), "..##..\n...##.\n.TH##.\n....#.\ns###..\n");
+
+        let mut steps = 0;
+        let mut sink = |step: usize, _: &Frame| steps = step;
+        draw(
+            &parse_input(EXAMPLE2).unwrap(),
+            2,
+            &mut Frames::new(1, &mut sink),
+        );
+        assert_eq!(steps, 5 + 8 + 8 + 3 + 17 + 10 + 25 + 20);
+    }
 }
@@ -0,0 +1,214 @@
+use crate::parse::ParseError;
+use crate::solution::{Answer, Solution};
+use crate::*;
+use std::fmt;
+
+/// The days with a slow but simple reference solution to check the clever one against.
+///
+pub const DAYS: [u32; 4] = [11, 15, 17, 20];
+
+/// One answer worked out both by a day's solution and by its reference solution.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub answer: Answer,
+    pub reference: Answer,
+}
+
+impl Check {
+    fn new(name: &str, answer: impl Into<Answer>, reference: impl Into<Answer>) -> Check {
+        Check {
+            name: name.to_string(),
+            answer: answer.into(),
+            reference: reference.into(),
+        }
+    }
+
+    pub fn agrees(&self) -> bool {
+        self.answer == self.reference
+    }
+}
+
+/// Why an input didn't pass, with the description of the input.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Failure {
+    /// The day's parser rejected the input.
+    Parse { input: String, error: ParseError },
+    /// The two solutions gave different answers.
+    Mismatch { input: String, check: Check },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Parse { input, error } => write!(f, "{}: {}", input, error),
+            Failure::Mismatch { input, check } => write!(
+                f,
+                "{}: {} is {} but the reference gives {}",
+                input, check.name, check.answer, check.reference
+            ),
+        }
+    }
+}
+
+/// Solves `input` for `day` both ways. Returns `None` for the days without a reference solution. There are no checks
+/// for day 15 when its search area is too big to try every point in, or for day 11 when the reference's modulus
+/// doesn't fit in a `u64`.
+///
+pub fn compare(day: u32, input: &str) -> Option<Result<Vec<Check>, ParseError>> {
+    fn run<S: Solution>(
+        input: &str,
+        compare: impl FnOnce(&S::Input) -> Vec<Check>,
+    ) -> Result<Vec<Check>, ParseError> {
+        Ok(compare(&S::parse(input)?))
+    }
+
+    Some(match day {
+        11 => run::<day11::Day11>(input, |monkeys| match day11::reference_part2(monkeys) {
+            Some(reference) => vec![Check::new("part 2", day11::part2(monkeys), reference)],
+            None => Vec::new(),
+        }),
+        15 => run::<day15::Day15>(input, |sensors| {
+            if day15::search_bound(sensors) > 1000 {
+                return Vec::new();
+            }
+            vec![Check::new(
+                "part 2",
+                day15::part2(sensors, None),
+                day15::reference_part2(sensors, None),
+            )]
+        }),
+        17 => run::<day17::Day17>(input, |directions| {
+            [1, 5, 100, 2022, 5000]
+                .into_iter()
+                .map(|n| {
+                    Check::new(
+                        &format!("the height after {} rocks", n),
+                        day17::solve_n(directions, n),
+                        day17::reference_height(directions, n as usize),
+                    )
+                })
+                .collect()
+        }),
+        20 => run::<day20::Day20>(input, |seq| {
+            vec![
+                Check::new(
+                    "part 1",
+                    day20::part1(seq, None),
+                    day20::reference_part1(seq),
+                ),
+                Check::new(
+                    "part 2",
+                    day20::part2(seq, None),
+                    day20::reference_part2(seq),
+                ),
+            ]
+        }),
+        _ => return None,
+    })
+}
+
+/// How many inputs both solutions agreed on, and how many had nothing to compare.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Tally {
+    pub agreed: usize,
+    pub skipped: usize,
+}
+
+/// Compares the two solutions on each of `inputs`, given as a description and the input, stopping at the first one
+/// that fails. Returns the tally otherwise, or `None` for the days without a reference solution.
+///
+pub fn check(
+    day: u32,
+    inputs: impl IntoIterator<Item = (String, String)>,
+) -> Option<Result<Tally, Failure>> {
+    if !DAYS.contains(&day) {
+        return None;
+    }
+    let mut tally = Tally::default();
+    for (description, input) in inputs {
+        let checks = match compare(day, &input)? {
+            Ok(checks) => checks,
+            Err(error) => {
+                return Some(Err(Failure::Parse {
+                    input: description,
+                    error,
+                }))
+            }
+        };
+        if checks.is_empty() {
+            tally.skipped += 1;
+            continue;
+        }
+        if let Some(check) = checks.into_iter().find(|check| !check.agrees()) {
+            return Some(Err(Failure::Mismatch {
+                input: description,
+                check,
+            }));
+        }
+        tally.agreed += 1;
+    }
+    Some(Ok(tally))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, compare, Check, Failure, Tally, DAYS};
+    use crate::generate::generate;
+    use crate::solution::Answer;
+
+    #[test]
+    fn test_compare() {
+        assert!(compare(1, "1\n").is_none());
+        assert!(compare(20, "1\n").unwrap().is_err());
+        let checks = compare(20, "1\n2\n-3\n3\n-2\n0\n4\n").unwrap().unwrap();
+        assert_eq!(
+            checks,
+            vec![
+                Check::new("part 1", 3, 3),
+                Check::new("part 2", 1623178306, 1623178306)
+            ]
+        );
+        // The real search area is too big to try every point in.
+        let sensors = "Sensor at x=5000000, y=0: closest beacon is at x=0, y=0\n";
+        assert_eq!(compare(15, sensors), Some(Ok(Vec::new())));
+    }
+
+    #[test]
+    fn test_check() {
+        for day in DAYS {
+            let inputs =
+                (0..5).map(|seed| (format!("seed {}", seed), generate(day, 8, seed).unwrap()));
+            let tally = Tally {
+                agreed: 5,
+                skipped: 0,
+            };
+            assert_eq!(check(day, inputs), Some(Ok(tally)), "day {}", day);
+        }
+        assert_eq!(check(1, Vec::new()), None);
+
+        let sensors = "Sensor at x=5000000, y=0: closest beacon is at x=0, y=0\n";
+        let inputs = vec![("too big".to_string(), sensors.to_string())];
+        let tally = Tally {
+            agreed: 0,
+            skipped: 1,
+        };
+        assert_eq!(check(15, inputs), Some(Ok(tally)));
+
+        let inputs = vec![("no zero".to_string(), "1\n".to_string())];
+        let failure = check(20, inputs).unwrap().unwrap_err();
+        assert!(matches!(failure, Failure::Parse { .. }));
+
+        let failure = Failure::Mismatch {
+            input: "seed 3".to_string(),
+            check: Check::new("part 1", 3, Answer::Number(4)),
+        };
+        assert_eq!(
+            failure.to_string(),
+            "seed 3: part 1 is 3 but the reference gives 4"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::*;
+
+/// A small pseudo-random number generator (SplitMix64). It needs no crates and gives the same numbers for the same
+/// seed on every machine, so a generated input can be reproduced from its seed alone.
+///
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, which must not be empty.
+    ///
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "nothing to pick from");
+        // Multiplying rather than taking the remainder keeps the bias down to a negligible 2^-64 or so.
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// A number in `min..=max`.
+    ///
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        assert!(min <= max, "empty range {}..={}", min, max);
+        let span = (max - min) as u64 + 1;
+        min + ((self.next_u64() as u128 * span as u128) >> 64) as i64
+    }
+
+    /// True with probability `p`.
+    ///
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A random puzzle input for `day` that its solution accepts, from `seed`. `size` is roughly how big it is: the number
+/// of lines or items for most days (elves, rounds, monkeys, valves, blueprints, ...) and the side of the grid for the
+/// days on a grid. Some days have a smallest size that makes a sensible puzzle and use that for anything smaller.
+/// Returns `None` for a day without a generator.
+///
+pub fn generate(day: u32, size: usize, seed: u64) -> Option<String> {
+    let rng = &mut Rng::new(seed);
+    Some(match day {
+        1 => day1::generate(rng, size),
+        2 => day2::generate(rng, size),
+        3 => day3::generate(rng, size),
+        4 => day4::generate(rng, size),
+        5 => day5::generate(rng, size),
+        6 => day6::generate(rng, size),
+        7 => day7::generate(rng, size),
+        8 => day8::generate(rng, size),
+        9 => day9::generate(rng, size),
+        10 => day10::generate(rng, size),
+        11 => day11::generate(rng, size),
+        12 => day12::generate(rng, size),
+        13 => day13::generate(rng, size),
+        14 => day14::generate(rng, size),
+        15 => day15::generate(rng, size),
+        16 => day16::generate(rng, size),
+        17 => day17::generate(rng, size),
+        18 => day18::generate(rng, size),
+        19 => day19::generate(rng, size),
+        20 => day20::generate(rng, size),
+        21 => day21::generate(rng, size),
+        22 => day22::generate(rng, size),
+        23 => day23::generate(rng, size),
+        24 => day24::generate(rng, size),
+        25 => day25::generate(rng, size),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, Rng};
+    use crate::solution::{self, Answer};
+
+    #[test]
+    fn test_rng() {
+        let mut rng = Rng::new(7);
+        let numbers = (0..5).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        let mut again = Rng::new(7);
+        assert_eq!(
+            (0..5).map(|_| again.next_u64()).collect::<Vec<_>>(),
+            numbers
+        );
+        assert_ne!(Rng::new(8).next_u64(), numbers[0]);
+
+        for _ in 0..1000 {
+            assert!(rng.below(3) < 3);
+            assert!((-2..=2).contains(&rng.range(-2, 2)));
+        }
+        assert!((0..1000).any(|_| rng.range(-2, 2) == -2));
+        assert!((0..1000).any(|_| rng.range(-2, 2) == 2));
+
+        let mut items = (0..10).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..10).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_reproducible() {
+        for day in 1..=25 {
+            assert_eq!(generate(day, 5, 1), generate(day, 5, 1), "day {}", day);
+            assert_ne!(generate(day, 5, 1), generate(day, 5, 2), "day {}", day);
+        }
+        assert_eq!(generate(26, 5, 1), None);
+    }
+
+    /// Every generated input is accepted and solved, whatever its size.
+    ///
+    #[test]
+    fn test_generate() {
+        for (day, solution) in solution::all() {
+            for (size, seed) in [(0, 0), (1, 1), (3, 2), (8, 3)] {
+                let input = generate(day, size, seed).unwrap();
+                let parsed = solution.parse(&input).unwrap_or_else(|e| {
+                    panic!("day {} size {} seed {}: {}\n{}", day, size, seed, e, input)
+                });
+                assert_ne!(solution.part1(&*parsed), Answer::None, "day {}", day);
+                if day != 25 {
+                    assert_ne!(solution.part2(&*parsed), Answer::None, "day {}", day);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::parse::{cursors, ParseError};
+use std::ops::{Index, IndexMut};
+
+/// A position in a grid. Rows count down from the top and columns right from the left, both from 0.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub fn new(row: usize, col: usize) -> Pos {
+        Pos { row, col }
+    }
+}
+
+/// A step from one position to another, as `(rows, columns)`.
+///
+pub type Offset = (isize, isize);
+
+pub const UP: Offset = (-1, 0);
+pub const DOWN: Offset = (1, 0);
+pub const LEFT: Offset = (0, -1);
+pub const RIGHT: Offset = (0, 1);
+
+/// The steps to the four neighbours that share an edge.
+///
+pub const ORTHOGONAL: [Offset; 4] = [UP, RIGHT, DOWN, LEFT];
+
+/// The steps to all eight neighbours, clockwise from the one above.
+///
+pub const ALL: [Offset; 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// A rectangular grid of values, stored row by row.
+///
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    n_rows: usize,
+    n_cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A grid from its values row by row. Panics unless there are `n_rows * n_cols` of them.
+    ///
+    pub fn from_vec(n_rows: usize, n_cols: usize, cells: Vec<T>) -> Grid<T> {
+        assert_eq!(
+            cells.len(),
+            n_rows * n_cols,
+            "grid is not {n_rows}x{n_cols}"
+        );
+        Grid {
+            n_rows,
+            n_cols,
+            cells,
+        }
+    }
+
+    /// Reads a map with one character per cell. The first line sets the number of columns and every line must have
+    /// that many characters that `f` turns into values, describing them as `expected` in errors.
+    ///
+    pub fn parse(
+        day: u32,
+        input: &str,
+        expected: &str,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Grid<T>, ParseError> {
+        let mut cells = Vec::new();
+        let mut n_rows = 0;
+        let mut n_cols = None;
+        for mut cursor in cursors(day, input) {
+            let mut col = 0;
+            while Some(col) != n_cols && !(n_cols.is_none() && cursor.is_empty()) {
+                match cursor.peek().and_then(&mut f) {
+                    Some(value) => {
+                        cursor.char(expected, |_| true)?;
+                        cells.push(value);
+                    }
+                    None => return Err(cursor.expected(expected)),
+                }
+                col += 1;
+            }
+            n_cols.get_or_insert(col);
+            cursor.end()?;
+            n_rows += 1;
+        }
+        Ok(Grid::from_vec(n_rows, n_cols.unwrap_or(0), cells))
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.n_rows && pos.col < self.n_cols
+    }
+
+    /// The position at signed coordinates, if it is inside the grid.
+    ///
+    pub fn pos(&self, row: isize, col: isize) -> Option<Pos> {
+        let pos = Pos::new(usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        self.contains(pos).then_some(pos)
+    }
+
+    /// The position at signed coordinates, wrapping around the edges.
+    ///
+    pub fn wrap(&self, row: isize, col: isize) -> Pos {
+        Pos::new(
+            row.rem_euclid(self.n_rows as isize) as usize,
+            col.rem_euclid(self.n_cols as isize) as usize,
+        )
+    }
+
+    /// The position one step away, if it is inside the grid.
+    ///
+    pub fn offset(&self, pos: Pos, (dr, dc): Offset) -> Option<Pos> {
+        self.pos(pos.row as isize + dr, pos.col as isize + dc)
+    }
+
+    /// The position one step away, coming back in on the other side of the grid if the step leaves it.
+    ///
+    pub fn wrapping_offset(&self, pos: Pos, (dr, dc): Offset) -> Pos {
+        self.wrap(pos.row as isize + dr, pos.col as isize + dc)
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.row * self.n_cols + pos.col])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.row * self.n_cols + pos.col])
+        } else {
+            None
+        }
+    }
+
+    /// The up to four neighbours that share an edge with `pos`.
+    ///
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .filter_map(move |offset| self.offset(pos, offset))
+    }
+
+    /// The up to eight neighbours of `pos`, including the diagonal ones.
+    ///
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ALL.into_iter()
+            .filter_map(move |offset| self.offset(pos, offset))
+    }
+
+    /// The positions from `pos`, not included, to the edge of the grid taking steps of `offset`: along a row, a
+    /// column or a diagonal.
+    ///
+    pub fn ray(&self, pos: Pos, offset: Offset) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(Some(pos), move |&pos| self.offset(pos, offset)).skip(1)
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.n_cols..(row + 1) * self.n_cols]
+    }
+
+    pub fn col(&self, col: usize) -> impl Iterator<Item = &T> + '_ {
+        (0..self.n_rows).map(move |row| &self[Pos::new(row, col)])
+    }
+
+    /// All the positions, row by row.
+    ///
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.n_rows).flat_map(move |row| (0..self.n_cols).map(move |col| Pos::new(row, col)))
+    }
+
+    /// All the values with their positions, row by row.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// A grid of the same size with `f` applied to each value.
+    ///
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::from_vec(self.n_rows, self.n_cols, self.cells.iter().map(f).collect())
+    }
+
+    /// The grid as text, a line per row with `f` giving the character for each value.
+    ///
+    pub fn render(&self, f: impl Fn(&T) -> char) -> String {
+        let mut buf = String::with_capacity(self.n_rows * (self.n_cols + 1));
+        for row in 0..self.n_rows {
+            buf.extend(self.row(row).iter().map(&f));
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// A grid with every cell set to `value`.
+    ///
+    pub fn new(n_rows: usize, n_cols: usize, value: T) -> Grid<T> {
+        Grid::from_vec(n_rows, n_cols, vec![value; n_rows * n_cols])
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Pos, DOWN, LEFT, RIGHT, UP};
+    use crate::parse::ErrorKind;
+
+    const MAP: &str = "#..
+.#.
+..#
+#..
+";
+
+    fn parse(input: &str) -> Result<Grid<bool>, crate::parse::ParseError> {
+        Grid::parse(1, input, "`.` or `#`", |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = parse(MAP).unwrap();
+        assert_eq!((grid.n_rows(), grid.n_cols()), (4, 3));
+        assert!(grid[Pos::new(1, 1)]);
+        assert!(!grid[Pos::new(1, 2)]);
+        assert_eq!(grid.row(3), &[true, false, false]);
+        assert_eq!(grid.col(0).filter(|&&v| v).count(), 2);
+        assert_eq!(grid.render(|&v| if v { '#' } else { '.' }), MAP);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse("#..\n.x.\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.kind, ErrorKind::Expected("`.` or `#`".to_string()));
+
+        let err = parse("#..\n.#\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.kind, ErrorKind::EndOfLine("`.` or `#`".to_string()));
+
+        let err = parse("#..\n.#..\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+    }
+
+    #[test]
+    fn test_access() {
+        let grid = parse(MAP).unwrap();
+        assert_eq!(grid.pos(3, 2), Some(Pos::new(3, 2)));
+        assert_eq!(grid.pos(-1, 0), None);
+        assert_eq!(grid.pos(4, 0), None);
+        assert_eq!(grid.wrap(-1, 3), Pos::new(3, 0));
+        assert_eq!(grid.offset(Pos::new(0, 0), UP), None);
+        assert_eq!(grid.offset(Pos::new(0, 0), DOWN), Some(Pos::new(1, 0)));
+        assert_eq!(grid.wrapping_offset(Pos::new(0, 0), LEFT), Pos::new(0, 2));
+        assert_eq!(grid.get(Pos::new(0, 3)), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = parse(MAP).unwrap();
+        assert_eq!(grid.neighbours4(Pos::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbours4(Pos::new(1, 1)).count(), 4);
+        assert_eq!(grid.neighbours8(Pos::new(0, 0)).count(), 3);
+        assert_eq!(grid.neighbours8(Pos::new(1, 1)).count(), 8);
+        assert_eq!(
+            grid.ray(Pos::new(0, 0), (1, 1)).collect::<Vec<_>>(),
+            vec![Pos::new(1, 1), Pos::new(2, 2)]
+        );
+        assert_eq!(grid.ray(Pos::new(0, 0), RIGHT).count(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the directory the puzzle inputs are read from.
+///
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+#[derive(Debug)]
+pub enum InputError {
+    /// None of the candidate files for a day exist.
+    Missing { day: u32, tried: Vec<PathBuf> },
+    /// The file (or stdin) exists but could not be read.
+    Io { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Missing { day, tried } => {
+                write!(f, "no input for day {}, tried ", day)?;
+                for (i, path) in tried.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+            InputError::Io { path, error } => {
+                write!(f, "could not read {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Missing { .. } => None,
+            InputError::Io { error, .. } => Some(error),
+        }
+    }
+}
+
+/// A directory of puzzle inputs named like `day7.txt`, with variants such as `day10_example1.txt` or
+/// `day22-3d.txt`.
+///
+pub struct InputDir {
+    root: PathBuf,
+}
+
+impl InputDir {
+    pub fn new(root: impl Into<PathBuf>) -> InputDir {
+        InputDir { root: root.into() }
+    }
+
+    /// `$AOC_INPUT_DIR` if it is set, otherwise the `input` directory of this crate, so that it doesn't matter
+    /// which directory the tests or the runner are started from.
+    ///
+    pub fn from_env() -> InputDir {
+        match env::var_os(INPUT_DIR_VAR) {
+            Some(dir) => InputDir::new(dir),
+            None => InputDir::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("input")),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The files a day's input may be in, in the order they are tried. A variant is separated from the day by an
+    /// underscore or a dash.
+    ///
+    pub fn candidates(&self, day: u32, variant: Option<&str>) -> Vec<PathBuf> {
+        match variant {
+            None => vec![self.root.join(format!("day{}.txt", day))],
+            Some(variant) => vec![
+                self.root.join(format!("day{}_{}.txt", day, variant)),
+                self.root.join(format!("day{}-{}.txt", day, variant)),
+            ],
+        }
+    }
+
+    pub fn path(&self, day: u32, variant: Option<&str>) -> Result<PathBuf, InputError> {
+        let tried = self.candidates(day, variant);
+        match tried.iter().find(|path| path.is_file()) {
+            Some(path) => Ok(path.clone()),
+            None => Err(InputError::Missing { day, tried }),
+        }
+    }
+
+    pub fn read(&self, day: u32, variant: Option<&str>) -> Result<String, InputError> {
+        read_path(&self.path(day, variant)?)
+    }
+}
+
+pub fn read_path(path: &Path) -> Result<String, InputError> {
+    fs::read_to_string(path).map_err(|error| InputError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+pub fn read_stdin() -> Result<String, InputError> {
+    let mut buf = String::new();
+    io::stdin()
+        .read_to_string(&mut buf)
+        .map_err(|error| InputError::Io {
+            path: PathBuf::from("<stdin>"),
+            error,
+        })?;
+    Ok(buf)
+}
+
+/// A day's input from the default input directory.
+///
+pub fn read(day: u32) -> Result<String, InputError> {
+    InputDir::from_env().read(day, None)
+}
+
+/// A named variant of a day's input from the default input directory, e.g. `read_variant(10, "example1")`.
+///
+pub fn read_variant(day: u32, variant: &str) -> Result<String, InputError> {
+    InputDir::from_env().read(day, Some(variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_path, InputDir, InputError};
+    use std::path::Path;
+
+    fn input_dir() -> InputDir {
+        InputDir::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("input"))
+    }
+
+    #[test]
+    fn test_read() {
+        let input = input_dir().read(1, None).unwrap();
+        assert!(input.starts_with(char::is_numeric));
+    }
+
+    #[test]
+    fn test_read_variant() {
+        let dir = input_dir();
+        assert!(dir
+            .path(10, Some("example1"))
+            .unwrap()
+            .ends_with("day10_example1.txt"));
+        assert!(dir.path(22, Some("3d")).unwrap().ends_with("day22-3d.txt"));
+    }
+
+    #[test]
+    fn test_missing() {
+        match input_dir().read(10, Some("example2")) {
+            Err(InputError::Missing { day, tried }) => {
+                assert_eq!(day, 10);
+                assert_eq!(tried.len(), 2);
+            }
+            _ => panic!("expected a missing input"),
+        }
+
+        match read_path(Path::new("no/such/file.txt")) {
+            Err(InputError::Io { path, .. }) => assert_eq!(path, Path::new("no/such/file.txt")),
+            _ => panic!("expected an io error"),
+        }
+    }
+}
@@ -1,28 +1,44 @@
 extern crate core;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day2;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod answers;
+pub mod bench;
+pub mod cycle;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod differential;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod ocr;
+pub mod parse;
+pub mod render;
+pub mod scaffold;
+pub mod search;
+pub mod solution;
+// Built so that it keeps up with the modules it uses, but without its tests, which fail until a day is solved.
+#[cfg(not(test))]
+#[allow(dead_code)]
 mod template;
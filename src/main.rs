@@ -0,0 +1,834 @@
+use aoc_2022::answers::{self, Answers, Verdict, ANSWERS_FILE};
+use aoc_2022::bench::{self, Format};
+use aoc_2022::differential;
+use aoc_2022::generate;
+use aoc_2022::input::{self, InputDir, InputError};
+use aoc_2022::render::{self, Frame, Frames};
+use aoc_2022::scaffold;
+use aoc_2022::solution::{self, Answer};
+use std::env;
+use std::fs;
+use std::iter;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: aoc run <day|all> [<part>] [--input <path>|-] [--input-dir <dir>] [--variant <name>]
+       aoc verify [<day|all>] [--input-dir <dir>] [--answers <path>]
+       aoc bench [<day|all>] [--runs <n>] [--input-dir <dir>] [--output <path>] [--baseline <path> [--threshold <percent>]]
+       aoc new <day> [--input-dir <dir>]
+       aoc draw <day> [<part>] [--input <path>|-] [--input-dir <dir>] [--variant <name>] [--output <path>]
+                [--frames <dir> [--every <n>]] [--format ascii|ppm|pgm] [--scale <n>]
+       aoc generate <day> [--size <n>] [--seed <n>] [--output <path>]
+       aoc check [<day|all>] [--size <n>] [--seeds <n>] [--input-dir <dir>]
+
+Inputs are read from `<dir>/day<day>.txt`, or `<dir>/day<day>_<name>.txt` / `<dir>/day<day>-<name>.txt` for a variant.
+The directory defaults to $AOC_INPUT_DIR, then the crate's `input` directory. `--input -` reads from stdin.
+`verify` checks the answers against `<dir>/answers.txt`, one `<day> <part> <variant|-> <answer>` per line.
+`bench` times parsing and each part over `--runs` runs (default 10). Timings are saved as JSON if the output path ends
+in `.json`, otherwise as CSV, and a baseline saved either way is flagged where the median got more than `--threshold`
+percent (default 10) slower.
+`new` creates `src/day<day>.rs` from the template, declares it in `src/lib.rs`, adds it to the solutions in
+`src/solution.rs` and creates empty input and example files. It refuses to touch a day that already exists.
+`draw` renders the final state of the simulation of day 9, 14, 17, 22, 23 or 24 (part 1 by default) as text on
+stdout or to `--output`, and with `--frames` also every `--every` steps (default 1) to `<dir>/day<day>_<step>.<ext>`.
+The format is taken from the output's extension (`.ppm`, `.pgm`) unless given, and images draw each cell as a
+`--scale` by `--scale` square (default 4).
+`generate` writes a random input for the day to stdout or `--output`. `--size` is roughly its number of lines or
+items, or the side of the grid for the days on a grid (default 10), and the same `--seed` (default 0) always gives the
+same input.
+`check` solves generated inputs for `--seeds` seeds (default 20) of `--size` (default 10), and the day's `example`
+variant if there is one, both with the day's solution and with a slow but simple reference solution, and reports the
+first input they disagree on. Days 11, 15, 17 and 20 have a reference solution.";
+
+enum Error {
+    Usage(String),
+    Failed,
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let res = match args.first().map(|s| s.as_str()) {
+        Some("run") => run(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        Some("bench") => bench(&args[1..]),
+        Some("new") => new(&args[1..]),
+        Some("draw") => draw(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("check") => check(&args[1..]),
+        Some("help") | Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(cmd) => Err(Error::Usage(format!("unknown command `{}`", cmd))),
+        None => Err(Error::Usage("missing command".to_string())),
+    };
+
+    match res {
+        Ok(()) => {}
+        Err(Error::Usage(msg)) => {
+            eprintln!("aoc: {}", msg);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        Err(Error::Failed) => process::exit(1),
+    }
+}
+
+/// `aoc run <day|all> [<part>] [--input <path>|-] [--input-dir <dir>] [--variant <name>]`
+///
+/// Prints one `day <day> part <part>: <answer>` line per solved part. Answers that span several lines (the day 10
+/// CRT) start on the line after the header. Failures are reported on stderr and the exit code is 1 if any part failed.
+///
+fn run(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut input_path = None;
+    let mut input_dir = None;
+    let mut variant = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" => input_path = Some(option_value(arg, iter.next())?),
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            "--variant" => variant = Some(option_value(arg, iter.next())?),
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let days = match positional.first() {
+        Some(&"all") if input_path.is_some() => {
+            return Err(Error::Usage(
+                "--input cannot be used with `all`".to_string(),
+            ))
+        }
+        Some(&"all") => (1..=25).collect(),
+        Some(day) => vec![parse_arg(day, "day", 1..=25)?],
+        None => return Err(Error::Usage("missing day".to_string())),
+    };
+    if input_path.is_some() && (input_dir.is_some() || variant.is_some()) {
+        return Err(Error::Usage(
+            "--input cannot be combined with --input-dir or --variant".to_string(),
+        ));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+    let parts = match positional.get(1) {
+        Some(part) => vec![parse_arg(part, "part", 1..=2)?],
+        None => vec![1, 2],
+    };
+    if positional.len() > 2 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[2]
+        )));
+    }
+
+    let mut failed = false;
+    for &day in days.iter() {
+        let solution = match solution::get(day) {
+            Some(solution) => solution,
+            None => {
+                eprintln!("day {}: not solvable from the puzzle input yet", day);
+                failed = true;
+                continue;
+            }
+        };
+
+        let input = match input_path.as_deref() {
+            Some("-") => input::read_stdin(),
+            Some(path) => input::read_path(Path::new(path)),
+            None => input_dir.read(day, variant.as_deref()),
+        };
+        let input = match input {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("day {}: {}", day, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        let parsed = match solution.parse(&input) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("{}", e);
+                failed = true;
+                continue;
+            }
+        };
+        for &part in parts.iter() {
+            let answer = match part {
+                1 => solution.part1(parsed.as_ref()),
+                _ => solution.part2(parsed.as_ref()),
+            };
+            match answer {
+                Answer::None if parts.len() > 1 => {}
+                Answer::None => {
+                    eprintln!("day {} part {}: there is no such puzzle", day, part);
+                    failed = true;
+                }
+                Answer::Unsolvable(reason) => {
+                    eprintln!("day {} part {}: no answer, {}", day, part, reason);
+                    failed = true;
+                }
+                Answer::Text(s) if s.contains('\n') => {
+                    println!("day {} part {}:\n{}", day, part, s.trim_end())
+                }
+                answer => println!("day {} part {}: {}", day, part, answer),
+            }
+        }
+    }
+
+    if failed {
+        Err(Error::Failed)
+    } else {
+        Ok(())
+    }
+}
+
+/// `aoc verify [<day|all>] [--input-dir <dir>] [--answers <path>]`
+///
+/// Solves every part of every solvable day, on the puzzle input and on each variant with recorded answers, and prints
+/// whether each answer passes, fails or is missing from the answers file. The exit code is 1 if anything failed.
+///
+fn verify(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut input_dir = None;
+    let mut answers_path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            "--answers" => answers_path = Some(option_value(arg, iter.next())?),
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let days = match positional.first() {
+        None | Some(&"all") => (1..=25).collect(),
+        Some(day) => vec![parse_arg(day, "day", 1..=25)?],
+    };
+    if positional.len() > 1 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[1]
+        )));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+    let answers_path = match answers_path {
+        Some(path) => path.into(),
+        None => input_dir.root().join(ANSWERS_FILE),
+    };
+    let answers = match input::read_path(&answers_path).map(|text| Answers::parse(&text)) {
+        Ok(Ok(answers)) => answers,
+        Ok(Err(e)) => {
+            eprintln!("{}: {}", answers_path.display(), e);
+            return Err(Error::Failed);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Error::Failed);
+        }
+    };
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for &day in days.iter() {
+        let solution = match solution::get(day) {
+            Some(solution) => solution,
+            None if days.len() > 1 => continue,
+            None => {
+                eprintln!("day {}: not solvable from the puzzle input yet", day);
+                return Err(Error::Failed);
+            }
+        };
+
+        let variants = iter::once(None).chain(answers.variants(day).into_iter().map(Some));
+        for variant in variants {
+            let label = match variant {
+                Some(variant) => format!("day {} ({})", day, variant),
+                None => format!("day {}", day),
+            };
+            let parsed = match input_dir.read(day, variant) {
+                Ok(input) => solution.parse(&input).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            let parsed = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    println!("{}: FAIL, {}", label, e);
+                    failed += 1;
+                    continue;
+                }
+            };
+
+            for part in 1..=2 {
+                let answer = match part {
+                    1 => solution.part1(parsed.as_ref()),
+                    _ => solution.part2(parsed.as_ref()),
+                };
+                let expected = answers.get(day, part, variant);
+                if answer == Answer::None && expected.is_none() {
+                    continue;
+                }
+                match answers::check(expected, &answer) {
+                    Verdict::Pass => {
+                        println!("{} part {}: pass", label, part);
+                        passed += 1;
+                    }
+                    Verdict::Fail { expected } => {
+                        println!(
+                            "{} part {}: FAIL, expected {}, got {}",
+                            label,
+                            part,
+                            one_line(&expected),
+                            one_line(&answer.to_string())
+                        );
+                        failed += 1;
+                    }
+                    Verdict::Missing => {
+                        println!(
+                            "{} part {}: missing, got {}",
+                            label,
+                            part,
+                            one_line(&answer.to_string())
+                        );
+                        missing += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    if failed > 0 {
+        Err(Error::Failed)
+    } else {
+        Ok(())
+    }
+}
+
+/// `aoc bench [<day|all>] [--runs <n>] [--input-dir <dir>] [--output <path>] [--baseline <path> [--threshold <percent>]]`
+///
+/// Prints the min, median and max time of each stage of each solvable day. With a baseline, the exit code is 1 if
+/// any stage regressed.
+///
+fn bench(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut runs = 10;
+    let mut input_dir = None;
+    let mut output = None;
+    let mut baseline = None;
+    let mut threshold = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--runs" => runs = parse_arg(&option_value(arg, iter.next())?, "runs", 1..=u32::MAX)?,
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            "--output" => output = Some(option_value(arg, iter.next())?),
+            "--baseline" => baseline = Some(option_value(arg, iter.next())?),
+            "--threshold" => {
+                let value = option_value(arg, iter.next())?;
+                match value.parse::<f64>() {
+                    Ok(v) if v >= 0.0 => threshold = Some(v),
+                    _ => {
+                        return Err(Error::Usage(format!(
+                            "threshold must be a percentage, got `{}`",
+                            value
+                        )))
+                    }
+                }
+            }
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let days = match positional.first() {
+        None | Some(&"all") => (1..=25).collect(),
+        Some(day) => vec![parse_arg(day, "day", 1..=25)?],
+    };
+    if positional.len() > 1 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[1]
+        )));
+    }
+    if threshold.is_some() && baseline.is_none() {
+        return Err(Error::Usage("--threshold requires --baseline".to_string()));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+    let baseline = match baseline {
+        Some(path) => match input::read_path(Path::new(&path)).map(|text| bench::read(&text)) {
+            Ok(Ok(timings)) => Some(timings),
+            Ok(Err(e)) => {
+                eprintln!("{}: {}", path, e);
+                return Err(Error::Failed);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Error::Failed);
+            }
+        },
+        None => None,
+    };
+
+    let mut failed = false;
+    let mut timings = Vec::new();
+    for &day in days.iter() {
+        let solution = match solution::get(day) {
+            Some(solution) => solution,
+            None if days.len() > 1 => continue,
+            None => {
+                eprintln!("day {}: not solvable from the puzzle input yet", day);
+                return Err(Error::Failed);
+            }
+        };
+        let res = match input_dir.read(day, None) {
+            Ok(input) => {
+                bench::measure(day, solution, &input, runs as usize).map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        match res {
+            Ok(day_timings) => {
+                for timing in day_timings.iter() {
+                    println!(
+                        "day {} {}: min {}, median {}, max {}",
+                        day,
+                        timing.stage,
+                        bench::format_duration(timing.stats.min),
+                        bench::format_duration(timing.stats.median),
+                        bench::format_duration(timing.stats.max)
+                    );
+                }
+                timings.extend(day_timings);
+            }
+            Err(e) => {
+                eprintln!("day {}: {}", day, e);
+                failed = true;
+            }
+        }
+    }
+
+    if let Some(path) = output {
+        let format = Format::from_path(Path::new(&path));
+        if let Err(e) = fs::write(&path, bench::write(&timings, format)) {
+            eprintln!("could not write {}: {}", path, e);
+            failed = true;
+        }
+    }
+
+    if let Some(baseline) = baseline {
+        let threshold = threshold.unwrap_or(10.0);
+        let regressions = bench::regressions(&timings, &baseline, threshold);
+        for regression in regressions.iter() {
+            println!(
+                "day {} {}: REGRESSION, median {} against {} in the baseline (+{:.0}%)",
+                regression.day,
+                regression.stage,
+                bench::format_duration(regression.current),
+                bench::format_duration(regression.baseline),
+                regression.percent()
+            );
+        }
+        println!("{} regressions over {}%", regressions.len(), threshold);
+        failed |= !regressions.is_empty();
+    }
+
+    if failed {
+        Err(Error::Failed)
+    } else {
+        Ok(())
+    }
+}
+
+/// `aoc new <day> [--input-dir <dir>]`
+///
+/// Scaffolds a day in this crate and prints the files it wrote.
+///
+fn new(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut input_dir = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let day = match positional.first() {
+        Some(day) => parse_arg(day, "day", 1..=25)?,
+        None => return Err(Error::Usage("missing day".to_string())),
+    };
+    if positional.len() > 1 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[1]
+        )));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+
+    match scaffold::create(Path::new(env!("CARGO_MANIFEST_DIR")), &input_dir, day) {
+        Ok(written) => {
+            for path in written.iter() {
+                println!("wrote {}", path.display());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Error::Failed)
+        }
+    }
+}
+
+/// `aoc draw <day> [<part>] [--input <path>|-] [--input-dir <dir>] [--variant <name>] [--output <path>]
+/// [--frames <dir> [--every <n>]] [--format ascii|ppm|pgm] [--scale <n>]`
+///
+fn draw(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut input_path = None;
+    let mut input_dir = None;
+    let mut variant = None;
+    let mut output = None;
+    let mut frames_dir = None;
+    let mut every = None;
+    let mut format = None;
+    let mut scale = 4;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" => input_path = Some(option_value(arg, iter.next())?),
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            "--variant" => variant = Some(option_value(arg, iter.next())?),
+            "--output" => output = Some(option_value(arg, iter.next())?),
+            "--frames" => frames_dir = Some(option_value(arg, iter.next())?),
+            "--every" => {
+                every = Some(parse_arg(
+                    &option_value(arg, iter.next())?,
+                    "every",
+                    1..=u32::MAX,
+                )?)
+            }
+            "--scale" => scale = parse_arg(&option_value(arg, iter.next())?, "scale", 1..=64)?,
+            "--format" => {
+                format = match option_value(arg, iter.next())?.as_str() {
+                    "ascii" => Some(render::Format::Ascii),
+                    "ppm" => Some(render::Format::Ppm),
+                    "pgm" => Some(render::Format::Pgm),
+                    s => {
+                        return Err(Error::Usage(format!(
+                            "format must be ascii, ppm or pgm, got `{}`",
+                            s
+                        )))
+                    }
+                }
+            }
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let day = match positional.first() {
+        Some(day) => parse_arg(day, "day", 1..=25)?,
+        None => return Err(Error::Usage("missing day".to_string())),
+    };
+    let part = match positional.get(1) {
+        Some(part) => parse_arg(part, "part", 1..=2)?,
+        None => 1,
+    };
+    if positional.len() > 2 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[2]
+        )));
+    }
+    if input_path.is_some() && (input_dir.is_some() || variant.is_some()) {
+        return Err(Error::Usage(
+            "--input cannot be combined with --input-dir or --variant".to_string(),
+        ));
+    }
+    if every.is_some() && frames_dir.is_none() {
+        return Err(Error::Usage("--every requires --frames".to_string()));
+    }
+    let format = format.unwrap_or_else(|| match output.as_deref() {
+        Some(path) => render::Format::from_path(Path::new(path)),
+        None => render::Format::Ascii,
+    });
+    if output.is_none() && frames_dir.is_none() && format != render::Format::Ascii {
+        return Err(Error::Usage("images need --output or --frames".to_string()));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+
+    let input = match input_path.as_deref() {
+        Some("-") => input::read_stdin(),
+        Some(path) => input::read_path(Path::new(path)),
+        None => input_dir.read(day, variant.as_deref()),
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("day {}: {}", day, e);
+            return Err(Error::Failed);
+        }
+    };
+    if let Some(dir) = frames_dir.as_deref() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("could not create {}: {}", dir, e);
+            return Err(Error::Failed);
+        }
+    }
+
+    let scale = scale as usize;
+    let mut last = None;
+    let mut errors = Vec::new();
+    let mut sink = |step: usize, frame: &Frame| {
+        if let Some(dir) = frames_dir.as_deref() {
+            let path =
+                Path::new(dir).join(format!("day{}_{:06}.{}", day, step, format.extension()));
+            if let Err(e) = fs::write(&path, render::encode(frame, format, scale)) {
+                errors.push(format!("could not write {}: {}", path.display(), e));
+            }
+        }
+        last = Some(frame.clone());
+    };
+    let every = if frames_dir.is_some() {
+        every.unwrap_or(1) as usize
+    } else {
+        0
+    };
+    let mut frames = Frames::new(every, &mut sink);
+    match render::draw(day, part, &input, &mut frames) {
+        Some(Ok(())) => {}
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return Err(Error::Failed);
+        }
+        None => {
+            eprintln!("day {}: there is nothing to draw", day);
+            return Err(Error::Failed);
+        }
+    }
+    if let Some(e) = errors.first() {
+        match errors.len() {
+            1 => eprintln!("{}", e),
+            n => eprintln!("{} (and {} more)", e, n - 1),
+        }
+        return Err(Error::Failed);
+    }
+
+    let last = match last {
+        Some(frame) => render::encode(&frame, format, scale),
+        None => {
+            eprintln!("day {}: drawing it gave no frame", day);
+            return Err(Error::Failed);
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, last) {
+                eprintln!("could not write {}: {}", path, e);
+                return Err(Error::Failed);
+            }
+        }
+        None if format == render::Format::Ascii => {
+            print!("{}", String::from_utf8(last).unwrap())
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// `aoc generate <day> [--size <n>] [--seed <n>] [--output <path>]`
+///
+fn generate(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut size = 10;
+    let mut seed = 0;
+    let mut output = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => size = parse_arg(&option_value(arg, iter.next())?, "size", 0..=1_000_000)?,
+            "--seed" => {
+                let value = option_value(arg, iter.next())?;
+                seed = value.parse().map_err(|_| {
+                    Error::Usage(format!("seed must be a whole number, got `{}`", value))
+                })?
+            }
+            "--output" => output = Some(option_value(arg, iter.next())?),
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let day = match positional.first() {
+        Some(day) => parse_arg(day, "day", 1..=25)?,
+        None => return Err(Error::Usage("missing day".to_string())),
+    };
+    if positional.len() > 1 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[1]
+        )));
+    }
+
+    let input = match generate::generate(day, size as usize, seed) {
+        Some(input) => input,
+        None => {
+            eprintln!("day {}: there is no generator", day);
+            return Err(Error::Failed);
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, input) {
+                eprintln!("could not write {}: {}", path, e);
+                return Err(Error::Failed);
+            }
+        }
+        None => print!("{}", input),
+    }
+    Ok(())
+}
+
+/// `aoc check [<day|all>] [--size <n>] [--seeds <n>] [--input-dir <dir>]`
+///
+/// Prints `day <day>: <n> inputs agree, <m> skipped` for each day checked, or the first input the solutions disagree
+/// on, which can be made again with `aoc generate`. Inputs with nothing to compare are skipped. The exit code is 1 if
+/// any day failed or had no inputs to compare.
+///
+fn check(args: &[String]) -> Result<(), Error> {
+    let mut positional = Vec::new();
+    let mut size = 10;
+    let mut seeds = 20;
+    let mut input_dir = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--size" => size = parse_arg(&option_value(arg, iter.next())?, "size", 0..=1_000_000)?,
+            "--seeds" => {
+                seeds = parse_arg(&option_value(arg, iter.next())?, "seeds", 0..=1_000_000)?
+            }
+            "--input-dir" => input_dir = Some(option_value(arg, iter.next())?),
+            s if s.starts_with("--") => {
+                return Err(Error::Usage(format!("unknown option `{}`", s)))
+            }
+            s => positional.push(s),
+        }
+    }
+
+    let days = match positional.first() {
+        None | Some(&"all") => differential::DAYS.to_vec(),
+        Some(day) => vec![parse_arg(day, "day", 1..=25)?],
+    };
+    if positional.len() > 1 {
+        return Err(Error::Usage(format!(
+            "unexpected argument `{}`",
+            positional[1]
+        )));
+    }
+    let input_dir = match input_dir {
+        Some(dir) => InputDir::new(dir),
+        None => InputDir::from_env(),
+    };
+
+    let mut failed = false;
+    for &day in days.iter() {
+        let example = match input_dir.read(day, Some("example")) {
+            Ok(input) => Some(("the example".to_string(), input)),
+            Err(InputError::Missing { .. }) => None,
+            Err(e) => {
+                eprintln!("{}", e);
+                failed = true;
+                continue;
+            }
+        };
+        let generated = (0..seeds as u64).map(|seed| {
+            let input = generate::generate(day, size as usize, seed).unwrap_or_default();
+            (format!("size {} seed {}", size, seed), input)
+        });
+        match differential::check(day, example.into_iter().chain(generated)) {
+            Some(Ok(tally)) if tally.agreed == 0 => {
+                println!(
+                    "day {}: FAIL, nothing to compare, {} skipped",
+                    day, tally.skipped
+                );
+                failed = true;
+            }
+            Some(Ok(tally)) => println!(
+                "day {}: {} inputs agree, {} skipped",
+                day, tally.agreed, tally.skipped
+            ),
+            Some(Err(failure)) => {
+                println!("day {}: FAIL, {}", day, failure);
+                failed = true;
+            }
+            None => {
+                eprintln!("day {}: there is no reference solution", day);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        Err(Error::Failed)
+    } else {
+        Ok(())
+    }
+}
+
+/// An answer written the way it is in the answers file, with its line breaks as `\n`.
+///
+fn one_line(answer: &str) -> String {
+    answer.trim_end().replace('\n', "\\n")
+}
+
+fn option_value(option: &str, value: Option<&String>) -> Result<String, Error> {
+    match value {
+        Some(value) => Ok(value.clone()),
+        None => Err(Error::Usage(format!("{} requires a value", option))),
+    }
+}
+
+fn parse_arg(arg: &str, name: &str, range: std::ops::RangeInclusive<u32>) -> Result<u32, Error> {
+    match arg.parse() {
+        Ok(v) if range.contains(&v) => Ok(v),
+        _ => Err(Error::Usage(format!(
+            "{} must be between {} and {}, got `{}`",
+            name,
+            range.start(),
+            range.end(),
+            arg
+        ))),
+    }
+}
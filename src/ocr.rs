@@ -0,0 +1,163 @@
+use std::fmt;
+
+/// The letters of the puzzle font, each 4 columns wide and 6 rows high, written row by row.
+///
+const FONT: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum OcrError {
+    /// The screen isn't 6 rows of letters 4 columns wide with a dark column between them.
+    Shape(String),
+    /// The letter at `index`, counting from 1, isn't in the font. The glyph is its rows joined by `/`.
+    Unknown { index: usize, glyph: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Shape(message) => write!(f, "not a screen of letters: {}", message),
+            OcrError::Unknown { index, glyph } => {
+                write!(f, "letter {} is not in the font: {}", index, glyph)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// The letters shown on a screen of `#` (lit) and `.` (dark) pixels, one line per row.
+///
+pub fn read(screen: &str) -> Result<String, OcrError> {
+    let rows = screen.lines().collect::<Vec<_>>();
+    if rows.len() != HEIGHT {
+        return Err(OcrError::Shape(format!(
+            "expected {} rows, got {}",
+            HEIGHT,
+            rows.len()
+        )));
+    }
+    if rows
+        .iter()
+        .any(|row| row.chars().any(|c| c != '#' && c != '.'))
+    {
+        return Err(OcrError::Shape("pixels must be `#` or `.`".to_string()));
+    }
+    let width = rows[0].len();
+    if let Some(row) = rows.iter().position(|row| row.len() != width) {
+        return Err(OcrError::Shape(format!(
+            "row {} is not {} pixels wide like the first",
+            row + 1,
+            width
+        )));
+    }
+    if width % (WIDTH + 1) != 0 && width % (WIDTH + 1) != WIDTH {
+        return Err(OcrError::Shape(format!(
+            "{} columns is not a whole number of letters",
+            width
+        )));
+    }
+    let gaps = (WIDTH..width).step_by(WIDTH + 1);
+    if let Some(col) = gaps
+        .into_iter()
+        .find(|&col| rows.iter().any(|row| &row[col..=col] == "#"))
+    {
+        return Err(OcrError::Shape(format!(
+            "column {} between letters is lit",
+            col + 1
+        )));
+    }
+
+    (0..width.div_ceil(WIDTH + 1))
+        .map(|i| {
+            let col = i * (WIDTH + 1);
+            let glyph = rows
+                .iter()
+                .map(|row| &row[col..col + WIDTH])
+                .collect::<Vec<_>>();
+            FONT.iter()
+                .find(|(_, pixels)| *pixels == glyph.concat())
+                .map(|&(letter, _)| letter)
+                .ok_or_else(|| OcrError::Unknown {
+                    index: i + 1,
+                    glyph: glyph.join("/"),
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, OcrError, FONT};
+
+    /// The letters side by side as they appear on a screen.
+    ///
+    fn screen(letters: &str) -> String {
+        let mut rows = vec![String::new(); 6];
+        for letter in letters.chars() {
+            let (_, pixels) = FONT.iter().find(|&&(c, _)| c == letter).unwrap();
+            for (row, line) in rows.iter_mut().enumerate() {
+                line.push_str(&pixels[row * 4..(row + 1) * 4]);
+                line.push('.');
+            }
+        }
+        rows.iter().map(|row| format!("{}\n", row)).collect()
+    }
+
+    #[test]
+    fn test_read() {
+        let letters = FONT.iter().map(|&(c, _)| c).collect::<String>();
+        assert_eq!(read(&screen(&letters)), Ok(letters));
+
+        // The last column can be left off.
+        let screen = screen("HI")
+            .lines()
+            .map(|row| format!("{}\n", &row[..9]))
+            .collect::<String>();
+        assert_eq!(read(&screen), Ok("HI".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            read(&screen("AB").replacen(".##..", ".##.#", 1)),
+            Err(OcrError::Shape(
+                "column 5 between letters is lit".to_string()
+            ))
+        );
+        let screen = screen("AB").replacen(".##.", "####", 1);
+        assert_eq!(
+            read(&screen),
+            Err(OcrError::Unknown {
+                index: 1,
+                glyph: "####/#..#/#..#/####/#..#/#..#".to_string()
+            })
+        );
+        assert!(matches!(read("#..#\n"), Err(OcrError::Shape(_))));
+        assert!(matches!(
+            read(&"#..#..\n".repeat(6)),
+            Err(OcrError::Shape(_))
+        ));
+    }
+}
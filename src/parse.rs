@@ -0,0 +1,300 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in a day's puzzle input. Lines and columns are 1-based, columns count characters.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// Something other than the expected text was found.
+    Expected(String),
+    /// The line ended before the expected text.
+    EndOfLine(String),
+    /// The input ended before the expected text.
+    EndOfInput(String),
+    /// The line was complete but more text followed.
+    TrailingText,
+    /// A run of digits that doesn't fit the number type it is parsed into.
+    InvalidNumber(String),
+}
+
+impl ParseError {
+    /// An error for something missing at the end of the input, reported on the line after the last one.
+    ///
+    pub fn end_of_input(day: u32, input: &str, expected: impl Into<String>) -> ParseError {
+        ParseError {
+            day,
+            line: input.lines().count() + 1,
+            column: 1,
+            kind: ErrorKind::EndOfInput(expected.into()),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} line {} column {}: {}",
+            self.day, self.line, self.column, self.kind
+        )
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Expected(expected) => write!(f, "expected {}", expected),
+            ErrorKind::EndOfLine(expected) => {
+                write!(f, "expected {}, found the end of the line", expected)
+            }
+            ErrorKind::EndOfInput(expected) => {
+                write!(f, "expected {}, found the end of the input", expected)
+            }
+            ErrorKind::TrailingText => write!(f, "unexpected text after the end of the line"),
+            ErrorKind::InvalidNumber(s) => write!(f, "invalid number `{}`", s),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Reads one line of puzzle input from left to right, keeping track of the position for error reporting.
+///
+pub struct Cursor<'a> {
+    day: u32,
+    line: usize,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(day: u32, line: usize, text: &'a str) -> Cursor<'a> {
+        Cursor {
+            day,
+            line,
+            text,
+            pos: 0,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.text[..self.pos].chars().count() + 1
+    }
+
+    /// The whole line, regardless of how much has been read.
+    ///
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The part of the line that hasn't been read yet.
+    ///
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// An error at the current position.
+    ///
+    pub fn error(&self, kind: ErrorKind) -> ParseError {
+        ParseError {
+            day: self.day,
+            line: self.line,
+            column: self.column(),
+            kind,
+        }
+    }
+
+    /// An error at an earlier column of the line, e.g. the start of a value that turned out to be invalid.
+    ///
+    pub fn error_at(&self, column: usize, kind: ErrorKind) -> ParseError {
+        ParseError {
+            column,
+            ..self.error(kind)
+        }
+    }
+
+    /// `Expected` at the current position, or `EndOfLine` if the whole line has been read.
+    ///
+    pub fn expected(&self, expected: impl Into<String>) -> ParseError {
+        if self.is_empty() {
+            self.error(ErrorKind::EndOfLine(expected.into()))
+        } else {
+            self.error(ErrorKind::Expected(expected.into()))
+        }
+    }
+
+    /// Skips over `literal`.
+    ///
+    pub fn literal(&mut self, literal: &str) -> Result<(), ParseError> {
+        match self.rest().strip_prefix(literal) {
+            Some(_) => {
+                self.pos += literal.len();
+                Ok(())
+            }
+            None => Err(self.expected(format!("`{}`", literal))),
+        }
+    }
+
+    /// Reads a single character, describing it as `expected` if there is none or `valid` rejects it.
+    ///
+    pub fn char(
+        &mut self,
+        expected: &str,
+        valid: impl Fn(char) -> bool,
+    ) -> Result<char, ParseError> {
+        match self.peek() {
+            Some(c) if valid(c) => {
+                self.pos += c.len_utf8();
+                Ok(c)
+            }
+            _ => Err(self.expected(expected)),
+        }
+    }
+
+    /// Reads characters for as long as `pred` holds, possibly none.
+    ///
+    pub fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Reads a non-empty run of ASCII letters and digits.
+    ///
+    pub fn word(&mut self, expected: &str) -> Result<&'a str, ParseError> {
+        if !self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+            return Err(self.expected(expected));
+        }
+        Ok(self.take_while(|c| c.is_ascii_alphanumeric()))
+    }
+
+    /// Reads a decimal number with an optional leading minus sign.
+    ///
+    pub fn number<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        let start = self.pos;
+        let rest = self.rest();
+        let sign = usize::from(rest.starts_with('-'));
+        let digits = rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign);
+        if digits == 0 {
+            return Err(self.expected("a number"));
+        }
+
+        let s = &rest[..sign + digits];
+        match T::from_str(s) {
+            Ok(v) => {
+                self.pos += s.len();
+                Ok(v)
+            }
+            Err(_) => {
+                self.pos = start;
+                Err(self.error(ErrorKind::InvalidNumber(s.to_string())))
+            }
+        }
+    }
+
+    /// Checks that the whole line has been read.
+    ///
+    pub fn end(&self) -> Result<(), ParseError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::TrailingText))
+        }
+    }
+}
+
+/// A cursor for each line of a day's input, numbered from 1.
+///
+pub fn cursors(day: u32, input: &str) -> impl Iterator<Item = Cursor<'_>> {
+    input
+        .lines()
+        .enumerate()
+        .map(move |(i, line)| Cursor::new(day, i + 1, line))
+}
+
+/// Parses every line of the input with `f`, which must read the whole line.
+///
+pub fn parse_lines<T>(
+    day: u32,
+    input: &str,
+    mut f: impl FnMut(&mut Cursor) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    cursors(day, input)
+        .map(|mut cursor| {
+            let res = f(&mut cursor)?;
+            cursor.end()?;
+            Ok(res)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_lines, Cursor, ErrorKind, ParseError};
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = Cursor::new(1, 1, "move -12 from x");
+        cursor.literal("move ").unwrap();
+        assert_eq!(cursor.number::<i32>(), Ok(-12));
+        cursor.literal(" from ").unwrap();
+        assert_eq!(
+            cursor.number::<i32>(),
+            Err(ParseError {
+                day: 1,
+                line: 1,
+                column: 15,
+                kind: ErrorKind::Expected("a number".to_string())
+            })
+        );
+        assert_eq!(cursor.word("a word"), Ok("x"));
+        assert_eq!(
+            cursor.literal("!").unwrap_err().kind,
+            ErrorKind::EndOfLine("`!`".to_string())
+        );
+        assert!(cursor.end().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let mut cursor = Cursor::new(1, 1, "300");
+        let err = cursor.number::<u8>().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidNumber("300".to_string()));
+        assert_eq!(err.column, 1);
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let res = parse_lines(3, "1\n2\n3 \n", |cursor| cursor.number::<u32>());
+        let err = res.unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (3, 3, 2));
+        assert_eq!(err.kind, ErrorKind::TrailingText);
+        assert_eq!(
+            err.to_string(),
+            "day 3 line 3 column 2: unexpected text after the end of the line"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use crate::grid::{Grid, Pos};
+use crate::parse::ParseError;
+use crate::solution::Solution;
+use crate::*;
+use std::path::Path;
+
+/// A picture of a simulation at one step, with a character per cell as in the puzzle descriptions.
+///
+pub type Frame = Grid<char>;
+
+pub type Rgb = [u8; 3];
+
+/// The colour each character is drawn in. Walls and rock are light, open ground dark, and moving things stand out.
+///
+pub fn colour(c: char) -> Rgb {
+    match c {
+        ' ' => [0, 0, 0],
+        '.' => [32, 32, 32],
+        '#' => [200, 200, 200],
+        '|' | '-' | '+' => [120, 120, 120],
+        'o' => [230, 190, 80],
+        '<' | '>' | '^' | 'v' => [80, 140, 230],
+        '2'..='9' => [40, 80, 200],
+        's' => [60, 200, 90],
+        _ => [230, 60, 60],
+    }
+}
+
+/// The ways a frame can be written out: as text, or as a binary PPM (colour) or PGM (grey) image.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Ascii,
+    Ppm,
+    Pgm,
+}
+
+impl Format {
+    /// PPM for `.ppm` files, PGM for `.pgm` files, otherwise text.
+    ///
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ppm") => Format::Ppm,
+            Some("pgm") => Format::Pgm,
+            _ => Format::Ascii,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ascii => "txt",
+            Format::Ppm => "ppm",
+            Format::Pgm => "pgm",
+        }
+    }
+}
+
+/// The frame in `format`. Images draw each cell as a `scale` by `scale` square.
+///
+pub fn encode(frame: &Frame, format: Format, scale: usize) -> Vec<u8> {
+    let (magic, channels) = match format {
+        Format::Ascii => return frame.render(|&c| c).into_bytes(),
+        Format::Ppm => ("P6", 3),
+        Format::Pgm => ("P5", 1),
+    };
+    let (width, height) = (frame.n_cols() * scale, frame.n_rows() * scale);
+    let mut buf = format!("{}\n{} {}\n255\n", magic, width, height).into_bytes();
+    buf.reserve(width * height * channels);
+    for row in 0..frame.n_rows() {
+        let pixels = frame
+            .row(row)
+            .iter()
+            .flat_map(|&c| {
+                let [r, g, b] = colour(c);
+                let grey = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8;
+                let pixel = if channels == 3 {
+                    vec![r, g, b]
+                } else {
+                    vec![grey]
+                };
+                std::iter::repeat_n(pixel, scale).flatten()
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..scale {
+            buf.extend_from_slice(&pixels);
+        }
+    }
+    buf
+}
+
+/// Collects the frames of a simulation: every `every` steps, or only the final state if `every` is 0. The frames are
+/// handed to `sink` with the number of the step they show, counting from 1, so they are only drawn when wanted.
+///
+pub struct Frames<'a> {
+    every: usize,
+    steps: usize,
+    sink: &'a mut dyn FnMut(usize, &Frame),
+}
+
+impl<'a> Frames<'a> {
+    pub fn new(every: usize, sink: &'a mut dyn FnMut(usize, &Frame)) -> Frames<'a> {
+        Frames {
+            every,
+            steps: 0,
+            sink,
+        }
+    }
+
+    /// Marks the end of a step, drawing it if it is one of the frames to keep.
+    ///
+    pub fn step(&mut self, draw: impl FnOnce() -> Frame) {
+        self.steps += 1;
+        if self.keeps_last_step() {
+            (self.sink)(self.steps, &draw());
+        }
+    }
+
+    /// Draws the final state, unless the last step already was.
+    ///
+    pub fn finish(&mut self, draw: impl FnOnce() -> Frame) {
+        if !self.keeps_last_step() {
+            (self.sink)(self.steps, &draw());
+        }
+    }
+
+    fn keeps_last_step(&self) -> bool {
+        self.every > 0 && self.steps > 0 && self.steps.is_multiple_of(self.every)
+    }
+}
+
+/// The smallest frame that holds all of `coords`, as `(row, col)`, filled with `empty`, and a function that turns a
+/// coordinate into its position in it.
+///
+pub fn bounding_frame(
+    coords: impl IntoIterator<Item = (i32, i32)>,
+    empty: char,
+) -> (Frame, impl Fn((i32, i32)) -> Pos) {
+    let (mut min_row, mut max_row, mut min_col, mut max_col) = (0, -1, 0, -1);
+    for (i, (row, col)) in coords.into_iter().enumerate() {
+        if i == 0 {
+            (min_row, max_row, min_col, max_col) = (row, row, col, col);
+        }
+        (min_row, max_row) = (min_row.min(row), max_row.max(row));
+        (min_col, max_col) = (min_col.min(col), max_col.max(col));
+    }
+    let frame = Grid::new(
+        (max_row - min_row + 1) as usize,
+        (max_col - min_col + 1) as usize,
+        empty,
+    );
+    let pos =
+        move |(row, col): (i32, i32)| Pos::new((row - min_row) as usize, (col - min_col) as usize);
+    (frame, pos)
+}
+
+/// Runs a day's simulation for `part` on `input`, passing its frames on. Returns `None` for the days that can't be
+/// drawn.
+///
+pub fn draw(
+    day: u32,
+    part: u32,
+    input: &str,
+    frames: &mut Frames,
+) -> Option<Result<(), ParseError>> {
+    fn run<S: Solution>(input: &str, draw: impl FnOnce(&S::Input)) -> Result<(), ParseError> {
+        draw(&S::parse(input)?);
+        Ok(())
+    }
+
+    Some(match day {
+        9 => run::<day9::Day9>(input, |input| day9::draw(input, part, frames)),
+        14 => run::<day14::Day14>(input, |input| day14::draw(input, part, frames)),
+        17 => run::<day17::Day17>(input, |input| day17::draw(input, frames)),
+        22 => run::<day22::Day22>(input, |input| day22::draw(input, part, frames)),
+        23 => run::<day23::Day23>(input, |input| day23::draw(input, part, frames)),
+        24 => run::<day24::Day24>(input, |input| day24::draw(input, part, frames)),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bounding_frame, draw, encode, Format, Frame, Frames};
+    use crate::grid::{Grid, Pos};
+    use std::path::Path;
+
+    #[test]
+    fn test_encode() {
+        let frame = Grid::parse(1, "#.\n.o\n", "a cell", Some).unwrap();
+        assert_eq!(encode(&frame, Format::Ascii, 1), b"#.\n.o\n");
+
+        let ppm = encode(&frame, Format::Ppm, 2);
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+        assert_eq!(&ppm[11..17], &[200, 200, 200, 200, 200, 200]);
+
+        let pgm = encode(&frame, Format::Pgm, 1);
+        assert_eq!(pgm, b"P5\n2 2\n255\n\xc8\x20\x20\xbd");
+
+        assert_eq!(Format::from_path(Path::new("out/rope.pgm")), Format::Pgm);
+        assert_eq!(Format::from_path(Path::new("rope")), Format::Ascii);
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut steps = Vec::new();
+        let mut sink = |step: usize, frame: &Frame| steps.push((step, frame[Pos::new(0, 0)]));
+        let mut frames = Frames::new(2, &mut sink);
+        for c in ['a', 'b', 'c'] {
+            frames.step(|| Grid::new(1, 1, c));
+        }
+        frames.finish(|| Grid::new(1, 1, 'z'));
+        assert_eq!(steps, vec![(2, 'b'), (3, 'z')]);
+
+        let mut steps = Vec::new();
+        let mut sink = |step: usize, _: &Frame| steps.push(step);
+        let mut frames = Frames::new(0, &mut sink);
+        frames.step(|| panic!("only the final frame is drawn"));
+        frames.finish(|| Grid::new(1, 1, '.'));
+        assert_eq!(steps, vec![1]);
+    }
+
+    #[test]
+    fn test_bounding_frame() {
+        let (frame, pos) = bounding_frame([(-1, 2), (3, -2)], '.');
+        assert_eq!((frame.n_rows(), frame.n_cols()), (5, 5));
+        assert_eq!(pos((-1, 2)), Pos::new(0, 4));
+        assert_eq!(pos((3, -2)), Pos::new(4, 0));
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut last = None;
+        let mut sink = |_: usize, frame: &Frame| last = Some(frame.clone());
+        let mut frames = Frames::new(0, &mut sink);
+        assert!(draw(1, 1, "1\n", &mut frames).is_none());
+        assert!(draw(9, 1, "X 1\n", &mut frames).unwrap().is_err());
+        draw(9, 1, "R 2\n", &mut frames).unwrap().unwrap();
+        assert_eq!(last.unwrap().render(|&c| c), "sTH\n");
+    }
+}
@@ -0,0 +1,267 @@
+use crate::input::InputDir;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The module every new day starts from, written as day 0 so that it compiles: `Day0` and `DAY` stand for the day.
+///
+pub const TEMPLATE: &str = include_str!("template.rs");
+
+#[derive(Debug)]
+pub enum ScaffoldError {
+    /// The day already has a module, which is never overwritten.
+    Exists {
+        day: u32,
+        path: PathBuf,
+    },
+    /// The solutions in `path` have no empty entry for the day to go in.
+    Unregistered {
+        day: u32,
+        path: PathBuf,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaffoldError::Exists { day, path } => {
+                write!(f, "day {} already exists in {}", day, path.display())
+            }
+            ScaffoldError::Unregistered { day, path } => {
+                write!(f, "no empty entry for day {} in {}", day, path.display())
+            }
+            ScaffoldError::Io { path, error } => {
+                write!(f, "could not write {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScaffoldError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScaffoldError::Exists { .. } | ScaffoldError::Unregistered { .. } => None,
+            ScaffoldError::Io { error, .. } => Some(error),
+        }
+    }
+}
+
+/// The template filled in for `day`.
+///
+pub fn render(day: u32) -> String {
+    TEMPLATE
+        .replace("const DAY: u32 = 0;", &format!("const DAY: u32 = {};", day))
+        .replace("Day0", &format!("Day{}", day))
+}
+
+/// `lib_rs` with `pub mod day<day>;` added among the other modules, which are kept sorted by name. Returns `None` if
+/// the module is already declared.
+///
+pub fn register(lib_rs: &str, day: u32) -> Option<String> {
+    let line = format!("pub mod day{};", day);
+    if lib_rs.lines().any(|l| l.trim() == line) {
+        return None;
+    }
+
+    let lines = lib_rs.lines().collect::<Vec<_>>();
+    let modules = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.starts_with("pub mod "))
+        .collect::<Vec<_>>();
+    let at = match modules.iter().find(|(_, l)| **l > line.as_str()) {
+        Some(&(i, _)) => i,
+        None => modules.last().map_or(lines.len(), |&(i, _)| i + 1),
+    };
+
+    let mut buf = String::with_capacity(lib_rs.len() + line.len() + 1);
+    for (i, l) in lines.iter().enumerate() {
+        if i == at {
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+        buf.push_str(l);
+        buf.push('\n');
+    }
+    if at == lines.len() {
+        buf.push_str(&line);
+        buf.push('\n');
+    }
+    Some(buf)
+}
+
+/// `solution_rs` with the day's solution in its `None` entry of `SOLUTIONS`. Returns `None` if there is no such entry.
+///
+pub fn register_solution(solution_rs: &str, day: u32) -> Option<String> {
+    let start = solution_rs
+        .lines()
+        .position(|l| l.starts_with("const SOLUTIONS"))?;
+    let entry = start + day as usize;
+    let mut lines = solution_rs.lines().collect::<Vec<_>>();
+    if lines.get(entry)?.trim() != "None," {
+        return None;
+    }
+    let solution = format!("    Some(&day{}::Day{}),", day, day);
+    lines[entry] = &solution;
+    Some(lines.iter().map(|l| format!("{}\n", l)).collect())
+}
+
+/// Creates `src/day<day>.rs` in the crate at `crate_root` from the template, declares it in `src/lib.rs`, adds it to
+/// the solutions in `src/solution.rs`, and creates empty `day<day>.txt` and `day<day>_example.txt` files in
+/// `input_dir` unless they are already there. Nothing is written if the day already has a module or there is no empty
+/// entry for it among the solutions. Returns the files written.
+///
+pub fn create(
+    crate_root: &Path,
+    input_dir: &InputDir,
+    day: u32,
+) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let src = crate_root.join("src");
+    let module = src.join(format!("day{}.rs", day));
+    let lib = src.join("lib.rs");
+    let solutions = src.join("solution.rs");
+    if module.exists() {
+        return Err(ScaffoldError::Exists { day, path: module });
+    }
+    let read = |path: &PathBuf| {
+        fs::read_to_string(path).map_err(|error| ScaffoldError::Io {
+            path: path.clone(),
+            error,
+        })
+    };
+    let lib_rs = match register(&read(&lib)?, day) {
+        Some(lib_rs) => lib_rs,
+        None => return Err(ScaffoldError::Exists { day, path: lib }),
+    };
+    let solution_rs = match register_solution(&read(&solutions)?, day) {
+        Some(solution_rs) => solution_rs,
+        None => {
+            return Err(ScaffoldError::Unregistered {
+                day,
+                path: solutions,
+            })
+        }
+    };
+
+    let mut files = vec![
+        (module, render(day)),
+        (lib, lib_rs),
+        (solutions, solution_rs),
+    ];
+    for variant in [None, Some("example")] {
+        if input_dir.path(day, variant).is_err() {
+            files.push((input_dir.candidates(day, variant).remove(0), String::new()));
+        }
+    }
+
+    let mut written = Vec::new();
+    for (path, contents) in files {
+        let res = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(&path, contents)),
+            None => fs::write(&path, contents),
+        };
+        res.map_err(|error| ScaffoldError::Io {
+            path: path.clone(),
+            error,
+        })?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, register, register_solution, render, ScaffoldError};
+    use crate::input::InputDir;
+    use std::fs;
+
+    #[test]
+    fn test_render() {
+        let module = render(7);
+        assert!(!module.contains("Day0") && !module.contains("DAY: u32 = 0"));
+        assert!(module.contains("const DAY: u32 = 7;"));
+        assert!(module.contains("pub struct Day7;"));
+        assert!(module.contains("impl Solution for Day7 {"));
+    }
+
+    #[test]
+    fn test_register() {
+        let lib_rs =
+            "extern crate core;\n\npub mod day1;\npub mod day3;\npub mod grid;\nmod template;\n";
+        assert_eq!(
+            register(lib_rs, 2).unwrap(),
+            "extern crate core;\n\npub mod day1;\npub mod day2;\npub mod day3;\npub mod grid;\nmod template;\n"
+        );
+        assert_eq!(
+            register(lib_rs, 25).unwrap(),
+            "extern crate core;\n\npub mod day1;\npub mod day25;\npub mod day3;\npub mod grid;\nmod template;\n"
+        );
+        assert_eq!(register("", 1).unwrap(), "pub mod day1;\n");
+        assert_eq!(register(lib_rs, 3), None);
+    }
+
+    #[test]
+    fn test_register_solution() {
+        let solution_rs = "/// The solutions.\nconst SOLUTIONS: [Option<&dyn DynSolution>; 3] = [\n    Some(&day1::Day1),\n    None,\n    None,\n];\n";
+        assert_eq!(
+            register_solution(solution_rs, 2).unwrap(),
+            solution_rs.replacen("None", "Some(&day2::Day2)", 1)
+        );
+        assert_eq!(register_solution(solution_rs, 1), None);
+        assert_eq!(register_solution(solution_rs, 4), None);
+        assert_eq!(register_solution("", 1), None);
+    }
+
+    #[test]
+    fn test_create() {
+        let root = std::env::temp_dir().join(format!("aoc-scaffold-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod day1;\n").unwrap();
+        let solution_rs = "const SOLUTIONS: [Option<&dyn DynSolution>; 4] = [\n    Some(&day1::Day1),\n    None,\n    None,\n    None,\n];\n";
+        fs::write(root.join("src/solution.rs"), solution_rs).unwrap();
+        let input_dir = InputDir::new(root.join("input"));
+
+        let written = create(&root, &input_dir, 2).unwrap();
+        assert_eq!(written.len(), 5);
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "pub mod day1;\npub mod day2;\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("src/day2.rs")).unwrap(),
+            render(2)
+        );
+        assert!(fs::read_to_string(root.join("src/solution.rs"))
+            .unwrap()
+            .contains("    Some(&day2::Day2),\n    None,\n"));
+        assert_eq!(input_dir.read(2, None).unwrap(), "");
+        assert_eq!(input_dir.read(2, Some("example")).unwrap(), "");
+
+        // The day is there now, so nothing is touched the second time.
+        fs::write(root.join("input/day2.txt"), "1\n").unwrap();
+        let err = create(&root, &input_dir, 2).unwrap_err();
+        assert!(matches!(err, ScaffoldError::Exists { day: 2, .. }));
+        assert_eq!(input_dir.read(2, None).unwrap(), "1\n");
+
+        // An input that was downloaded first is kept.
+        let written = create(&root, &input_dir, 3).unwrap();
+        assert_eq!(written.len(), 5);
+        fs::write(root.join("input/day4.txt"), "4\n").unwrap();
+        let written = create(&root, &input_dir, 4).unwrap();
+        assert_eq!(written.len(), 4);
+        assert_eq!(input_dir.read(4, None).unwrap(), "4\n");
+
+        // There is nowhere among the solutions for day 5 to go.
+        let err = create(&root, &input_dir, 5).unwrap_err();
+        assert!(matches!(err, ScaffoldError::Unregistered { day: 5, .. }));
+        assert!(!root.join("src/day5.rs").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
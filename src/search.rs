@@ -0,0 +1,263 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// The cheapest way found to a goal: its total cost and the states along it, from a start to the goal.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Path<S> {
+    pub cost: usize,
+    pub states: Vec<S>,
+}
+
+impl<S> Path<S> {
+    /// The state the path ends in.
+    ///
+    pub fn goal(&self) -> &S {
+        self.states.last().unwrap()
+    }
+}
+
+/// The states seen so far, numbered in the order they were found, with the cost to reach them and the state they
+/// were reached from.
+///
+struct Seen<S> {
+    ids: HashMap<S, usize>,
+    states: Vec<(S, usize, Option<usize>)>,
+}
+
+impl<S: Clone + Eq + Hash> Seen<S> {
+    fn new() -> Seen<S> {
+        Seen {
+            ids: HashMap::new(),
+            states: Vec::new(),
+        }
+    }
+
+    /// Records a way to reach `state`, returning its id if it is new or cheaper than before.
+    ///
+    fn reach(&mut self, state: S, cost: usize, parent: Option<usize>) -> Option<usize> {
+        match self.ids.get(&state) {
+            Some(&id) if self.states[id].1 <= cost => None,
+            Some(&id) => {
+                self.states[id].1 = cost;
+                self.states[id].2 = parent;
+                Some(id)
+            }
+            None => {
+                let id = self.states.len();
+                self.ids.insert(state.clone(), id);
+                self.states.push((state, cost, parent));
+                Some(id)
+            }
+        }
+    }
+
+    fn path(&self, id: usize) -> Path<S> {
+        let mut states = Vec::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            states.push(self.states[id].0.clone());
+            next = self.states[id].2;
+        }
+        states.reverse();
+        Path {
+            cost: self.states[id].1,
+            states,
+        }
+    }
+}
+
+/// Breadth first search, for when every step costs 1. Starts from all of `starts` at once and stops at the first
+/// state that `is_goal` accepts.
+///
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = Seen::new();
+    let mut q = VecDeque::new();
+    for start in starts {
+        q.extend(seen.reach(start, 0, None));
+    }
+
+    while let Some(id) = q.pop_front() {
+        let (state, cost, _) = &seen.states[id];
+        if is_goal(state) {
+            return Some(seen.path(id));
+        }
+        let cost = cost + 1;
+        for next in neighbours(state) {
+            q.extend(seen.reach(next, cost, Some(id)));
+        }
+    }
+    None
+}
+
+/// The number of steps to every state reachable from `starts`, when every step costs 1.
+///
+pub fn bfs_distances<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: impl FnMut(&S) -> I,
+) -> HashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = HashMap::new();
+    let mut q = VecDeque::new();
+    for start in starts {
+        if !distances.contains_key(&start) {
+            distances.insert(start.clone(), 0);
+            q.push_back(start);
+        }
+    }
+
+    while let Some(state) = q.pop_front() {
+        let distance = distances[&state] + 1;
+        for next in neighbours(&state) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), distance);
+                q.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Dijkstra's algorithm, for steps with their own costs: `neighbours` gives each next state with the cost of getting
+/// there.
+///
+pub fn dijkstra<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    neighbours: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    astar(starts, neighbours, |_| 0, is_goal)
+}
+
+/// A* search: Dijkstra's algorithm trying the states that look closest to a goal first. The `heuristic` must never
+/// overestimate the remaining cost for the path found to be the cheapest.
+///
+pub fn astar<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> usize,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    let mut seen = Seen::new();
+    let mut q = BinaryHeap::new();
+    for start in starts {
+        if let Some(id) = seen.reach(start.clone(), 0, None) {
+            q.push((Reverse(heuristic(&start)), Reverse(0), id));
+        }
+    }
+
+    while let Some((_, Reverse(cost), id)) = q.pop() {
+        let state = &seen.states[id].0;
+        if cost > seen.states[id].1 {
+            // Already reached more cheaply.
+            continue;
+        }
+        if is_goal(state) {
+            return Some(seen.path(id));
+        }
+        for (next, step) in neighbours(state) {
+            let next_cost = cost + step;
+            let estimate = next_cost + heuristic(&next);
+            if let Some(next_id) = seen.reach(next, next_cost, Some(id)) {
+                q.push((Reverse(estimate), Reverse(next_cost), next_id));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{astar, bfs, bfs_distances, dijkstra};
+    use crate::grid::{Grid, Pos};
+
+    const MAZE: &str = "S.#.....
+.##.###.
+....#...
+.####.#E
+";
+
+    fn maze() -> Grid<char> {
+        Grid::parse(1, MAZE, "a maze tile", Some).unwrap()
+    }
+
+    fn find(maze: &Grid<char>, tile: char) -> Pos {
+        maze.iter().find(|&(_, &c)| c == tile).unwrap().0
+    }
+
+    #[test]
+    fn test_bfs() {
+        let maze = maze();
+        let end = find(&maze, 'E');
+        let neighbours = |&pos: &Pos| {
+            maze.neighbours4(pos)
+                .filter(|&p| maze[p] != '#')
+                .collect::<Vec<_>>()
+        };
+        let path = bfs([find(&maze, 'S')], neighbours, |&pos| pos == end).unwrap();
+        assert_eq!(path.cost, 14);
+        assert_eq!(path.states.len(), 15);
+        assert_eq!(path.states[0], Pos::new(0, 0));
+        assert_eq!(*path.goal(), end);
+        assert!(path
+            .states
+            .windows(2)
+            .all(|w| maze.neighbours4(w[0]).any(|p| p == w[1])));
+
+        // Starting from the open tile in the bottom row as well, the path comes from there.
+        let starts = [find(&maze, 'S'), Pos::new(3, 5)];
+        let path = bfs(starts, neighbours, |&pos| pos == end).unwrap();
+        assert_eq!((path.cost, path.states[0]), (4, Pos::new(3, 5)));
+
+        let distances = bfs_distances([end], neighbours);
+        assert_eq!(distances[&Pos::new(0, 0)], 14);
+        assert_eq!(
+            distances.len(),
+            maze.iter().filter(|&(_, &c)| c != '#').count()
+        );
+
+        assert_eq!(
+            bfs([end], |_| Vec::new(), |&pos| pos == Pos::new(0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_weighted() {
+        // Going through a `.` costs 1 and through a `#` costs 10.
+        let maze = maze();
+        let (start, end) = (find(&maze, 'S'), find(&maze, 'E'));
+        let neighbours = |&pos: &Pos| {
+            maze.neighbours4(pos)
+                .map(|p| (p, if maze[p] == '#' { 10 } else { 1 }))
+                .collect::<Vec<_>>()
+        };
+        let path = dijkstra([start], neighbours, |&pos| pos == end).unwrap();
+        assert_eq!(path.cost, 14);
+
+        let manhattan = |pos: &Pos| end.row.abs_diff(pos.row) + end.col.abs_diff(pos.col);
+        let astar_path = astar([start], neighbours, manhattan, |&pos| pos == end).unwrap();
+        assert_eq!(astar_path.cost, path.cost);
+        assert_eq!(*astar_path.goal(), end);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::parse::ParseError;
+use crate::*;
+use std::any::Any;
+use std::fmt;
+
+/// The answer to one part of a puzzle. Most answers are numbers, a few are strings of letters (day 5 crates, the
+/// day 10 CRT, day 25 SNAFU numbers). Day 25 has no second puzzle, so its part two answer is `None`. An input that
+/// parses but has no answer, like a day 22 board that doesn't fold into a cube, is `Unsolvable` with the reason.
+///
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    None,
+    Unsolvable(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(v) => write!(f, "{}", v),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::None => Ok(()),
+            Answer::Unsolvable(reason) => write!(f, "no answer: {}", reason),
+        }
+    }
+}
+
+/// Numbers too big for an `i64` are written out as text instead of wrapping round.
+///
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(v: $t) -> Self {
+                    i64::try_from(v).map_or_else(|_| Answer::Text(v.to_string()), Answer::Number)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, i64, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+/// A day's puzzle: the input is parsed once and both parts are solved from the parsed input.
+///
+pub trait Solution {
+    type Input;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+
+    fn part1(input: &Self::Input) -> Answer;
+
+    fn part2(input: &Self::Input) -> Answer;
+}
+
+/// Object-safe view of a `Solution`, so all the days can be kept in one registry. The parsed input is passed around
+/// as `dyn Any` and must come from the same solution's `parse`.
+///
+pub trait DynSolution {
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+
+    fn part1(&self, input: &dyn Any) -> Answer;
+
+    fn part2(&self, input: &dyn Any) -> Answer;
+}
+
+impl<S> DynSolution for S
+where
+    S: Solution,
+    S::Input: 'static,
+{
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn part1(&self, input: &dyn Any) -> Answer {
+        S::part1(
+            input
+                .downcast_ref()
+                .expect("input parsed by another solution"),
+        )
+    }
+
+    fn part2(&self, input: &dyn Any) -> Answer {
+        S::part2(
+            input
+                .downcast_ref()
+                .expect("input parsed by another solution"),
+        )
+    }
+}
+
+/// The solutions indexed by day - 1. A day scaffolded by `aoc new` takes the place of its `None`.
+///
+const SOLUTIONS: [Option<&dyn DynSolution>; 25] = [
+    Some(&day1::Day1),
+    Some(&day2::Day2),
+    Some(&day3::Day3),
+    Some(&day4::Day4),
+    Some(&day5::Day5),
+    Some(&day6::Day6),
+    Some(&day7::Day7),
+    Some(&day8::Day8),
+    Some(&day9::Day9),
+    Some(&day10::Day10),
+    Some(&day11::Day11),
+    Some(&day12::Day12),
+    Some(&day13::Day13),
+    Some(&day14::Day14),
+    Some(&day15::Day15),
+    Some(&day16::Day16),
+    Some(&day17::Day17),
+    Some(&day18::Day18),
+    Some(&day19::Day19),
+    Some(&day20::Day20),
+    Some(&day21::Day21),
+    Some(&day22::Day22),
+    Some(&day23::Day23),
+    Some(&day24::Day24),
+    Some(&day25::Day25),
+];
+
+/// The solution for a day, if there is one.
+///
+pub fn get(day: u32) -> Option<&'static dyn DynSolution> {
+    match day {
+        1..=25 => SOLUTIONS[day as usize - 1],
+        _ => None,
+    }
+}
+
+/// All the available solutions with their day numbers, in order.
+///
+pub fn all() -> impl Iterator<Item = (u32, &'static dyn DynSolution)> {
+    (1..=25).filter_map(|day| get(day).map(|solution| (day, solution)))
+}
+
+/// Splits puzzle text into owned lines, the form most of the day parsers take.
+///
+pub fn lines(input: &str) -> impl Iterator<Item = String> + '_ {
+    input.lines().map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all, get, Answer};
+
+    const DAY1_EXAMPLE: &str = "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000
+";
+
+    #[test]
+    fn test_get() {
+        let solution = get(1).unwrap();
+        let input = solution.parse(DAY1_EXAMPLE).unwrap();
+        assert_eq!(solution.part1(input.as_ref()), Answer::Number(24000));
+        assert_eq!(solution.part2(input.as_ref()), Answer::Number(45000));
+
+        assert!(get(0).is_none());
+        assert!(get(26).is_none());
+    }
+
+    #[test]
+    fn test_all() {
+        let days = all().map(|(day, _)| day).collect::<Vec<_>>();
+        assert_eq!(days.first(), Some(&1));
+        assert_eq!(days.last(), Some(&25));
+        assert!(days.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_answer_display() {
+        assert_eq!(Answer::from(42_u64).to_string(), "42");
+        // Too big for a `Number`, but the same when written out.
+        assert_eq!(Answer::from(u64::MAX), Answer::from(u64::MAX.to_string()));
+        assert_eq!(Answer::from("CMZ").to_string(), "CMZ");
+        assert_eq!(Answer::None.to_string(), "");
+        assert_eq!(
+            Answer::Unsolvable("no cube".to_string()).to_string(),
+            "no answer: no cube"
+        );
+    }
+}
@@ -1,46 +1,67 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::parse::{parse_lines, ParseError};
+use crate::solution::{Answer, Solution};
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/dayX.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap())
+const DAY: u32 = 0;
+
+fn parse_input(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_lines(DAY, input, |cursor| {
+        Ok(cursor.take_while(|_| true).to_string())
+    })
 }
 
-fn part1(_input: impl Iterator<Item = String>) -> u32 {
+pub fn part1(_input: &[String]) -> u32 {
     unimplemented!()
 }
 
-fn part2(_input: impl Iterator<Item = String>) -> u32 {
+pub fn part2(_input: &[String]) -> u32 {
     unimplemented!()
 }
 
+pub struct Day0;
+
+impl Solution for Day0 {
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, read_file};
-
-    const EXAMPLE: &str = "
-";
+    use super::{parse_input, part1, part2, DAY};
+    use crate::input::{read, read_variant};
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE.lines().map(|v| v.to_string())), 0);
+        let input = parse_input(&read_variant(DAY, "example").unwrap()).unwrap();
+        assert_eq!(part1(&input), 0);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let res = part1(&parse_input(&read(DAY).unwrap()).unwrap());
         println!("{}", res);
         // assert_eq!(res, 0);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|v| v.to_string())), 0);
+        let input = parse_input(&read_variant(DAY, "example").unwrap()).unwrap();
+        assert_eq!(part2(&input), 0);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file());
+        let res = part2(&parse_input(&read(DAY).unwrap()).unwrap());
         println!("{}", res);
         // assert_eq!(res, 0);
     }